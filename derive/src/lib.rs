@@ -0,0 +1,391 @@
+//! Derive macro for `bevy_common_assets`
+//!
+//! Use the macro through the `derive` feature of `bevy_common_assets`.
+
+#![forbid(unsafe_code)]
+#![warn(unused_imports, missing_docs)]
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{format_ident, quote};
+use syn::{Data, DeriveInput, Fields, Ident, Index, LitStr, parse_macro_input};
+
+/// The formats that can be loaded, named like the attribute keys and features
+const FORMATS: &[&str] = &[
+    "json", "ron", "toml", "yaml", "msgpack", "xml", "postcard", "cbor",
+];
+
+/// The formats that have an asset saver
+const SAVERS: &[&str] = &["postcard", "cbor"];
+
+/// Derive `Asset`, `TypePath` and `CommonAsset` for a type loaded from common file formats.
+///
+/// Fields marked with `#[dependency]` are dependencies of the asset, like with Bevy's `Asset`
+/// derive. The formats and their file extensions are configured with the `common_asset` attribute.
+/// Every format can be given multiple times for several extensions. `saver` registers asset
+/// processors saving the asset from all configured formats. `validate = "fail"` or
+/// `validate = "warn"` checks loaded assets with their `ValidateAsset` implementation.
+///
+/// ```ignore
+/// #[derive(serde::Deserialize, serde::Serialize, CommonAsset)]
+/// #[common_asset(json = "level.json", ron = "level.ron", saver = "postcard")]
+/// struct Level {
+///     positions: Vec<[f32; 3]>,
+/// }
+///
+/// App::new().add_common_asset::<Level>();
+/// ```
+#[proc_macro_derive(CommonAsset, attributes(common_asset, dependency))]
+pub fn derive_common_asset(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &input.generics,
+            "`CommonAsset` can not be derived for generic types",
+        ));
+    }
+    let mut formats: Vec<(Ident, LitStr)> = vec![];
+    let mut savers: Vec<Ident> = vec![];
+    let mut validate: Option<Ident> = None;
+    for attribute in input
+        .attrs
+        .iter()
+        .filter(|attribute| attribute.path().is_ident("common_asset"))
+    {
+        attribute.parse_nested_meta(|meta| {
+            let Some(key) = meta.path.get_ident().cloned() else {
+                return Err(meta.error("expected a format, `saver` or `validate`"));
+            };
+            let value: LitStr = meta.value()?.parse()?;
+            if key == "saver" {
+                if !SAVERS.contains(&value.value().as_str()) {
+                    return Err(syn::Error::new_spanned(
+                        &value,
+                        format!("unknown saver, expected one of {}", SAVERS.join(", ")),
+                    ));
+                }
+                savers.push(Ident::new(&value.value(), value.span()));
+            } else if key == "validate" {
+                let mode = match value.value().as_str() {
+                    "fail" => "Fail",
+                    "warn" => "Warn",
+                    _ => {
+                        return Err(syn::Error::new_spanned(
+                            &value,
+                            "unknown validation mode, expected `fail` or `warn`",
+                        ));
+                    }
+                };
+                validate = Some(Ident::new(mode, value.span()));
+            } else if FORMATS.contains(&key.to_string().as_str()) {
+                formats.push((key, value));
+            } else {
+                return Err(meta.error(format!(
+                    "unknown format, expected one of {}, `saver` or `validate`",
+                    FORMATS.join(", ")
+                )));
+            }
+            Ok(())
+        })?;
+    }
+    if formats.is_empty() {
+        return Err(syn::Error::new(
+            Span::call_site(),
+            "`CommonAsset` needs at least one format, e.g. `#[common_asset(json = \"level.json\")]`",
+        ));
+    }
+
+    let ident = &input.ident;
+    let name = ident.to_string();
+    let (dependencies, visited) = dependencies(input)?;
+    let visit = if visited {
+        format_ident!("visit")
+    } else {
+        format_ident!("_visit")
+    };
+    let formats = formats.iter().map(|(format, extension)| {
+        let add_format = format_ident!("__common_asset_{}", format, span = format.span());
+        quote! {
+            let plugin = ::bevy_common_assets::#add_format!(plugin, #extension);
+        }
+    });
+    let savers = savers.iter().map(|format| {
+        let add_saver = format_ident!("__common_asset_{}_saver", format, span = format.span());
+        quote! {
+            let plugin = ::bevy_common_assets::#add_saver!(plugin);
+        }
+    });
+
+    let validate = validate.map(|mode| {
+        quote! {
+            let plugin = plugin.with_asset_validation(::bevy_common_assets::ValidationMode::#mode);
+        }
+    });
+
+    Ok(quote! {
+        impl ::bevy_common_assets::__private::bevy_reflect::TypePath for #ident {
+            fn type_path() -> &'static str {
+                ::core::concat!(::core::module_path!(), "::", #name)
+            }
+
+            fn short_type_path() -> &'static str {
+                #name
+            }
+
+            fn type_ident() -> ::core::option::Option<&'static str> {
+                ::core::option::Option::Some(#name)
+            }
+
+            fn crate_name() -> ::core::option::Option<&'static str> {
+                ::core::module_path!().split(':').next()
+            }
+
+            fn module_path() -> ::core::option::Option<&'static str> {
+                ::core::option::Option::Some(::core::module_path!())
+            }
+        }
+
+        impl ::bevy_common_assets::__private::bevy_asset::VisitAssetDependencies for #ident {
+            fn visit_dependencies(
+                &self,
+                #visit: &mut impl ::core::ops::FnMut(
+                    ::bevy_common_assets::__private::bevy_asset::UntypedAssetId,
+                ),
+            ) {
+                #dependencies
+            }
+        }
+
+        impl ::bevy_common_assets::__private::bevy_asset::Asset for #ident {}
+
+        impl ::bevy_common_assets::CommonAsset for #ident {
+            fn common_assets_plugin() -> ::bevy_common_assets::CommonAssetsPlugin<Self> {
+                let plugin = ::bevy_common_assets::CommonAssetsPlugin::<Self>::new();
+                #(#formats)*
+                #(#savers)*
+                #validate
+                plugin
+            }
+        }
+    })
+}
+
+/// Visit the fields marked with `#[dependency]` in `visit_dependencies`, and whether there are any
+fn dependencies(input: &DeriveInput) -> syn::Result<(TokenStream2, bool)> {
+    match &input.data {
+        Data::Struct(data) => {
+            let (pattern, visits) = visit_fields(&data.fields);
+            let visited = !visits.is_empty();
+            Ok((
+                quote! {
+                    let Self #pattern = self;
+                    #(#visits)*
+                },
+                visited,
+            ))
+        }
+        Data::Enum(data) if data.variants.is_empty() => Ok((quote!(match *self {}), false)),
+        Data::Enum(data) => {
+            let mut visited = false;
+            let variants: Vec<_> = data
+                .variants
+                .iter()
+                .map(|variant| {
+                    let ident = &variant.ident;
+                    let (pattern, visits) = visit_fields(&variant.fields);
+                    visited |= !visits.is_empty();
+                    quote! {
+                        Self::#ident #pattern => {
+                            #(#visits)*
+                        }
+                    }
+                })
+                .collect();
+            Ok((
+                quote! {
+                    match self {
+                        #(#variants)*
+                    }
+                },
+                visited,
+            ))
+        }
+        Data::Union(data) => Err(syn::Error::new_spanned(
+            data.union_token,
+            "`CommonAsset` can not be derived for unions",
+        )),
+    }
+}
+
+/// A pattern binding the dependencies in `fields` and the visits of the bound dependencies
+fn visit_fields(fields: &Fields) -> (TokenStream2, Vec<TokenStream2>) {
+    let dependencies: Vec<_> = fields
+        .iter()
+        .enumerate()
+        .filter(|(_, field)| {
+            field
+                .attrs
+                .iter()
+                .any(|attribute| attribute.path().is_ident("dependency"))
+        })
+        .map(|(index, field)| {
+            let binding = format_ident!("dependency_{}", index);
+            let member = match &field.ident {
+                Some(ident) => quote!(#ident),
+                None => {
+                    let index = Index::from(index);
+                    quote!(#index)
+                }
+            };
+            (member, binding)
+        })
+        .collect();
+    let bindings = dependencies
+        .iter()
+        .map(|(member, binding)| quote!(#member: #binding));
+    let pattern = match fields {
+        Fields::Unit => quote!(),
+        _ => quote!({ #(#bindings,)* .. }),
+    };
+    let visits = dependencies
+        .iter()
+        .map(|(_, binding)| {
+            quote! {
+                ::bevy_common_assets::__private::bevy_asset::VisitAssetDependencies::visit_dependencies(#binding, visit);
+            }
+        })
+        .collect();
+    (pattern, visits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand_str(input: &str) -> syn::Result<String> {
+        expand(&syn::parse_str(input).unwrap()).map(|tokens| tokens.to_string())
+    }
+
+    fn error(input: &str) -> String {
+        expand_str(input).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn formats_savers_and_validation_are_added_to_the_plugin() {
+        let expanded = expand_str(
+            r#"
+            #[common_asset(json = "level.json", ron = "level.ron", saver = "postcard")]
+            #[common_asset(json = "lvl.json", validate = "warn")]
+            struct Level;
+            "#,
+        )
+        .unwrap();
+        let plugin = quote! {
+            impl ::bevy_common_assets::CommonAsset for Level {
+                fn common_assets_plugin() -> ::bevy_common_assets::CommonAssetsPlugin<Self> {
+                    let plugin = ::bevy_common_assets::CommonAssetsPlugin::<Self>::new();
+                    let plugin = ::bevy_common_assets::__common_asset_json!(plugin, "level.json");
+                    let plugin = ::bevy_common_assets::__common_asset_ron!(plugin, "level.ron");
+                    let plugin = ::bevy_common_assets::__common_asset_json!(plugin, "lvl.json");
+                    let plugin = ::bevy_common_assets::__common_asset_postcard_saver!(plugin);
+                    let plugin = plugin.with_asset_validation(::bevy_common_assets::ValidationMode::Warn);
+                    plugin
+                }
+            }
+        };
+        assert!(expanded.ends_with(&plugin.to_string()), "{expanded}");
+    }
+
+    #[test]
+    fn asset_and_type_path_are_derived() {
+        let expanded = expand_str(r#"#[common_asset(json = "json")] struct Level;"#).unwrap();
+        let type_path = quote! {
+            impl ::bevy_common_assets::__private::bevy_reflect::TypePath for Level
+        };
+        let asset = quote! {
+            impl ::bevy_common_assets::__private::bevy_asset::Asset for Level {}
+        };
+        let short_type_path = quote! {
+            fn short_type_path() -> &'static str {
+                "Level"
+            }
+        };
+        for part in [type_path, asset, short_type_path] {
+            assert!(expanded.contains(&part.to_string()), "{expanded}");
+        }
+    }
+
+    #[test]
+    fn dependencies_are_visited() {
+        let visit = |binding: &str| {
+            let binding = format_ident!("{}", binding);
+            quote! {
+                ::bevy_common_assets::__private::bevy_asset::VisitAssetDependencies::visit_dependencies(#binding, visit);
+            }
+            .to_string()
+        };
+        let expanded = expand_str(
+            r#"
+            #[common_asset(json = "json")]
+            struct Level {
+                name: String,
+                #[dependency]
+                tree: Handle<Image>,
+            }
+            "#,
+        )
+        .unwrap();
+        assert!(
+            expanded.contains(&quote!(let Self { tree: dependency_1, .. } = self;).to_string())
+        );
+        assert!(expanded.contains(&visit("dependency_1")), "{expanded}");
+
+        let expanded = expand_str(
+            r#"
+            #[common_asset(json = "json")]
+            enum Level {
+                Empty,
+                Trees(u32, #[dependency] Vec<Handle<Image>>),
+            }
+            "#,
+        )
+        .unwrap();
+        assert!(expanded.contains(&quote!(Self::Empty => {}).to_string()));
+        assert!(expanded.contains(&quote!(Self::Trees { 1: dependency_1, .. } => ).to_string()));
+        assert!(expanded.contains(&visit("dependency_1")), "{expanded}");
+    }
+
+    #[test]
+    fn the_visitor_is_unused_without_dependencies() {
+        let expanded = expand_str(r#"#[common_asset(json = "json")] struct Level(u32);"#).unwrap();
+        assert!(expanded.contains(&quote!(_visit: &mut impl).to_string()));
+        assert!(!expanded.contains("VisitAssetDependencies :: visit_dependencies"));
+    }
+
+    #[test]
+    fn invalid_attributes_are_errors() {
+        assert!(error("struct Level;").contains("needs at least one format"));
+        assert!(error(r#"#[common_asset(jsn = "json")] struct Level;"#).contains("unknown format"));
+        assert!(
+            error(r#"#[common_asset(saver = "json")] struct Level;"#).contains("unknown saver")
+        );
+        assert!(
+            error(r#"#[common_asset(json = "json", validate = "maybe")] struct Level;"#)
+                .contains("unknown validation mode")
+        );
+        assert!(
+            error(r#"#[common_asset(json = "json")] struct Level<T>(T);"#)
+                .contains("generic types")
+        );
+        assert!(
+            error(r#"#[common_asset(json = "json")] union Level { a: u32 }"#).contains("unions")
+        );
+    }
+}
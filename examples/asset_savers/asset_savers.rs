@@ -1,6 +1,7 @@
 use bevy::asset::processor::LoadTransformAndSave;
 use bevy::asset::transformer::IdentityAssetTransformer;
 use bevy::prelude::*;
+use bevy_common_assets::change::DataAssetChanged;
 use bevy_common_assets::json::{JsonAssetLoader, JsonAssetPlugin};
 use bevy_common_assets::postcard::{PostcardAssetPlugin, PostcardAssetSaver};
 use serde::{Deserialize, Serialize};
@@ -20,7 +21,7 @@ fn main() {
                 processed_file_path: "examples/asset_savers/imported_assets/Default".to_string(),
                 ..default()
             }),
-            PostcardAssetPlugin::<Level>::new(&["level"]),
+            PostcardAssetPlugin::<Level>::new(&["level"]).with_change_events(),
             JsonAssetPlugin::<Level>::new(&[]),
         ))
         .register_asset_processor::<LoadTransformAndSave<
@@ -54,50 +55,75 @@ fn spawn_level(
     mut state: ResMut<NextState<AppState>>,
 ) {
     if let Some(level) = levels.get(level.0.id()) {
-        for position in level.positions.iter() {
-            commands.spawn((
-                Sprite::from_image(tree.0.clone()),
-                Transform::from_translation((*position).into()),
-            ));
+        for (index, position) in level.positions.iter().enumerate() {
+            spawn_tree(&mut commands, &tree, index, *position);
         }
         state.set(AppState::Level);
     }
 }
 
+fn spawn_tree(commands: &mut Commands, tree: &ImageHandle, index: usize, position: [f32; 3]) {
+    commands.spawn((
+        Tree(index),
+        Sprite::from_image(tree.0.clone()),
+        Transform::from_translation(position.into()),
+    ));
+}
+
 // for development is is helpful to react to changes in asset files
-// The processed asset will automatically be updated when the source asset changes,
-// this system will then react and respawn the level
-// Try it out and edit a tree position in `assets/trees.level
+// The processed asset will automatically be updated when the source asset changes.
+// `with_change_events` on the plugin sends a `DataAssetChanged` message with the changed
+// fields, so this system only moves, spawns or despawns the trees whose positions changed.
+// Try it out and edit a tree position in `assets/trees.level`
 fn update_level(
     mut commands: Commands,
-    mut asset_event: MessageReader<AssetEvent<Level>>,
+    mut changes: MessageReader<DataAssetChanged<Level>>,
     level: Res<LevelHandle>,
     levels: Res<Assets<Level>>,
-    trees: Query<Entity, With<Sprite>>,
+    mut trees: Query<(Entity, &Tree, &mut Transform)>,
     tree: Res<ImageHandle>,
 ) {
-    for event in asset_event.read() {
-        if let AssetEvent::Modified { id } = event
-            && id == &level.0.id()
-        {
-            trees
-                .iter()
-                .for_each(|tree| commands.entity(tree).despawn());
-            for position in levels
-                .get(level.0.id())
-                .expect("Level missing after asset update event")
-                .positions
-                .iter()
-            {
-                commands.spawn((
-                    Sprite::from_image(tree.0.clone()),
-                    Transform::from_translation((*position).into()),
-                ));
+    for changed in changes.read() {
+        if changed.id != level.0.id() {
+            continue;
+        }
+        let positions = &levels
+            .get(level.0.id())
+            .expect("Level missing after asset update event")
+            .positions;
+        for change in &changed.changes {
+            let segments: Vec<_> = change.segments().collect();
+            let index = match segments.as_slice() {
+                [field, index, ..] if field == "positions" => index.parse::<usize>().ok(),
+                _ => None,
+            };
+            let Some(index) = index else {
+                // the whole list was replaced
+                trees
+                    .iter()
+                    .for_each(|(entity, _, _)| commands.entity(entity).despawn());
+                for (index, position) in positions.iter().enumerate() {
+                    spawn_tree(&mut commands, &tree, index, *position);
+                }
+                break;
+            };
+            let existing = trees.iter_mut().find(|(_, tree, _)| tree.0 == index);
+            match (existing, positions.get(index)) {
+                (Some((_, _, mut transform)), Some(position)) => {
+                    transform.translation = (*position).into();
+                }
+                (Some((entity, _, _)), None) => commands.entity(entity).despawn(),
+                (None, Some(position)) => spawn_tree(&mut commands, &tree, index, *position),
+                (None, None) => {}
             }
         }
     }
 }
 
+/// A tree sprite showing the position with this index in the level
+#[derive(Component)]
+struct Tree(usize);
+
 #[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash, States)]
 enum AppState {
     #[default]
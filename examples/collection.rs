@@ -0,0 +1,78 @@
+use bevy::prelude::*;
+use bevy::reflect::TypePath;
+use bevy_common_assets::collection::LoadedVec;
+use bevy_common_assets::json::JsonAssetPlugin;
+
+fn main() {
+    App::new()
+        .add_plugins((
+            DefaultPlugins,
+            // The file contains a json array of levels
+            JsonAssetPlugin::<LoadedVec<Level>>::new(&["levels.json"]),
+        ))
+        .init_state::<AppState>()
+        .add_systems(Startup, setup)
+        .add_systems(Update, spawn_levels.run_if(in_state(AppState::Loading)))
+        .run();
+}
+
+fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let levels = LevelsHandle(asset_server.load("trees.levels.json"));
+    commands.insert_resource(levels);
+    // Every item of the collection is a labeled sub-asset and can be loaded on its own
+    let second_level = LevelHandle(asset_server.load("trees.levels.json#1"));
+    commands.insert_resource(second_level);
+    let tree = ImageHandle(asset_server.load("tree.png"));
+    commands.insert_resource(tree);
+
+    commands.spawn((Camera2d, Msaa::Off));
+}
+
+fn spawn_levels(
+    mut commands: Commands,
+    levels_handle: Res<LevelsHandle>,
+    second_level: Res<LevelHandle>,
+    tree: Res<ImageHandle>,
+    loaded_levels: Res<Assets<LoadedVec<Level>>>,
+    levels: Res<Assets<Level>>,
+    mut state: ResMut<NextState<AppState>>,
+) {
+    if let Some(loaded_levels) = loaded_levels.get(&levels_handle.0) {
+        info!(
+            "Loaded {} levels; the second level has {} trees",
+            loaded_levels.items.len(),
+            levels.get(&second_level.0).unwrap().positions.len()
+        );
+        for level in loaded_levels.items.iter() {
+            for position in levels.get(level).unwrap().positions.iter() {
+                commands.spawn((
+                    Sprite::from_image(tree.0.clone()),
+                    Transform::from_translation((*position).into()),
+                ));
+            }
+        }
+
+        state.set(AppState::Level);
+    }
+}
+
+#[derive(serde::Deserialize, Asset, TypePath)]
+struct Level {
+    positions: Vec<[f32; 3]>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash, States)]
+enum AppState {
+    #[default]
+    Loading,
+    Level,
+}
+
+#[derive(Resource)]
+struct ImageHandle(Handle<Image>);
+
+#[derive(Resource)]
+struct LevelsHandle(Handle<LoadedVec<Level>>);
+
+#[derive(Resource)]
+struct LevelHandle(Handle<Level>);
@@ -0,0 +1,88 @@
+use bevy::asset::LoadContext;
+use bevy::prelude::*;
+use bevy_common_assets::{CommonAsset, CommonAssetApp, DataAsset, DefinitionError};
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        // registers the loaders configured in the `common_asset` attribute of `Level`
+        .add_common_asset::<Level>()
+        .init_state::<AppState>()
+        .add_systems(Startup, setup)
+        .add_systems(Update, spawn_level.run_if(in_state(AppState::Loading)))
+        .run();
+}
+
+fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let level = LevelHandle(asset_server.load("trees.level.ron"));
+    commands.insert_resource(level);
+
+    commands.spawn((Camera2d, Msaa::Off));
+}
+
+fn spawn_level(
+    mut commands: Commands,
+    level: Res<LevelHandle>,
+    asset_server: Res<AssetServer>,
+    mut levels: ResMut<Assets<Level>>,
+    mut state: ResMut<NextState<AppState>>,
+) {
+    // The tree image is a dependency of the level, so it is loaded as well
+    if !asset_server.is_loaded_with_dependencies(&level.0) {
+        return;
+    }
+    if let Some(level) = levels.remove(level.0.id()) {
+        for position in level.positions {
+            commands.spawn((
+                Sprite::from_image(level.tree.clone()),
+                Transform::from_translation(position.into()),
+            ));
+        }
+
+        state.set(AppState::Level);
+    }
+}
+
+#[derive(CommonAsset)]
+#[common_asset(json = "level.json", ron = "level.ron")]
+struct Level {
+    positions: Vec<[f32; 3]>,
+    #[dependency]
+    tree: Handle<Image>,
+}
+
+/// The level as written in the files, with the path of the tree image
+#[derive(serde::Deserialize)]
+struct LevelDefinition {
+    positions: Vec<[f32; 3]>,
+    #[serde(default = "default_tree")]
+    tree: String,
+}
+
+fn default_tree() -> String {
+    "tree.png".to_owned()
+}
+
+impl DataAsset for Level {
+    type Definition = LevelDefinition;
+
+    async fn from_definition(
+        definition: LevelDefinition,
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<Self, DefinitionError> {
+        Ok(Level {
+            positions: definition.positions,
+            tree: load_context.load(definition.tree),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash, States)]
+enum AppState {
+    #[default]
+    Loading,
+    Level,
+}
+
+#[derive(Resource)]
+struct LevelHandle(Handle<Level>);
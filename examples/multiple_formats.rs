@@ -1,7 +1,6 @@
 use bevy::prelude::*;
 use bevy::reflect::TypePath;
-use bevy_common_assets::json::JsonAssetPlugin;
-use bevy_common_assets::ron::RonAssetPlugin;
+use bevy_common_assets::CommonAssetsPlugin;
 
 fn main() {
     App::new()
@@ -9,8 +8,9 @@ fn main() {
         // The important thing is: they all need distinct extensions!
         .add_plugins((
             DefaultPlugins,
-            RonAssetPlugin::<Level>::new(&["level.ron"]),
-            JsonAssetPlugin::<Level>::new(&["level.json"]),
+            CommonAssetsPlugin::<Level>::new()
+                .ron(&["level.ron"])
+                .json(&["level.json"]),
         ))
         .init_state::<AppState>()
         .add_systems(Startup, setup)
@@ -0,0 +1,77 @@
+use bevy::prelude::*;
+use bevy::reflect::TypePath;
+use bevy_common_assets::yaml::{LoadedYamlDocuments, YamlDocumentsAssetPlugin};
+
+fn main() {
+    App::new()
+        .add_plugins((
+            DefaultPlugins,
+            // Every document of the file is labeled by its `name` field
+            YamlDocumentsAssetPlugin::<Level>::new(&["levels.yaml"]).with_label_field("name"),
+        ))
+        .init_state::<AppState>()
+        .add_systems(Startup, setup)
+        .add_systems(Update, spawn_level.run_if(in_state(AppState::Loading)))
+        .run();
+}
+
+fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let levels = LevelsHandle(asset_server.load("trees.levels.yaml"));
+    commands.insert_resource(levels);
+    // Single documents can be loaded by their label
+    let clearing = LevelHandle(asset_server.load("trees.levels.yaml#clearing"));
+    commands.insert_resource(clearing);
+    let tree = ImageHandle(asset_server.load("tree.png"));
+    commands.insert_resource(tree);
+
+    commands.spawn((Camera2d, Msaa::Off));
+}
+
+fn spawn_level(
+    mut commands: Commands,
+    levels_handle: Res<LevelsHandle>,
+    clearing: Res<LevelHandle>,
+    tree: Res<ImageHandle>,
+    loaded_levels: Res<Assets<LoadedYamlDocuments<Level>>>,
+    levels: Res<Assets<Level>>,
+    mut state: ResMut<NextState<AppState>>,
+) {
+    if let Some(loaded_levels) = loaded_levels.get(&levels_handle.0) {
+        info!(
+            "Loaded {} levels; the clearing has {} trees",
+            loaded_levels.documents.len(),
+            levels.get(&clearing.0).unwrap().positions.len()
+        );
+        for level in loaded_levels.documents.iter() {
+            for position in levels.get(level).unwrap().positions.iter() {
+                commands.spawn((
+                    Sprite::from_image(tree.0.clone()),
+                    Transform::from_translation((*position).into()),
+                ));
+            }
+        }
+
+        state.set(AppState::Level);
+    }
+}
+
+#[derive(serde::Deserialize, Asset, TypePath)]
+struct Level {
+    positions: Vec<[f32; 3]>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash, States)]
+enum AppState {
+    #[default]
+    Loading,
+    Level,
+}
+
+#[derive(Resource)]
+struct ImageHandle(Handle<Image>);
+
+#[derive(Resource)]
+struct LevelsHandle(Handle<LoadedYamlDocuments<Level>>);
+
+#[derive(Resource)]
+struct LevelHandle(Handle<Level>);
@@ -1,51 +1,106 @@
+use crate::fingerprint::{Fingerprint, FingerprintError, strip_envelope};
+use crate::loader::{AssetCheck, LoaderOptions, loader_options_methods};
+use crate::stream::{Decoded, LoadedStream, read_records};
+use crate::value::Value;
+use crate::{DataAsset, DataAssetError, ToDefinition, ValidateAsset, ValidationMode};
 use bevy_app::{App, Plugin};
 use bevy_asset::{
-    Asset, AssetApp, AssetLoader, AsyncWriteExt, LoadContext, io::Reader, saver::AssetSaver,
+    AssetApp, AssetLoader, AsyncWriteExt, LoadContext, io::Reader, saver::AssetSaver,
 };
+use bevy_reflect::{TypePath, Typed};
 use ciborium::from_reader;
 use serde::{Deserialize, Serialize};
+use std::io::ErrorKind;
 use std::marker::PhantomData;
 use thiserror::Error;
 
 /// Plugin to load your asset type `A` from "Concise Binary Object Representation" (CBOR) files.
 pub struct CborAssetPlugin<A> {
     extensions: Vec<&'static str>,
-    _marker: PhantomData<A>,
+    options: LoaderOptions<A>,
+    fingerprint: Option<Fingerprint>,
 }
 
 impl<A> Plugin for CborAssetPlugin<A>
 where
-    for<'de> A: Deserialize<'de> + Asset,
+    A: DataAsset,
 {
     fn build(&self, app: &mut App) {
-        app.init_asset::<A>()
-            .register_asset_loader(CborAssetLoader::<A> {
-                extensions: self.extensions.clone(),
-                _marker: PhantomData,
-            });
+        crate::data_asset::init_data_asset::<A>(app);
+        self.register_loader(app);
     }
 }
 
 impl<A> CborAssetPlugin<A>
 where
-    for<'de> A: Deserialize<'de> + Asset,
+    A: DataAsset,
 {
     /// Create a new plugin that will load assets from files with the given extensions.
     pub fn new(extensions: &[&'static str]) -> Self {
         Self {
             extensions: extensions.to_owned(),
-            _marker: PhantomData,
+            options: LoaderOptions::default(),
+            fingerprint: None,
         }
     }
+
+    /// Only load files with the [`Fingerprint`] of `A`, as written by
+    /// [`CborAssetSaver::with_fingerprint`].
+    ///
+    /// Files without a fingerprint or with the fingerprint of an older schema fail to load.
+    pub fn with_fingerprint(mut self) -> Self
+    where
+        A::Definition: Typed,
+    {
+        self.fingerprint = Some(Fingerprint::of::<A>());
+        self
+    }
+
+    pub(crate) fn with_optional_fingerprint(mut self, fingerprint: Option<Fingerprint>) -> Self {
+        self.fingerprint = fingerprint;
+        self
+    }
+
+    pub(crate) fn with_check(mut self, check: AssetCheck<A>) -> Self {
+        self.options.check = check;
+        self
+    }
+
+    /// Register the loader of this plugin without initializing the asset
+    pub(crate) fn register_loader(&self, app: &mut App) {
+        let options = self.options.build(app);
+        app.register_asset_loader(CborAssetLoader::<A> {
+            extensions: self.extensions.clone(),
+            options,
+            fingerprint: self.fingerprint,
+        });
+    }
+
+    loader_options_methods!();
 }
 
 /// Loads your asset type `A` from CBOR files
 pub struct CborAssetLoader<A> {
     extensions: Vec<&'static str>,
-    _marker: PhantomData<A>,
+    options: LoaderOptions<A>,
+    fingerprint: Option<Fingerprint>,
 }
 
-/// Possible errors that can be produced by [`CborAssetLoader`] or [`CborAssetSaver`]
+impl<A> CborAssetLoader<A> {
+    /// Create a loader without extensions for other loaders delegating to it
+    pub(crate) fn from_options(
+        options: LoaderOptions<A>,
+        fingerprint: Option<Fingerprint>,
+    ) -> Self {
+        Self {
+            extensions: vec![],
+            options,
+            fingerprint,
+        }
+    }
+}
+
+/// Possible errors that can be produced by the CBOR asset loaders and savers
 #[non_exhaustive]
 #[derive(Debug, Error)]
 pub enum CborAssetError {
@@ -60,11 +115,17 @@ pub enum CborAssetError {
     /// A [ciborium deserializing Error](ciborium::de::Error)
     #[error("Could not parse CBOR: {0}")]
     CborDeError(#[from] ciborium::de::Error<std::io::Error>),
+    /// A missing or outdated [`Fingerprint`]
+    #[error(transparent)]
+    FingerprintError(#[from] FingerprintError),
+    /// An error of the format independent loading steps
+    #[error(transparent)]
+    DataAssetError(#[from] DataAssetError),
 }
 
 impl<A> AssetLoader for CborAssetLoader<A>
 where
-    for<'de> A: Deserialize<'de> + Asset,
+    A: DataAsset,
 {
     type Asset = A;
     type Settings = ();
@@ -74,12 +135,17 @@ where
         &self,
         reader: &mut dyn Reader,
         _settings: &(),
-        _load_context: &mut LoadContext<'_>,
+        load_context: &mut LoadContext<'_>,
     ) -> Result<Self::Asset, Self::Error> {
         let mut bytes = Vec::new();
         reader.read_to_end(&mut bytes).await?;
-        let asset: A = from_reader(&bytes[..])?;
-        Ok(asset)
+        let bytes = strip_envelope(&bytes, A::type_path(), self.fingerprint.as_ref())?;
+        if self.options.uses_value() {
+            let document = from_reader::<Value, _>(bytes)?;
+            return Ok(self.options.load_value(document, load_context).await?);
+        }
+        let definition: A::Definition = from_reader(bytes)?;
+        Ok(self.options.create_asset(definition, load_context).await?)
     }
 
     fn extensions(&self) -> &[&str] {
@@ -89,18 +155,51 @@ where
 
 /// Saves your asset type `A` to `Cbor` files
 pub struct CborAssetSaver<A> {
-    _marker: PhantomData<A>,
+    fingerprint: Option<Fingerprint>,
+    check: AssetCheck<A>,
 }
 
 impl<A> Default for CborAssetSaver<A> {
     fn default() -> Self {
         Self {
-            _marker: PhantomData,
+            fingerprint: None,
+            check: AssetCheck::default(),
+        }
+    }
+}
+
+impl<A> CborAssetSaver<A> {
+    /// Create a saver writing the [`Fingerprint`] of `A` in front of the data
+    pub fn with_fingerprint() -> Self
+    where
+        A: DataAsset<Definition: Typed>,
+    {
+        Self::with_optional_fingerprint(Some(Fingerprint::of::<A>()))
+    }
+
+    pub(crate) fn with_optional_fingerprint(fingerprint: Option<Fingerprint>) -> Self {
+        Self {
+            fingerprint,
+            check: AssetCheck::default(),
         }
     }
+
+    /// Refuse to save assets failing their [`ValidateAsset`] check, which fails asset processing
+    pub fn with_asset_validation(mut self) -> Self
+    where
+        A: ValidateAsset,
+    {
+        self.check = AssetCheck::new(ValidationMode::Fail);
+        self
+    }
+
+    pub(crate) fn with_check(mut self, check: AssetCheck<A>) -> Self {
+        self.check = check;
+        self
+    }
 }
 
-impl<A: Asset + for<'de> Deserialize<'de> + Serialize> AssetSaver for CborAssetSaver<A> {
+impl<A: ToDefinition> AssetSaver for CborAssetSaver<A> {
     type Asset = A;
     type Settings = ();
     type OutputLoader = CborAssetLoader<A>;
@@ -112,9 +211,138 @@ impl<A: Asset + for<'de> Deserialize<'de> + Serialize> AssetSaver for CborAssetS
         asset: bevy_asset::saver::SavedAsset<'_, Self::Asset>,
         _settings: &Self::Settings,
     ) -> Result<<Self::OutputLoader as AssetLoader>::Settings, Self::Error> {
+        self.check.require(asset.get())?;
+        let mut bytes = self
+            .fingerprint
+            .map(|fingerprint| fingerprint.envelope())
+            .unwrap_or_default();
+        ciborium::into_writer(&asset.get().to_definition(), &mut bytes)?;
+        writer.write_all(&bytes).await?;
+        Ok(())
+    }
+}
+
+/// Plugin to load streams of your asset type `A` from [CBOR sequence](https://www.rfc-editor.org/rfc/rfc8742) files.
+///
+/// The file holds concatenated CBOR data items, one per record. The records are collected in a
+/// [`LoadedStream`] asset.
+pub struct CborStreamAssetPlugin<A> {
+    extensions: Vec<&'static str>,
+    _marker: PhantomData<A>,
+}
+
+impl<A> Plugin for CborStreamAssetPlugin<A>
+where
+    for<'de> A: Deserialize<'de> + TypePath + Send + Sync + 'static,
+{
+    fn build(&self, app: &mut App) {
+        app.init_asset::<LoadedStream<A>>()
+            .register_asset_loader(CborStreamAssetLoader::<A> {
+                extensions: self.extensions.clone(),
+                _marker: PhantomData,
+            });
+    }
+}
+
+impl<A> CborStreamAssetPlugin<A>
+where
+    for<'de> A: Deserialize<'de> + TypePath + Send + Sync + 'static,
+{
+    /// Create a new plugin that will load assets from files with the given extensions.
+    pub fn new(extensions: &[&'static str]) -> Self {
+        Self {
+            extensions: extensions.to_owned(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+/// Loads streams of your asset type `A` from CBOR sequence files
+pub struct CborStreamAssetLoader<A> {
+    extensions: Vec<&'static str>,
+    _marker: PhantomData<A>,
+}
+
+impl<A> AssetLoader for CborStreamAssetLoader<A>
+where
+    for<'de> A: Deserialize<'de> + TypePath + Send + Sync + 'static,
+{
+    type Asset = LoadedStream<A>;
+    type Settings = ();
+    type Error = CborAssetError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let records = read_records(reader, |bytes, _, _| -> Result<_, CborAssetError> {
+            let mut remaining = &bytes[..];
+            match from_reader::<A, _>(&mut remaining) {
+                Ok(record) => Ok(Decoded::Record(record, bytes.len() - remaining.len())),
+                Err(ciborium::de::Error::Io(error)) if error.kind() == ErrorKind::UnexpectedEof => {
+                    Ok(Decoded::Incomplete)
+                }
+                Err(error) => Err(error.into()),
+            }
+        })
+        .await?;
+        Ok(LoadedStream { records })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &self.extensions
+    }
+}
+
+/// Saves streams of your asset type `A` to CBOR sequence files
+pub struct CborStreamAssetSaver<A> {
+    _marker: PhantomData<A>,
+}
+
+impl<A> Default for CborStreamAssetSaver<A> {
+    fn default() -> Self {
+        Self {
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<A> CborStreamAssetSaver<A>
+where
+    A: Serialize,
+{
+    /// Write `record` at the end of `writer`, see [`LoadedStream`]
+    pub async fn append_record(
+        writer: &mut bevy_asset::io::Writer,
+        record: &A,
+    ) -> Result<(), CborAssetError> {
         let mut bytes = Vec::new();
-        ciborium::into_writer(&asset.get(), &mut bytes)?;
+        ciborium::into_writer(record, &mut bytes)?;
         writer.write_all(&bytes).await?;
         Ok(())
     }
 }
+
+impl<A> AssetSaver for CborStreamAssetSaver<A>
+where
+    for<'de> A: Deserialize<'de> + Serialize + TypePath + Send + Sync + 'static,
+{
+    type Asset = LoadedStream<A>;
+    type Settings = ();
+    type OutputLoader = CborStreamAssetLoader<A>;
+    type Error = CborAssetError;
+
+    async fn save(
+        &self,
+        writer: &mut bevy_asset::io::Writer,
+        asset: bevy_asset::saver::SavedAsset<'_, Self::Asset>,
+        _settings: &Self::Settings,
+    ) -> Result<<Self::OutputLoader as AssetLoader>::Settings, Self::Error> {
+        for record in asset.get().records.iter() {
+            Self::append_record(writer, record).await?;
+        }
+        Ok(())
+    }
+}
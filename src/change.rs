@@ -0,0 +1,264 @@
+use crate::ToDefinition;
+use crate::value::{Value, diff, escape, unescape};
+use bevy_app::{App, PostUpdate};
+use bevy_asset::{Asset, AssetEvent, AssetEventSystems, AssetId, Assets};
+use bevy_ecs::message::{Message, MessageReader, MessageWriter};
+use bevy_ecs::prelude::{IntoScheduleConfigs, Res, ResMut, Resource};
+use std::collections::HashMap;
+use tracing::warn;
+
+/// Sent when an asset of type `A` was modified, with the fields that changed
+///
+/// Enable these messages with `with_change_events` on the format plugins. The definitions of
+/// all assets of type `A` are kept as [`Value`] trees, and every [`AssetEvent::Modified`], like
+/// the hot reload of a file or a change through `Assets::get_mut`, is compared against the
+/// previous tree. Modifications that change no field send no message.
+///
+/// ```no_run
+/// # use bevy::prelude::*;
+/// # use bevy_common_assets::change::DataAssetChanged;
+/// # #[derive(serde::Deserialize, serde::Serialize, Asset, TypePath)]
+/// # struct Level;
+/// fn move_trees(mut changes: MessageReader<DataAssetChanged<Level>>) {
+///     for changed in changes.read() {
+///         for change in &changed.changes {
+///             info!("{}: {:?} -> {:?}", change.path, change.old, change.new);
+///         }
+///         if changed.changed("positions/2") {
+///             // only move the third tree
+///         }
+///     }
+/// }
+/// ```
+#[derive(Message)]
+pub struct DataAssetChanged<A: Asset> {
+    /// The id of the modified asset
+    pub id: AssetId<A>,
+    /// The changed fields, in the order of the document
+    pub changes: Vec<FieldChange>,
+}
+
+impl<A: Asset> DataAssetChanged<A> {
+    /// Whether the value at `path` or any value inside of it changed
+    ///
+    /// A value also changed if one of its parents was replaced as a whole.
+    pub fn changed(&self, path: &str) -> bool {
+        self.changes
+            .iter()
+            .any(|change| contains(&change.path, path) || contains(path, &change.path))
+    }
+
+    /// The change of the value at exactly `path`
+    pub fn get(&self, path: &str) -> Option<&FieldChange> {
+        self.changes.iter().find(|change| change.path == path)
+    }
+}
+
+/// Whether `inner` is `outer` or a path inside of it
+fn contains(inner: &str, outer: &str) -> bool {
+    outer.is_empty()
+        || inner
+            .strip_prefix(outer)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+}
+
+/// A field of an asset definition that changed
+///
+/// Maps are compared per key and sequences per index, so changing one item of a list only
+/// reports that item. A value whose type or enum variant changed is reported as a whole.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FieldChange {
+    /// The map keys and sequence indices leading to the field, separated by `/` like the
+    /// paths of labeled sub-assets, e.g. `positions/2`
+    ///
+    /// `/` and `~` in keys are escaped as `~1` and `~0`. The path is empty if the whole
+    /// definition was replaced.
+    pub path: String,
+    /// The previous value, `None` if the field was added
+    pub old: Option<Value>,
+    /// The new value, `None` if the field was removed
+    pub new: Option<Value>,
+}
+
+impl FieldChange {
+    /// The unescaped map keys and sequence indices of the path
+    pub fn segments(&self) -> impl Iterator<Item = String> + '_ {
+        self.path
+            .split('/')
+            .filter(|_| !self.path.is_empty())
+            .map(unescape)
+    }
+}
+
+/// The definitions of the loaded assets of type `A` as of their last change
+#[derive(Resource)]
+struct ValueTrees<A: Asset> {
+    trees: HashMap<AssetId<A>, Value>,
+}
+
+impl<A: Asset> Default for ValueTrees<A> {
+    fn default() -> Self {
+        Self {
+            trees: HashMap::new(),
+        }
+    }
+}
+
+/// Send [`DataAssetChanged`] messages for modified assets of type `A`
+///
+/// Registering the messages more than once, e.g. from the plugins of several formats, has no
+/// further effect.
+pub(crate) fn register<A: ToDefinition>(app: &mut App) {
+    if app.world().contains_resource::<ValueTrees<A>>() {
+        return;
+    }
+    app.init_resource::<ValueTrees<A>>()
+        .add_message::<DataAssetChanged<A>>()
+        .add_systems(PostUpdate, send_changes::<A>.after(AssetEventSystems));
+}
+
+fn send_changes<A: ToDefinition>(
+    mut events: MessageReader<AssetEvent<A>>,
+    assets: Res<Assets<A>>,
+    mut trees: ResMut<ValueTrees<A>>,
+    mut changed: MessageWriter<DataAssetChanged<A>>,
+) {
+    for event in events.read() {
+        match *event {
+            AssetEvent::Added { id } | AssetEvent::Modified { id } => {
+                let Some(asset) = assets.get(id) else {
+                    continue;
+                };
+                let tree = match Value::from_serialize(&asset.to_definition()) {
+                    Ok(tree) => tree,
+                    Err(error) => {
+                        warn!("Could not compare the modified asset {id}: {error}");
+                        trees.trees.remove(&id);
+                        continue;
+                    }
+                };
+                if let Some(old) = trees.trees.get(&id) {
+                    let changes: Vec<_> = diff(old, &tree)
+                        .into_iter()
+                        .map(|difference| FieldChange {
+                            path: difference
+                                .path
+                                .iter()
+                                .map(|segment| escape(segment))
+                                .collect::<Vec<_>>()
+                                .join("/"),
+                            old: difference.old.cloned(),
+                            new: difference.new.cloned(),
+                        })
+                        .collect();
+                    if !changes.is_empty() {
+                        changed.write(DataAssetChanged { id, changes });
+                    }
+                }
+                trees.trees.insert(id, tree);
+            }
+            AssetEvent::Removed { id } => {
+                trees.trees.remove(&id);
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::prelude::{AssetApp, AssetPlugin, MinimalPlugins, TypePath};
+    use bevy_ecs::message::Messages;
+
+    #[derive(Asset, TypePath, serde::Deserialize, serde::Serialize)]
+    struct Level {
+        name: String,
+        positions: Vec<[i32; 2]>,
+    }
+
+    fn change(path: &str) -> FieldChange {
+        FieldChange {
+            path: path.to_owned(),
+            old: None,
+            new: None,
+        }
+    }
+
+    #[test]
+    fn parents_and_children_of_changes_changed() {
+        let changed = DataAssetChanged::<Level> {
+            id: AssetId::default(),
+            changes: vec![change("positions/2"), change("name")],
+        };
+        assert!(changed.changed("positions/2"));
+        assert!(changed.changed("positions/2/0"));
+        assert!(changed.changed("positions"));
+        assert!(changed.changed(""));
+        assert!(!changed.changed("positions/1"));
+        assert!(!changed.changed("positions/20"));
+        assert!(!changed.changed("names"));
+        assert!(changed.get("name").is_some());
+        assert!(changed.get("positions").is_none());
+    }
+
+    #[test]
+    fn segments_are_unescaped() {
+        assert_eq!(
+            change("a~1b/~0c/1").segments().collect::<Vec<_>>(),
+            ["a/b", "~c", "1"]
+        );
+        assert_eq!(change("").segments().count(), 0);
+    }
+
+    /// Update `app` and take the changes it sent
+    fn changes(app: &mut App) -> Vec<Vec<FieldChange>> {
+        app.update();
+        app.world_mut()
+            .resource_mut::<Messages<DataAssetChanged<Level>>>()
+            .drain()
+            .map(|changed| changed.changes)
+            .collect()
+    }
+
+    #[test]
+    fn modified_assets_send_their_changed_fields() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+            .init_asset::<Level>();
+        register::<Level>(&mut app);
+        let handle = app.world_mut().resource_mut::<Assets<Level>>().add(Level {
+            name: "forest".to_owned(),
+            positions: vec![[0, 0], [1, 1]],
+        });
+        let id = handle.id();
+        assert!(changes(&mut app).is_empty());
+
+        let mut level = app.world_mut().resource_mut::<Assets<Level>>();
+        let level = level.get_mut(id).unwrap();
+        level.positions[1][0] = 2;
+        level.positions.push([3, 3]);
+        assert_eq!(
+            changes(&mut app),
+            [vec![
+                FieldChange {
+                    path: "positions/1/0".to_owned(),
+                    old: Some(Value::Integer(1)),
+                    new: Some(Value::Integer(2)),
+                },
+                FieldChange {
+                    path: "positions/2".to_owned(),
+                    old: None,
+                    new: Some(Value::Seq(vec![Value::Integer(3), Value::Integer(3)])),
+                },
+            ]]
+        );
+
+        // Modifications without a changed field send nothing
+        app.world_mut()
+            .resource_mut::<Assets<Level>>()
+            .get_mut(id)
+            .unwrap();
+        assert!(changes(&mut app).is_empty());
+    }
+}
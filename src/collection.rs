@@ -0,0 +1,128 @@
+use crate::data_asset::init_data_asset;
+use crate::{DataAsset, DefinitionError};
+use bevy_app::App;
+use bevy_asset::{Asset, Handle, LoadContext, LoadedAsset};
+use bevy_reflect::TypePath;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::hash::Hash;
+
+/// Asset representing a list of assets of type `A`
+///
+/// It can be loaded by all format plugins from a top-level sequence. Every item is added as
+/// labeled sub-asset with its index as label (`levels.json#0`). The format plugins of the
+/// collection initialize the asset type of the items, which need no plugin of their own.
+///
+/// ```no_run
+/// # use bevy::prelude::*;
+/// # use bevy_common_assets::collection::LoadedVec;
+/// # use bevy_common_assets::json::JsonAssetPlugin;
+/// App::new()
+///     .add_plugins(JsonAssetPlugin::<LoadedVec<Level>>::new(&["levels.json"]))
+///     .add_systems(Startup, |asset_server: Res<AssetServer>| {
+///         let levels: Handle<LoadedVec<Level>> = asset_server.load("trees.levels.json");
+///         let first_level: Handle<Level> = asset_server.load("trees.levels.json#0");
+///     });
+/// # #[derive(serde::Deserialize, Asset, TypePath)]
+/// # struct Level {
+/// #     positions: Vec<[f32; 3]>,
+/// # }
+/// ```
+#[derive(TypePath, Asset)]
+pub struct LoadedVec<A>
+where
+    A: Asset,
+{
+    /// Handles to the items in the order of the sequence
+    pub items: Vec<Handle<A>>,
+}
+
+impl<A> DataAsset for LoadedVec<A>
+where
+    A: DataAsset,
+{
+    type Definition = Vec<A::Definition>;
+
+    async fn from_definition(
+        definition: Self::Definition,
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<Self, DefinitionError> {
+        let mut items = Vec::with_capacity(definition.len());
+        for (index, item) in definition.into_iter().enumerate() {
+            let item = load_item::<A>(item, load_context).await?;
+            items.push(load_context.add_loaded_labeled_asset(index.to_string(), item));
+        }
+        Ok(LoadedVec { items })
+    }
+
+    fn init_sub_assets(app: &mut App) {
+        init_data_asset::<A>(app);
+    }
+}
+
+/// Asset representing a map of keys to assets of type `A`
+///
+/// It can be loaded by all format plugins that support maps from a top-level map. Every item is
+/// added as labeled sub-asset with its key as label (`enemies.yaml#goblin`).
+///
+/// ```no_run
+/// # use bevy::prelude::*;
+/// # use bevy_common_assets::collection::LoadedMap;
+/// # use bevy_common_assets::yaml::YamlAssetPlugin;
+/// App::new()
+///     .add_plugins(YamlAssetPlugin::<LoadedMap<String, Enemy>>::new(&["enemies.yaml"]))
+///     .add_systems(Startup, |asset_server: Res<AssetServer>| {
+///         let enemies: Handle<LoadedMap<String, Enemy>> = asset_server.load("enemies.yaml");
+///         let goblin: Handle<Enemy> = asset_server.load("enemies.yaml#goblin");
+///     });
+/// # #[derive(serde::Deserialize, Asset, TypePath)]
+/// # struct Enemy {
+/// #     health: u32,
+/// # }
+/// ```
+#[derive(TypePath, Asset)]
+pub struct LoadedMap<K, A>
+where
+    K: TypePath + Send + Sync + 'static,
+    A: Asset,
+{
+    /// Handles to the items by their key
+    pub items: HashMap<K, Handle<A>>,
+}
+
+impl<K, A> DataAsset for LoadedMap<K, A>
+where
+    for<'de> K: Deserialize<'de> + Display + Eq + Hash + TypePath + Send + Sync + 'static,
+    A: DataAsset,
+{
+    type Definition = HashMap<K, A::Definition>;
+
+    async fn from_definition(
+        definition: Self::Definition,
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<Self, DefinitionError> {
+        let mut items = HashMap::with_capacity(definition.len());
+        for (key, item) in definition {
+            let item = load_item::<A>(item, load_context).await?;
+            let handle = load_context.add_loaded_labeled_asset(key.to_string(), item);
+            items.insert(key, handle);
+        }
+        Ok(LoadedMap { items })
+    }
+
+    fn init_sub_assets(app: &mut App) {
+        init_data_asset::<A>(app);
+    }
+}
+
+/// Create an item from its definition in a load context of its own, so its dependencies
+/// belong to the item instead of the collection
+async fn load_item<A: DataAsset>(
+    definition: A::Definition,
+    load_context: &mut LoadContext<'_>,
+) -> Result<LoadedAsset<A>, DefinitionError> {
+    let mut item_context = load_context.begin_labeled_asset();
+    let item = A::from_definition(definition, &mut item_context).await?;
+    Ok(item_context.finish(item))
+}
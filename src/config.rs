@@ -0,0 +1,318 @@
+use crate::DataAsset;
+use bevy_app::{App, Plugin, PostUpdate, Startup};
+use bevy_asset::{
+    Asset, AssetEvent, AssetEventSystems, AssetLoadFailedEvent, AssetPath, AssetServer, Assets,
+    Handle,
+};
+use bevy_ecs::message::MessageReader;
+use bevy_ecs::prelude::{Commands, IntoScheduleConfigs, Res, ResMut, Resource};
+use tracing::{error, warn};
+
+/// Plugin to keep the resource `R` in sync with the config file at a fixed path
+///
+/// The file is loaded as asset of type `R` by a format plugin, which has to be added for the
+/// extension of the path, like `TomlAssetPlugin::<R>`. Once loaded, a copy of the asset is
+/// inserted as resource and replaced whenever the file is hot reloaded. The resource is only
+/// replaced by successful reloads, so a file with errors keeps the last working config.
+/// Without a [`ConfigFallback`], the resource does not exist until the file is loaded; use the
+/// [`config_ready`] run condition for systems needing it.
+///
+/// ```no_run
+/// # use bevy::prelude::*;
+/// # use bevy_common_assets::config::{ConfigFallback, ConfigResourcePlugin, config_ready};
+/// # use bevy_common_assets::toml::TomlAssetPlugin;
+/// #[derive(serde::Deserialize, Asset, Resource, TypePath, Clone, Default)]
+/// struct GameSettings {
+///     volume: f32,
+/// }
+///
+/// App::new()
+///     .add_plugins((
+///         DefaultPlugins,
+///         TomlAssetPlugin::<GameSettings>::new(&["settings.toml"]),
+///         ConfigResourcePlugin::<GameSettings>::new("config/game.settings.toml")
+///             .with_fallback(ConfigFallback::OnFailure),
+///     ))
+///     .add_systems(Update, play_music.run_if(config_ready::<GameSettings>));
+///
+/// fn play_music(settings: Res<GameSettings>) {
+///     info!("Playing at volume {}", settings.volume);
+/// }
+/// ```
+pub struct ConfigResourcePlugin<R> {
+    path: AssetPath<'static>,
+    fallback: Option<(ConfigFallback, fn() -> R)>,
+}
+
+/// When the [`ConfigResourcePlugin`] inserts the default of its resource
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConfigFallback {
+    /// Start with the default until the config file is loaded
+    ///
+    /// The default is inserted when the plugin is added, so it exists for all systems, including
+    /// `Startup` systems. It stays if the file fails to load.
+    Immediately,
+    /// Insert the default if the config file fails to load
+    OnFailure,
+}
+
+impl<R> ConfigResourcePlugin<R>
+where
+    R: DataAsset + Resource + Clone,
+{
+    /// Create a new plugin that loads the resource from the file at `path`
+    pub fn new(path: impl Into<AssetPath<'static>>) -> Self {
+        Self {
+            path: path.into(),
+            fallback: None,
+        }
+    }
+
+    /// Use the default of `R` as config before or instead of the loaded file, depending on
+    /// `fallback`
+    pub fn with_fallback(mut self, fallback: ConfigFallback) -> Self
+    where
+        R: Default,
+    {
+        self.fallback = Some((fallback, R::default));
+        self
+    }
+}
+
+impl<R> Plugin for ConfigResourcePlugin<R>
+where
+    R: DataAsset + Resource + Clone,
+{
+    fn build(&self, app: &mut App) {
+        if let Some((ConfigFallback::Immediately, default)) = self.fallback {
+            app.insert_resource(default());
+        }
+        let path = self.path.clone();
+        let fallback = self.fallback;
+        app.add_systems(
+            Startup,
+            move |mut commands: Commands, server: Res<AssetServer>| {
+                commands.insert_resource(ConfigStatus {
+                    handle: server.load::<R>(path.clone()),
+                    state: ConfigState::Loading,
+                    fallback,
+                });
+            },
+        )
+        .add_systems(PostUpdate, sync_config::<R>.after(AssetEventSystems));
+    }
+}
+
+/// The loading state of the config file of a [`ConfigResourcePlugin`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConfigState {
+    /// The config file is loading
+    Loading,
+    /// The resource has the values of the config file
+    ///
+    /// Failed reloads of the file keep this state and the last loaded values.
+    Loaded,
+    /// The config file failed to load
+    ///
+    /// The resource is the default of its type if the plugin has a [`ConfigFallback`] and does
+    /// not exist otherwise. Fixing the file loads it if the asset server watches for changes.
+    Failed,
+}
+
+/// The config file of the resource `R` and whether it was loaded
+///
+/// Inserted by the [`ConfigResourcePlugin`] on startup. The handle keeps the config asset
+/// alive and can be used to change the file, e.g. with
+/// [`SaveAssets`](crate::save::SaveAssets).
+#[derive(Resource)]
+pub struct ConfigStatus<R: Asset> {
+    handle: Handle<R>,
+    state: ConfigState,
+    fallback: Option<(ConfigFallback, fn() -> R)>,
+}
+
+impl<R: Asset> ConfigStatus<R> {
+    /// The handle of the config asset
+    pub fn handle(&self) -> &Handle<R> {
+        &self.handle
+    }
+
+    /// The loading state of the config file
+    pub fn state(&self) -> ConfigState {
+        self.state
+    }
+
+    /// Whether loading the config file is done and the resource exists
+    ///
+    /// This is the case once the file is loaded, or once it failed to load and the resource
+    /// fell back to its default. With [`ConfigFallback::Immediately`], the default resource
+    /// exists before, but is not ready yet.
+    pub fn is_ready(&self) -> bool {
+        match self.state {
+            ConfigState::Loaded => true,
+            ConfigState::Failed => self.fallback.is_some(),
+            ConfigState::Loading => false,
+        }
+    }
+}
+
+/// Run condition for systems that need the config resource `R` of a [`ConfigResourcePlugin`]
+///
+/// See [`ConfigStatus::is_ready`].
+pub fn config_ready<R: Asset>(status: Option<Res<ConfigStatus<R>>>) -> bool {
+    status.is_some_and(|status| status.is_ready())
+}
+
+fn sync_config<R>(
+    mut commands: Commands,
+    mut events: MessageReader<AssetEvent<R>>,
+    mut failures: MessageReader<AssetLoadFailedEvent<R>>,
+    status: Option<ResMut<ConfigStatus<R>>>,
+    assets: Res<Assets<R>>,
+) where
+    R: DataAsset + Resource + Clone,
+{
+    let Some(mut status) = status else {
+        return;
+    };
+    let id = status.handle.id();
+    for event in events.read() {
+        match event {
+            AssetEvent::LoadedWithDependencies { id: loaded }
+            | AssetEvent::Modified { id: loaded }
+                if *loaded == id =>
+            {
+                if let Some(config) = assets.get(id) {
+                    commands.insert_resource(config.clone());
+                    status.state = ConfigState::Loaded;
+                }
+            }
+            _ => {}
+        }
+    }
+    for failure in failures.read().filter(|failure| failure.id == id) {
+        if status.state == ConfigState::Loaded {
+            warn!(
+                "Keeping the current config, reloading `{}` failed: {}",
+                failure.path, failure.error
+            );
+            continue;
+        }
+        error!(
+            "Could not load the config `{}`: {}",
+            failure.path, failure.error
+        );
+        match status.fallback {
+            Some((ConfigFallback::OnFailure, default)) if status.state == ConfigState::Loading => {
+                commands.insert_resource(default());
+            }
+            _ => {}
+        }
+        status.state = ConfigState::Failed;
+    }
+}
+
+#[cfg(all(test, feature = "json"))]
+mod tests {
+    use super::*;
+    use crate::json::JsonAssetPlugin;
+    use bevy::prelude::{AssetPlugin, MinimalPlugins, TypePath};
+    use std::path::PathBuf;
+
+    #[derive(serde::Deserialize, Asset, Resource, TypePath, Clone, Default, Debug, PartialEq)]
+    struct Settings {
+        volume: f32,
+    }
+
+    /// An app keeping `Settings` in sync with `settings.json` of a new directory
+    fn app(name: &str, content: Option<&str>, fallback: Option<ConfigFallback>) -> (App, PathBuf) {
+        let directory = std::env::temp_dir().join(format!("bevy_common_assets_{name}"));
+        let _ = std::fs::remove_dir_all(&directory);
+        std::fs::create_dir_all(&directory).unwrap();
+        if let Some(content) = content {
+            std::fs::write(directory.join("settings.json"), content).unwrap();
+        }
+        let mut plugin = ConfigResourcePlugin::<Settings>::new("settings.json");
+        if let Some(fallback) = fallback {
+            plugin = plugin.with_fallback(fallback);
+        }
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            AssetPlugin {
+                file_path: directory.to_string_lossy().into(),
+                ..Default::default()
+            },
+            JsonAssetPlugin::<Settings>::new(&["json"]),
+            plugin,
+        ));
+        (app, directory)
+    }
+
+    /// Update `app` until the config file is loaded or failed
+    fn settle(app: &mut App) -> ConfigState {
+        for _ in 0..500 {
+            app.update();
+            let state = app.world().resource::<ConfigStatus<Settings>>().state();
+            if state != ConfigState::Loading {
+                return state;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(5));
+        }
+        panic!("timed out");
+    }
+
+    fn ready(app: &App) -> bool {
+        app.world().resource::<ConfigStatus<Settings>>().is_ready()
+    }
+
+    #[test]
+    fn the_resource_follows_the_config_asset() {
+        let (mut app, _) = app("loaded_config", Some(r#"{"volume": 0.5}"#), None);
+        assert_eq!(settle(&mut app), ConfigState::Loaded);
+        assert!(ready(&app));
+        assert_eq!(app.world().resource::<Settings>().volume, 0.5);
+
+        let handle = app
+            .world()
+            .resource::<ConfigStatus<Settings>>()
+            .handle()
+            .clone();
+        app.world_mut()
+            .resource_mut::<Assets<Settings>>()
+            .get_mut(&handle)
+            .unwrap()
+            .volume = 0.25;
+        app.update();
+        assert_eq!(app.world().resource::<Settings>().volume, 0.25);
+    }
+
+    #[test]
+    fn failures_without_fallback_have_no_resource() {
+        let (mut app, _) = app("failed_config", Some("{"), None);
+        assert_eq!(settle(&mut app), ConfigState::Failed);
+        assert!(!ready(&app));
+        assert!(!app.world().contains_resource::<Settings>());
+    }
+
+    #[test]
+    fn failures_fall_back_to_the_default() {
+        let (mut app, _) = app("fallback_config", None, Some(ConfigFallback::OnFailure));
+        assert_eq!(settle(&mut app), ConfigState::Failed);
+        assert!(ready(&app));
+        assert_eq!(app.world().resource::<Settings>(), &Settings::default());
+    }
+
+    #[test]
+    fn immediate_defaults_exist_before_loading() {
+        let content = r#"{"volume": 0.5}"#;
+        let (mut app, _) = app(
+            "immediate_config",
+            Some(content),
+            Some(ConfigFallback::Immediately),
+        );
+        assert_eq!(app.world().resource::<Settings>(), &Settings::default());
+        assert_eq!(settle(&mut app), ConfigState::Loaded);
+        assert_eq!(app.world().resource::<Settings>().volume, 0.5);
+    }
+}
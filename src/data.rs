@@ -0,0 +1,826 @@
+use crate::DataAsset;
+#[cfg(any(feature = "json", feature = "ron", feature = "toml", feature = "yaml"))]
+use crate::loader::text_options_methods;
+use crate::loader::{LoaderOptions, loader_options_methods};
+use crate::migration::Migrate;
+#[cfg(any(
+    feature = "json",
+    feature = "msgpack",
+    feature = "ron",
+    feature = "toml",
+    feature = "yaml",
+    feature = "cbor"
+))]
+use crate::migration::migrate;
+#[cfg(any(
+    feature = "json",
+    feature = "msgpack",
+    feature = "ron",
+    feature = "toml",
+    feature = "yaml",
+    feature = "cbor"
+))]
+use crate::value::Value;
+use bevy_app::{App, Plugin};
+use bevy_asset::io::{Reader, SliceReader, Writer};
+use bevy_asset::meta::{AssetAction, AssetMeta};
+use bevy_asset::processor::{Process, ProcessContext, ProcessError};
+use bevy_asset::{AssetApp, AssetLoader, AsyncWriteExt, LoadContext};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::marker::PhantomData;
+use thiserror::Error;
+
+/// Plugin to load your asset type `A` from files of any enabled format.
+///
+/// The format of every file is detected from its content, so the extensions do not need to
+/// name a format. Files can also be loaded with a forced format by setting
+/// [`DataAssetSettings::format`] in their `.meta` file.
+///
+/// Detection looks at the start of a file:
+/// * the CBOR self-describe tag and CBOR map markers are read as cbor
+/// * `MessagePack` map and array markers are read as `MessagePack`
+/// * an XML prolog or element is read as xml
+/// * `---` or a `%YAML` directive is read as yaml
+/// * `{` is read as json (or ron if it is no valid json)
+/// * `[` is read as a toml table header if it looks like one, as json otherwise
+/// * RON extensions, tuples and named structs like `Level(` are read as ron
+/// * a first line like `key = value` is read as toml, anything else as yaml
+///
+/// Postcard is not self-describing and is only loaded when forced. Fingerprint envelopes in
+/// front of cbor data are skipped for the detection.
+pub struct DataAssetPlugin<A> {
+    extensions: Vec<&'static str>,
+    options: LoaderOptions<A>,
+    #[cfg(any(feature = "postcard", feature = "cbor"))]
+    fingerprint: Option<crate::fingerprint::Fingerprint>,
+    register_migration_processor: Option<fn(&mut App, &[&'static str])>,
+}
+
+impl<A> Plugin for DataAssetPlugin<A>
+where
+    A: DataAsset,
+{
+    fn build(&self, app: &mut App) {
+        crate::data_asset::init_data_asset::<A>(app);
+        let options = self.options.build(app);
+        app.register_asset_loader(DataAssetLoader::<A> {
+            extensions: self.extensions.clone(),
+            #[cfg(feature = "json")]
+            json: crate::json::JsonAssetLoader::from_options(options.clone()),
+            #[cfg(feature = "ron")]
+            ron: crate::ron::RonAssetLoader::from_options(options.clone()),
+            #[cfg(feature = "toml")]
+            toml: crate::toml::TomlAssetLoader::from_options(options.clone()),
+            #[cfg(feature = "yaml")]
+            yaml: crate::yaml::YamlAssetLoader::from_options(options.clone()),
+            #[cfg(feature = "msgpack")]
+            msgpack: crate::msgpack::MsgPackAssetLoader::from_options(options.clone()),
+            #[cfg(feature = "cbor")]
+            cbor: crate::cbor::CborAssetLoader::from_options(options.clone(), self.fingerprint),
+            #[cfg(feature = "xml")]
+            xml: crate::xml::XmlAssetLoader::new(options.check),
+            #[cfg(feature = "postcard")]
+            postcard: crate::postcard::PostcardAssetLoader::new(options.check, self.fingerprint),
+        });
+        if let Some(register_migration_processor) = self.register_migration_processor {
+            register_migration_processor(app, &self.extensions);
+        }
+    }
+}
+
+impl<A> DataAssetPlugin<A>
+where
+    A: DataAsset,
+{
+    /// Create a new plugin that will load assets from files with the given extensions.
+    pub fn new(extensions: &[&'static str]) -> Self {
+        Self {
+            extensions: extensions.to_owned(),
+            options: LoaderOptions::default(),
+            #[cfg(any(feature = "postcard", feature = "cbor"))]
+            fingerprint: None,
+            register_migration_processor: None,
+        }
+    }
+
+    loader_options_methods!();
+    #[cfg(any(feature = "json", feature = "ron", feature = "toml", feature = "yaml"))]
+    text_options_methods!();
+
+    /// Validate loaded json, toml and yaml documents against a JSON Schema before
+    /// deserializing them.
+    ///
+    /// See [`DocumentSettings::schema`](crate::DocumentSettings::schema) for where the schema
+    /// comes from. Set it per file in [`DataAssetSettings::document`].
+    #[cfg(feature = "validation")]
+    pub fn with_schema_validation(mut self) -> Self {
+        self.options.validate = true;
+        self
+    }
+
+    /// Only load postcard and cbor files with the
+    /// [`Fingerprint`](crate::fingerprint::Fingerprint) of `A`, as written by the savers built
+    /// with `with_fingerprint`.
+    ///
+    /// Files without a fingerprint or with the fingerprint of an older schema fail to load.
+    #[cfg(any(feature = "postcard", feature = "cbor"))]
+    pub fn with_fingerprint(mut self) -> Self
+    where
+        A::Definition: bevy_reflect::Typed,
+    {
+        self.fingerprint = Some(crate::fingerprint::Fingerprint::of::<A>());
+        self
+    }
+
+    /// Register a [`MigrationProcessor`] as default processor for the extensions of this plugin,
+    /// so processed assets are rewritten to the current version of `A`.
+    ///
+    /// This also [migrates documents while loading](Self::with_migrations) for when asset
+    /// processing is disabled.
+    pub fn with_migration_processor(mut self) -> Self
+    where
+        A: Migrate,
+    {
+        self.register_migration_processor = Some(|app, extensions| {
+            app.register_asset_processor(MigrationProcessor::<A>::default());
+            for extension in extensions {
+                app.set_default_asset_processor::<MigrationProcessor<A>>(extension);
+            }
+        });
+        self.with_migrations()
+    }
+}
+
+/// The formats the [`DataAssetLoader`] can load
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DataFormat {
+    /// json
+    #[cfg(feature = "json")]
+    Json,
+    /// ron
+    #[cfg(feature = "ron")]
+    Ron,
+    /// toml
+    #[cfg(feature = "toml")]
+    Toml,
+    /// yaml
+    #[cfg(feature = "yaml")]
+    Yaml,
+    /// `MessagePack`
+    #[cfg(feature = "msgpack")]
+    MsgPack,
+    /// cbor
+    #[cfg(feature = "cbor")]
+    Cbor,
+    /// xml
+    #[cfg(feature = "xml")]
+    Xml,
+    /// postcard, which is never detected and needs to be forced
+    #[cfg(feature = "postcard")]
+    Postcard,
+}
+
+impl DataFormat {
+    /// The enabled format of files with `extension`
+    ///
+    /// Only the last part of full extensions like `level.ron` is used.
+    pub fn from_extension(extension: &str) -> Option<DataFormat> {
+        match extension.rsplit('.').next()?.to_ascii_lowercase().as_str() {
+            #[cfg(feature = "json")]
+            "json" => Some(DataFormat::Json),
+            #[cfg(feature = "ron")]
+            "ron" => Some(DataFormat::Ron),
+            #[cfg(feature = "toml")]
+            "toml" => Some(DataFormat::Toml),
+            #[cfg(feature = "yaml")]
+            "yaml" | "yml" => Some(DataFormat::Yaml),
+            #[cfg(feature = "msgpack")]
+            "msgpack" => Some(DataFormat::MsgPack),
+            #[cfg(feature = "cbor")]
+            "cbor" => Some(DataFormat::Cbor),
+            #[cfg(feature = "xml")]
+            "xml" => Some(DataFormat::Xml),
+            #[cfg(feature = "postcard")]
+            "postcard" => Some(DataFormat::Postcard),
+            _ => None,
+        }
+    }
+}
+
+/// Settings of the [`DataAssetLoader`]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DataAssetSettings {
+    /// Load the file in this format instead of detecting it
+    pub format: Option<DataFormat>,
+    /// The settings of json, ron, toml and yaml documents
+    #[cfg(any(feature = "json", feature = "ron", feature = "toml", feature = "yaml"))]
+    pub document: crate::DocumentSettings,
+}
+
+/// Loads your asset type `A` from files of any enabled format
+pub struct DataAssetLoader<A> {
+    extensions: Vec<&'static str>,
+    #[cfg(feature = "json")]
+    json: crate::json::JsonAssetLoader<A>,
+    #[cfg(feature = "ron")]
+    ron: crate::ron::RonAssetLoader<A>,
+    #[cfg(feature = "toml")]
+    toml: crate::toml::TomlAssetLoader<A>,
+    #[cfg(feature = "yaml")]
+    yaml: crate::yaml::YamlAssetLoader<A>,
+    #[cfg(feature = "msgpack")]
+    msgpack: crate::msgpack::MsgPackAssetLoader<A>,
+    #[cfg(feature = "cbor")]
+    cbor: crate::cbor::CborAssetLoader<A>,
+    #[cfg(feature = "xml")]
+    xml: crate::xml::XmlAssetLoader<A>,
+    #[cfg(feature = "postcard")]
+    postcard: crate::postcard::PostcardAssetLoader<A>,
+}
+
+/// Possible errors that can be produced by [`DataAssetLoader`]
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum DataLoaderError {
+    /// An [IO Error](std::io::Error)
+    #[error("Could not read the file: {0}")]
+    Io(#[from] std::io::Error),
+    /// None of the enabled formats was detected
+    #[error("Could not detect the format of the file")]
+    UnknownFormat,
+    /// The [`MigrationProcessor`] got a file with a fingerprint, which is checked instead of
+    /// migrating the file
+    #[cfg(any(feature = "postcard", feature = "cbor"))]
+    #[error("Files with a fingerprint can not be migrated")]
+    Fingerprinted,
+    /// A [json loader error](crate::json::JsonLoaderError)
+    #[cfg(feature = "json")]
+    #[error("Could not load the file as json: {0}")]
+    Json(#[from] crate::json::JsonLoaderError),
+    /// A [ron loader error](crate::ron::RonLoaderError)
+    #[cfg(feature = "ron")]
+    #[error("Could not load the file as ron: {0}")]
+    Ron(#[from] crate::ron::RonLoaderError),
+    /// A [toml loader error](crate::toml::TomlLoaderError)
+    #[cfg(feature = "toml")]
+    #[error("Could not load the file as toml: {0}")]
+    Toml(#[from] crate::toml::TomlLoaderError),
+    /// A [yaml loader error](crate::yaml::YamlLoaderError)
+    #[cfg(feature = "yaml")]
+    #[error("Could not load the file as yaml: {0}")]
+    Yaml(#[from] crate::yaml::YamlLoaderError),
+    /// A [`MessagePack` loader error](crate::msgpack::MsgPackLoaderError)
+    #[cfg(feature = "msgpack")]
+    #[error("Could not load the file as MessagePack: {0}")]
+    MsgPack(#[from] crate::msgpack::MsgPackLoaderError),
+    /// A [cbor loader error](crate::cbor::CborAssetError)
+    #[cfg(feature = "cbor")]
+    #[error("Could not load the file as cbor: {0}")]
+    Cbor(#[from] crate::cbor::CborAssetError),
+    /// A [xml loader error](crate::xml::XmlLoaderError)
+    #[cfg(feature = "xml")]
+    #[error("Could not load the file as xml: {0}")]
+    Xml(#[from] crate::xml::XmlLoaderError),
+    /// A [postcard loader error](crate::postcard::PostcardAssetError)
+    #[cfg(feature = "postcard")]
+    #[error("Could not load the file as postcard: {0}")]
+    Postcard(#[from] crate::postcard::PostcardAssetError),
+}
+
+impl<A> AssetLoader for DataAssetLoader<A>
+where
+    A: DataAsset,
+{
+    type Asset = A;
+    type Settings = DataAssetSettings;
+    type Error = DataLoaderError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        settings: &DataAssetSettings,
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        #[cfg(any(feature = "postcard", feature = "cbor"))]
+        let data = crate::fingerprint::skip_envelope(&bytes);
+        #[cfg(not(any(feature = "postcard", feature = "cbor")))]
+        let data = &bytes;
+        let format = settings
+            .format
+            .or_else(|| detect_format(data))
+            .ok_or(DataLoaderError::UnknownFormat)?;
+        let reader = &mut SliceReader::new(&bytes);
+        Ok(match format {
+            #[cfg(feature = "json")]
+            DataFormat::Json => {
+                self.json
+                    .load(reader, &settings.document, load_context)
+                    .await?
+            }
+            #[cfg(feature = "ron")]
+            DataFormat::Ron => {
+                self.ron
+                    .load(reader, &settings.document, load_context)
+                    .await?
+            }
+            #[cfg(feature = "toml")]
+            DataFormat::Toml => {
+                self.toml
+                    .load(reader, &settings.document, load_context)
+                    .await?
+            }
+            #[cfg(feature = "yaml")]
+            DataFormat::Yaml => {
+                self.yaml
+                    .load(reader, &settings.document, load_context)
+                    .await?
+            }
+            #[cfg(feature = "msgpack")]
+            DataFormat::MsgPack => self.msgpack.load(reader, &(), load_context).await?,
+            #[cfg(feature = "cbor")]
+            DataFormat::Cbor => self.cbor.load(reader, &(), load_context).await?,
+            #[cfg(feature = "xml")]
+            DataFormat::Xml => self.xml.load(reader, &(), load_context).await?,
+            #[cfg(feature = "postcard")]
+            DataFormat::Postcard => self.postcard.load(reader, &(), load_context).await?,
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &self.extensions
+    }
+}
+
+/// Asset processor rewriting documents to the current version of `A`
+///
+/// The source format is detected like by the [`DataAssetLoader`],
+/// or forced with the processor settings. The migrated document is written in the same format
+/// and loaded by the `DataAssetLoader<A>`. Register it with
+/// [`DataAssetPlugin::with_migration_processor`].
+/// Comments and formatting of the source are not kept. Xml and postcard documents and files
+/// with a [fingerprint](crate::fingerprint::Fingerprint) can not be migrated.
+pub struct MigrationProcessor<A> {
+    _marker: PhantomData<fn() -> A>,
+}
+
+impl<A> Default for MigrationProcessor<A> {
+    fn default() -> Self {
+        Self {
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<A: Migrate> MigrationProcessor<A> {
+    /// The document `bytes` in `format` migrated to the current version of `A`
+    #[cfg(any(
+        feature = "json",
+        feature = "msgpack",
+        feature = "ron",
+        feature = "toml",
+        feature = "yaml",
+        feature = "cbor"
+    ))]
+    fn migrate(
+        &self,
+        format: DataFormat,
+        bytes: &[u8],
+    ) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+        let mut document = read_document(format, bytes)?;
+        migrate::<A>(&mut document)?;
+        write_document(format, &document)
+    }
+
+    /// Xml and postcard documents can not be migrated
+    #[cfg(not(any(
+        feature = "json",
+        feature = "msgpack",
+        feature = "ron",
+        feature = "toml",
+        feature = "yaml",
+        feature = "cbor"
+    )))]
+    fn migrate(
+        &self,
+        format: DataFormat,
+        _bytes: &[u8],
+    ) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+        Err(format!("{format:?} documents can not be migrated").into())
+    }
+}
+
+impl<A> Process for MigrationProcessor<A>
+where
+    A: DataAsset + Migrate,
+{
+    type Settings = DataAssetSettings;
+    type OutputLoader = DataAssetLoader<A>;
+
+    async fn process(
+        &self,
+        context: &mut ProcessContext<'_>,
+        meta: AssetMeta<(), Self>,
+        writer: &mut Writer,
+    ) -> Result<DataAssetSettings, ProcessError> {
+        let AssetAction::Process { mut settings, .. } = meta.asset else {
+            return Err(ProcessError::WrongMetaType);
+        };
+        let bytes = context.asset_bytes();
+        #[cfg(any(feature = "postcard", feature = "cbor"))]
+        if crate::fingerprint::has_envelope(bytes) {
+            return Err(ProcessError::AssetTransformError(
+                DataLoaderError::Fingerprinted.into(),
+            ));
+        }
+        let format = settings
+            .format
+            .or_else(|| detect_format(bytes))
+            .ok_or_else(|| {
+                ProcessError::AssetTransformError(DataLoaderError::UnknownFormat.into())
+            })?;
+        let bytes = self
+            .migrate(format, bytes)
+            .map_err(ProcessError::AssetTransformError)?;
+        writer
+            .write_all(&bytes)
+            .await
+            .map_err(|error| ProcessError::AssetSaveError(error.into()))?;
+        settings.format = Some(format);
+        Ok(settings)
+    }
+}
+
+/// The formats a file could be in, most likely first
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Candidate {
+    Json,
+    Ron,
+    Toml,
+    Yaml,
+    MsgPack,
+    Cbor,
+    Xml,
+}
+
+impl Candidate {
+    fn enabled(self) -> Option<DataFormat> {
+        match self {
+            #[cfg(feature = "json")]
+            Candidate::Json => Some(DataFormat::Json),
+            #[cfg(feature = "ron")]
+            Candidate::Ron => Some(DataFormat::Ron),
+            #[cfg(feature = "toml")]
+            Candidate::Toml => Some(DataFormat::Toml),
+            #[cfg(feature = "yaml")]
+            Candidate::Yaml => Some(DataFormat::Yaml),
+            #[cfg(feature = "msgpack")]
+            Candidate::MsgPack => Some(DataFormat::MsgPack),
+            #[cfg(feature = "cbor")]
+            Candidate::Cbor => Some(DataFormat::Cbor),
+            #[cfg(feature = "xml")]
+            Candidate::Xml => Some(DataFormat::Xml),
+            #[cfg(not(all(
+                feature = "json",
+                feature = "ron",
+                feature = "toml",
+                feature = "yaml",
+                feature = "msgpack",
+                feature = "cbor",
+                feature = "xml"
+            )))]
+            _ => None,
+        }
+    }
+}
+
+/// Detect the format of a file from its first bytes
+///
+/// Returns the most likely of the enabled formats.
+pub fn detect_format(bytes: &[u8]) -> Option<DataFormat> {
+    candidates(bytes)
+        .iter()
+        .find_map(|candidate| candidate.enabled())
+}
+
+fn candidates(bytes: &[u8]) -> Vec<Candidate> {
+    use Candidate::*;
+
+    let bytes = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes);
+    match bytes.first() {
+        None => return vec![],
+        // CBOR self-describe tag
+        Some(0xd9) if bytes.starts_with(b"\xd9\xd9\xf7") => return vec![Cbor],
+        // CBOR maps; fixstr in MessagePack, which is unlikely as document
+        Some(0xa0..=0xbf) => return vec![Cbor, MsgPack],
+        // MessagePack fixmap, map 16 and map 32; CBOR arrays overlap with the fixmap
+        Some(0x80..=0x8f | 0xde | 0xdf) => return vec![MsgPack, Cbor],
+        // MessagePack fixarray, array 16 and array 32
+        Some(0x90..=0x9f | 0xdc | 0xdd) => return vec![MsgPack, Cbor],
+        Some(byte) if !byte.is_ascii() => return vec![],
+        _ => {}
+    }
+    let Ok(text) = std::str::from_utf8(bytes) else {
+        return vec![];
+    };
+    let text = skip_comments(text);
+    if text.starts_with('<') {
+        vec![Xml]
+    } else if text.starts_with("---") || text.starts_with("%YAML") {
+        vec![Yaml]
+    } else if text.starts_with("#![enable") || text.starts_with('(') {
+        vec![Ron]
+    } else if text.starts_with('{') {
+        if is_json(text) {
+            vec![Json, Ron, Yaml]
+        } else {
+            vec![Ron, Json, Yaml]
+        }
+    } else if text.starts_with('[') {
+        if is_toml_header(first_line(text)) && !is_json(text) {
+            vec![Toml, Json, Ron, Yaml]
+        } else {
+            vec![Json, Ron, Yaml, Toml]
+        }
+    } else if is_named_struct(text) {
+        vec![Ron]
+    } else if is_toml_key_value(first_line(text)) {
+        vec![Toml, Yaml]
+    } else {
+        vec![Yaml, Toml, Ron, Json]
+    }
+}
+
+/// Skip whitespace and comments of all text formats at the start of `text`
+fn skip_comments(mut text: &str) -> &str {
+    loop {
+        text = text.trim_start();
+        if text.starts_with("//") || (text.starts_with('#') && !text.starts_with("#!")) {
+            text = text.split_once('\n').map_or("", |(_, rest)| rest);
+        } else if let Some(comment) = text.strip_prefix("/*") {
+            text = comment.split_once("*/").map_or("", |(_, rest)| rest);
+        } else {
+            return text;
+        }
+    }
+}
+
+fn first_line(text: &str) -> &str {
+    text.lines().next().unwrap_or_default().trim()
+}
+
+fn is_json(text: &str) -> bool {
+    #[cfg(feature = "json")]
+    {
+        serde_json::from_str::<serde::de::IgnoredAny>(text).is_ok()
+    }
+    #[cfg(not(feature = "json"))]
+    {
+        let _ = text;
+        false
+    }
+}
+
+/// Whether `line` is a toml table header like `[table]` or `[[array.of.tables]]`
+fn is_toml_header(line: &str) -> bool {
+    let line = line
+        .split_once('#')
+        .map_or(line, |(header, _)| header)
+        .trim();
+    let name = line
+        .strip_prefix("[[")
+        .and_then(|name| name.strip_suffix("]]"))
+        .or_else(|| {
+            line.strip_prefix('[')
+                .and_then(|name| name.strip_suffix(']'))
+        });
+    name.is_some_and(|name| {
+        !name.trim().is_empty()
+            && name
+                .chars()
+                .all(|character| character.is_ascii_alphanumeric() || "_-.\"' ".contains(character))
+    })
+}
+
+/// Whether `line` is a toml key value pair like `key = value`
+fn is_toml_key_value(line: &str) -> bool {
+    line.split_once('=').is_some_and(|(key, _)| {
+        let key = key.trim();
+        !key.is_empty()
+            && key
+                .chars()
+                .all(|character| character.is_ascii_alphanumeric() || "_-.\"' ".contains(character))
+    })
+}
+
+/// Whether `text` starts with a RON named struct or tuple like `Level(`
+fn is_named_struct(text: &str) -> bool {
+    let name_length = text
+        .find(|character: char| !(character.is_alphanumeric() || character == '_'))
+        .unwrap_or(text.len());
+    name_length > 0 && text[name_length..].trim_start().starts_with('(')
+}
+
+/// Read a document in `format` into a [`Value`] tree
+#[cfg(any(
+    feature = "json",
+    feature = "msgpack",
+    feature = "ron",
+    feature = "toml",
+    feature = "yaml",
+    feature = "cbor"
+))]
+pub(crate) fn read_document(
+    format: DataFormat,
+    bytes: &[u8],
+) -> Result<Value, Box<dyn std::error::Error + Send + Sync>> {
+    Ok(match format {
+        #[cfg(feature = "json")]
+        DataFormat::Json => serde_json::from_slice(bytes)?,
+        #[cfg(feature = "ron")]
+        DataFormat::Ron => crate::ron::read_value(bytes)?,
+        #[cfg(feature = "toml")]
+        DataFormat::Toml => serde_toml::from_str(std::str::from_utf8(bytes)?)?,
+        #[cfg(feature = "yaml")]
+        DataFormat::Yaml => serde_yaml::from_slice(bytes)?,
+        #[cfg(feature = "msgpack")]
+        DataFormat::MsgPack => rmp_serde::from_slice(bytes)?,
+        #[cfg(feature = "cbor")]
+        DataFormat::Cbor => ciborium::from_reader(bytes)?,
+        #[cfg(any(feature = "xml", feature = "postcard"))]
+        format => return Err(format!("{format:?} documents can not be read as value tree").into()),
+    })
+}
+
+/// Write a [`Value`] tree as document in `format`
+#[cfg(any(
+    feature = "json",
+    feature = "msgpack",
+    feature = "ron",
+    feature = "toml",
+    feature = "yaml",
+    feature = "cbor"
+))]
+pub(crate) fn write_document(
+    format: DataFormat,
+    document: &Value,
+) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+    Ok(match format {
+        #[cfg(feature = "json")]
+        DataFormat::Json => serde_json::to_vec_pretty(document)?,
+        #[cfg(feature = "ron")]
+        DataFormat::Ron => crate::ron::write_value(document)?.into_bytes(),
+        #[cfg(feature = "toml")]
+        DataFormat::Toml => serde_toml::to_string_pretty(document)?.into_bytes(),
+        #[cfg(feature = "yaml")]
+        DataFormat::Yaml => serde_yaml::to_string(document)?.into_bytes(),
+        #[cfg(feature = "msgpack")]
+        DataFormat::MsgPack => rmp_serde::to_vec_named(document)?,
+        #[cfg(feature = "cbor")]
+        DataFormat::Cbor => {
+            let mut bytes = vec![];
+            ciborium::into_writer(document, &mut bytes)?;
+            bytes
+        }
+        #[cfg(any(feature = "xml", feature = "postcard"))]
+        format => {
+            return Err(format!("{format:?} documents can not be written from value tree").into());
+        }
+    })
+}
+
+/// Read a document in `format` as value of type `T`
+pub(crate) fn read_definition<T: DeserializeOwned>(
+    format: DataFormat,
+    bytes: &[u8],
+) -> Result<T, Box<dyn std::error::Error + Send + Sync>> {
+    Ok(match format {
+        #[cfg(feature = "json")]
+        DataFormat::Json => serde_json::from_slice(bytes)?,
+        #[cfg(feature = "ron")]
+        DataFormat::Ron => serde_ron::de::from_bytes(bytes)?,
+        #[cfg(feature = "toml")]
+        DataFormat::Toml => serde_toml::from_str(std::str::from_utf8(bytes)?)?,
+        #[cfg(feature = "yaml")]
+        DataFormat::Yaml => serde_yaml::from_slice(bytes)?,
+        #[cfg(feature = "msgpack")]
+        DataFormat::MsgPack => rmp_serde::from_slice(bytes)?,
+        #[cfg(feature = "cbor")]
+        DataFormat::Cbor => ciborium::from_reader(bytes)?,
+        #[cfg(feature = "xml")]
+        DataFormat::Xml => quick_xml::de::from_str(std::str::from_utf8(bytes)?)?,
+        #[cfg(feature = "postcard")]
+        DataFormat::Postcard => postcard::from_bytes(bytes)?,
+    })
+}
+
+/// Write the definition of an asset as document in `format`
+pub(crate) fn write_definition<T: Serialize + ?Sized>(
+    format: DataFormat,
+    definition: &T,
+) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+    Ok(match format {
+        #[cfg(feature = "json")]
+        DataFormat::Json => serde_json::to_vec_pretty(definition)?,
+        #[cfg(feature = "ron")]
+        DataFormat::Ron => {
+            serde_ron::ser::to_string_pretty(definition, serde_ron::ser::PrettyConfig::default())?
+                .into_bytes()
+        }
+        #[cfg(feature = "toml")]
+        DataFormat::Toml => serde_toml::to_string_pretty(definition)?.into_bytes(),
+        #[cfg(feature = "yaml")]
+        DataFormat::Yaml => serde_yaml::to_string(definition)?.into_bytes(),
+        #[cfg(feature = "msgpack")]
+        DataFormat::MsgPack => rmp_serde::to_vec_named(definition)?,
+        #[cfg(feature = "cbor")]
+        DataFormat::Cbor => {
+            let mut bytes = vec![];
+            ciborium::into_writer(definition, &mut bytes)?;
+            bytes
+        }
+        #[cfg(feature = "xml")]
+        DataFormat::Xml => quick_xml::se::to_string(definition)?.into_bytes(),
+        #[cfg(feature = "postcard")]
+        DataFormat::Postcard => postcard::to_stdvec(definition)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Candidate::*;
+    use super::*;
+
+    #[test]
+    fn binary_formats_are_detected_by_their_markers() {
+        assert_eq!(candidates(b"\xd9\xd9\xf7\xa1"), [Cbor]);
+        assert_eq!(candidates(b"\xa1\x62hp\x01"), [Cbor, MsgPack]);
+        assert_eq!(candidates(b"\x81\xa2hp\x01"), [MsgPack, Cbor]);
+        assert_eq!(candidates(b"\xde\x00\x01"), [MsgPack, Cbor]);
+        assert_eq!(candidates(b"\x92\x01\x02"), [MsgPack, Cbor]);
+        assert_eq!(candidates(b"\xff\x00"), []);
+        assert_eq!(candidates(b""), []);
+    }
+
+    #[test]
+    fn text_formats_are_detected_by_their_start() {
+        assert_eq!(candidates(b"<?xml version=\"1.0\"?><level/>"), [Xml]);
+        assert_eq!(candidates(b"---\nhp: 1\n"), [Yaml]);
+        assert_eq!(candidates(b"%YAML 1.2\n---\n"), [Yaml]);
+        assert_eq!(candidates(b"#![enable(implicit_some)]\n(hp: 1)"), [Ron]);
+        assert_eq!(candidates(b"(hp: 1)"), [Ron]);
+        assert_eq!(candidates(b"Level(hp: 1)"), [Ron]);
+        assert_eq!(candidates(b"Level (hp: 1)"), [Ron]);
+        assert_eq!(candidates(b"hp = 1\n"), [Toml, Yaml]);
+        assert_eq!(candidates(b"\"hp\" = 1\n"), [Toml, Yaml]);
+        assert_eq!(candidates(b"[level]\nhp = 1\n"), [Toml, Json, Ron, Yaml]);
+        assert_eq!(
+            candidates(b"[[enemies]] # all\nhp = 1\n"),
+            [Toml, Json, Ron, Yaml]
+        );
+        assert_eq!(candidates(b"[1, 2]"), [Json, Ron, Yaml, Toml]);
+        assert_eq!(candidates(b"hp: 1\n"), [Yaml, Toml, Ron, Json]);
+        assert_eq!(candidates(b"- 1\n- 2\n"), [Yaml, Toml, Ron, Json]);
+    }
+
+    #[test]
+    fn comments_and_byte_order_marks_are_skipped() {
+        assert_eq!(candidates(b"\xEF\xBB\xBF(hp: 1)"), [Ron]);
+        assert_eq!(candidates(b"// level\n/* hp */ (hp: 1)"), [Ron]);
+        assert_eq!(candidates(b"# level\n\nhp = 1\n"), [Toml, Yaml]);
+        assert_eq!(candidates(b"  # level\nhp: 1\n"), [Yaml, Toml, Ron, Json]);
+    }
+
+    #[test]
+    fn braces_are_json_if_they_parse_as_json() {
+        let valid = b"{\"hp\": 1}";
+        let invalid = b"{hp: 1}";
+        assert_eq!(candidates(invalid), [Ron, Json, Yaml]);
+        #[cfg(feature = "json")]
+        assert_eq!(candidates(valid), [Json, Ron, Yaml]);
+        #[cfg(not(feature = "json"))]
+        assert_eq!(candidates(valid), [Ron, Json, Yaml]);
+    }
+
+    #[test]
+    fn the_most_likely_enabled_format_is_detected() {
+        assert_eq!(detect_format(b""), None);
+        #[cfg(feature = "ron")]
+        assert_eq!(detect_format(b"{hp: 1}"), Some(DataFormat::Ron));
+        #[cfg(all(feature = "json", not(feature = "ron")))]
+        assert_eq!(detect_format(b"{hp: 1}"), Some(DataFormat::Json));
+        #[cfg(feature = "toml")]
+        assert_eq!(detect_format(b"hp = 1"), Some(DataFormat::Toml));
+        #[cfg(all(feature = "yaml", not(feature = "toml")))]
+        assert_eq!(detect_format(b"hp = 1"), Some(DataFormat::Yaml));
+        #[cfg(not(any(feature = "toml", feature = "yaml")))]
+        assert_eq!(detect_format(b"hp = 1"), None);
+        #[cfg(feature = "cbor")]
+        assert_eq!(detect_format(b"\xa1\x62hp\x01"), Some(DataFormat::Cbor));
+        #[cfg(all(feature = "msgpack", not(feature = "cbor")))]
+        assert_eq!(detect_format(b"\xa1\x62hp\x01"), Some(DataFormat::MsgPack));
+    }
+}
@@ -0,0 +1,157 @@
+use bevy_app::App;
+use bevy_asset::{Asset, AssetApp, Assets, LoadContext};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::future::Future;
+
+/// Error returned by [`DataAsset::from_definition`]
+///
+/// Any error can be converted into it with `?`, and messages with `.into()`.
+pub type DefinitionError = Box<dyn Error + Send + Sync>;
+
+/// Asset types that can be loaded by the format plugins of this crate
+///
+/// The loaders deserialize the [`Definition`](DataAsset::Definition) of the asset and create the
+/// asset from it with [`DataAsset::from_definition`]. Every asset type implementing
+/// [`Deserialize`] is its own definition, so this trait rarely needs to be implemented by hand.
+/// The collection assets in [`collection`](crate::collection) use it to add every item as
+/// labeled sub-asset.
+///
+/// Implement it when the on-disk shape differs from the runtime asset, e.g. for definitions
+/// with asset paths that become handles. The conversion has access to the [`LoadContext`] to
+/// load dependencies, add labeled sub-assets or read other files. To save such assets with the
+/// postcard or cbor savers, also implement [`ToDefinition`].
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_common_assets::{DataAsset, DefinitionError};
+/// #[derive(serde::Deserialize)]
+/// struct LevelDefinition {
+///     ground: String,
+///     description: String,
+/// }
+///
+/// #[derive(Asset, TypePath)]
+/// struct Level {
+///     ground: Handle<Image>,
+///     description: String,
+/// }
+///
+/// impl DataAsset for Level {
+///     type Definition = LevelDefinition;
+///
+///     async fn from_definition(
+///         definition: LevelDefinition,
+///         load_context: &mut bevy::asset::LoadContext<'_>,
+///     ) -> Result<Self, DefinitionError> {
+///         let description = load_context.read_asset_bytes(definition.description).await?;
+///         Ok(Level {
+///             ground: load_context.load(definition.ground),
+///             description: String::from_utf8(description)?,
+///         })
+///     }
+/// }
+/// ```
+pub trait DataAsset: Asset + Sized {
+    /// The type deserialized from asset files
+    type Definition: for<'de> Deserialize<'de> + Send;
+
+    /// Create the asset from its deserialized definition
+    ///
+    /// Failing the conversion fails loading the asset.
+    fn from_definition(
+        definition: Self::Definition,
+        load_context: &mut LoadContext<'_>,
+    ) -> impl Future<Output = Result<Self, DefinitionError>> + Send;
+
+    /// Initialize the asset types that [`from_definition`](DataAsset::from_definition) adds as
+    /// labeled sub-assets
+    ///
+    /// The format plugins call this when they are built. The collection assets initialize
+    /// their item type here, so items can be loaded without a plugin of their own.
+    fn init_sub_assets(_app: &mut App) {}
+}
+
+/// Initialize the asset type `A` and the asset types of its labeled sub-assets
+pub(crate) fn init_data_asset<A: DataAsset>(app: &mut App) {
+    if !app.world().contains_resource::<Assets<A>>() {
+        app.init_asset::<A>();
+    }
+    A::init_sub_assets(app);
+}
+
+impl<A> DataAsset for A
+where
+    for<'de> A: Deserialize<'de> + Asset,
+{
+    type Definition = A;
+
+    async fn from_definition(
+        definition: Self::Definition,
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self, DefinitionError> {
+        Ok(definition)
+    }
+}
+
+/// Asset types that can be saved by the postcard and cbor savers of this crate
+///
+/// Savers serialize the definition returned by [`ToDefinition::to_definition`], which the
+/// loader of the saved files deserializes as [`DataAsset::Definition`] again. Every asset type
+/// implementing [`Serialize`] is its own definition.
+///
+/// Asset processors loading an asset through its definition and saving it again therefore
+/// work for assets that are created from a different definition type as well.
+pub trait ToDefinition: DataAsset {
+    /// The definition to save for this asset
+    fn to_definition(&self) -> impl Serialize + '_;
+}
+
+impl<A> ToDefinition for A
+where
+    for<'de> A: Serialize + Deserialize<'de> + Asset,
+{
+    fn to_definition(&self) -> impl Serialize + '_ {
+        self
+    }
+}
+
+/// Asset types that check their values after deserializing
+///
+/// Values that deserialize fine can still be invalid, like negative health or duplicate IDs.
+/// Enable the check with `with_asset_validation` on the format plugins; loaders then call
+/// [`ValidateAsset::validate`] on every loaded asset and handle invalid assets according to the
+/// [`ValidationMode`]. The postcard and cbor savers refuse to save invalid assets, so asset
+/// processing fails for them.
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_common_assets::ValidateAsset;
+/// #[derive(serde::Deserialize, Asset, TypePath)]
+/// struct Unit {
+///     health: i32,
+/// }
+///
+/// impl ValidateAsset for Unit {
+///     fn validate(&self) -> Result<(), String> {
+///         if self.health <= 0 {
+///             return Err(format!("health must be positive, but is {}", self.health));
+///         }
+///         Ok(())
+///     }
+/// }
+/// ```
+pub trait ValidateAsset: Asset {
+    /// Check the asset and describe all problems if it is invalid
+    fn validate(&self) -> Result<(), String>;
+}
+
+/// How loaders handle assets failing their [`ValidateAsset`] check
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ValidationMode {
+    /// Fail loading the asset
+    #[default]
+    Fail,
+    /// Log a warning and load the asset anyway
+    Warn,
+}
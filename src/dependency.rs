@@ -0,0 +1,80 @@
+use bevy_app::App;
+use bevy_asset::io::{Reader, SliceReader};
+use bevy_asset::{
+    Asset, AssetApp, AssetLoader, AssetPath, AssetServer, AssetServerMode, Assets, LoadContext,
+    LoadDirectError,
+};
+use bevy_reflect::TypePath;
+
+/// Watches files that documents would read if they existed, like missing overlay layers and
+/// variants
+///
+/// Missing files are made loader dependencies of the loaded document, so creating them reloads
+/// it. This is only done if the asset server watches unprocessed assets for changes. Processing
+/// compares the hashes of the loader dependencies, which missing files do not have.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct MissingFiles {
+    watch: bool,
+}
+
+impl MissingFiles {
+    /// Watch missing files if the asset server of `app` reloads changed unprocessed assets
+    pub(crate) fn new(app: &mut App) -> Self {
+        let watch = app
+            .world()
+            .get_resource::<AssetServer>()
+            .is_some_and(|server| {
+                server.watching_for_changes() && server.mode() == AssetServerMode::Unprocessed
+            });
+        if watch && !app.world().contains_resource::<Assets<MissingFile>>() {
+            app.init_asset::<MissingFile>()
+                .register_asset_loader(MissingFileLoader);
+        }
+        MissingFiles { watch }
+    }
+
+    /// Make the missing file at `path` a loader dependency of the loaded document
+    pub(crate) async fn watch(
+        &self,
+        load_context: &mut LoadContext<'_>,
+        path: &AssetPath<'_>,
+    ) -> Result<(), Box<LoadDirectError>> {
+        if !self.watch {
+            return Ok(());
+        }
+        // Bevy only adds loader dependencies for files it could read, so an empty placeholder
+        // is loaded at the path instead
+        let reader = &mut SliceReader::new(&[]);
+        load_context
+            .loader()
+            .immediate()
+            .with_reader(reader)
+            .load::<MissingFile>(path.clone_owned())
+            .await
+            .map(drop)
+            .map_err(Box::new)
+    }
+}
+
+/// Placeholder for a file that does not exist
+#[derive(Asset, TypePath)]
+struct MissingFile;
+
+/// Loads [`MissingFile`]s from empty readers
+#[derive(TypePath)]
+struct MissingFileLoader;
+
+impl AssetLoader for MissingFileLoader {
+    type Asset = MissingFile;
+    type Settings = ();
+    type Error = std::io::Error;
+
+    async fn load(
+        &self,
+        _reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<MissingFile, std::io::Error> {
+        Ok(MissingFile)
+    }
+}
@@ -0,0 +1,302 @@
+//! Support for the derive macro, which expands to calls of these macros.
+//!
+//! Every macro calls a method of `CommonAssetsPlugin` if the feature of the format is enabled and
+//! fails to compile with a clear error otherwise.
+//!
+//! Deriving `CommonAsset` with a format compiles exactly if its feature is enabled:
+//!
+#![cfg_attr(feature = "json", doc = "```no_run")]
+#![cfg_attr(not(feature = "json"), doc = "```compile_fail")]
+//! # use bevy::prelude::*;
+//! # use bevy_common_assets::CommonAssetApp;
+//! #[derive(serde::Deserialize, serde::Serialize, bevy_common_assets::CommonAsset)]
+//! #[common_asset(json = "level.json")]
+//! struct Level;
+//!
+//! App::new().add_common_asset::<Level>();
+//! ```
+//!
+#![cfg_attr(feature = "ron", doc = "```no_run")]
+#![cfg_attr(not(feature = "ron"), doc = "```compile_fail")]
+//! # use bevy::prelude::*;
+//! # use bevy_common_assets::CommonAssetApp;
+//! #[derive(serde::Deserialize, serde::Serialize, bevy_common_assets::CommonAsset)]
+//! #[common_asset(ron = "level.ron")]
+//! struct Level;
+//!
+//! App::new().add_common_asset::<Level>();
+//! ```
+//!
+#![cfg_attr(feature = "toml", doc = "```no_run")]
+#![cfg_attr(not(feature = "toml"), doc = "```compile_fail")]
+//! # use bevy::prelude::*;
+//! # use bevy_common_assets::CommonAssetApp;
+//! #[derive(serde::Deserialize, serde::Serialize, bevy_common_assets::CommonAsset)]
+//! #[common_asset(toml = "level.toml")]
+//! struct Level;
+//!
+//! App::new().add_common_asset::<Level>();
+//! ```
+//!
+#![cfg_attr(feature = "yaml", doc = "```no_run")]
+#![cfg_attr(not(feature = "yaml"), doc = "```compile_fail")]
+//! # use bevy::prelude::*;
+//! # use bevy_common_assets::CommonAssetApp;
+//! #[derive(serde::Deserialize, serde::Serialize, bevy_common_assets::CommonAsset)]
+//! #[common_asset(yaml = "level.yaml")]
+//! struct Level;
+//!
+//! App::new().add_common_asset::<Level>();
+//! ```
+//!
+#![cfg_attr(feature = "msgpack", doc = "```no_run")]
+#![cfg_attr(not(feature = "msgpack"), doc = "```compile_fail")]
+//! # use bevy::prelude::*;
+//! # use bevy_common_assets::CommonAssetApp;
+//! #[derive(serde::Deserialize, serde::Serialize, bevy_common_assets::CommonAsset)]
+//! #[common_asset(msgpack = "level.msgpack")]
+//! struct Level;
+//!
+//! App::new().add_common_asset::<Level>();
+//! ```
+//!
+#![cfg_attr(feature = "xml", doc = "```no_run")]
+#![cfg_attr(not(feature = "xml"), doc = "```compile_fail")]
+//! # use bevy::prelude::*;
+//! # use bevy_common_assets::CommonAssetApp;
+//! #[derive(serde::Deserialize, serde::Serialize, bevy_common_assets::CommonAsset)]
+//! #[common_asset(xml = "level.xml")]
+//! struct Level;
+//!
+//! App::new().add_common_asset::<Level>();
+//! ```
+//!
+#![cfg_attr(feature = "postcard", doc = "```no_run")]
+#![cfg_attr(not(feature = "postcard"), doc = "```compile_fail")]
+//! # use bevy::prelude::*;
+//! # use bevy_common_assets::CommonAssetApp;
+//! #[derive(serde::Deserialize, serde::Serialize, bevy_common_assets::CommonAsset)]
+//! #[common_asset(postcard = "level.pc")]
+//! struct Level;
+//!
+//! App::new().add_common_asset::<Level>();
+//! ```
+//!
+#![cfg_attr(feature = "cbor", doc = "```no_run")]
+#![cfg_attr(not(feature = "cbor"), doc = "```compile_fail")]
+//! # use bevy::prelude::*;
+//! # use bevy_common_assets::CommonAssetApp;
+//! #[derive(serde::Deserialize, serde::Serialize, bevy_common_assets::CommonAsset)]
+//! #[common_asset(cbor = "level.cbor")]
+//! struct Level;
+//!
+//! App::new().add_common_asset::<Level>();
+//! ```
+//!
+#![cfg_attr(feature = "postcard", doc = "```no_run")]
+#![cfg_attr(not(feature = "postcard"), doc = "```compile_fail")]
+//! # use bevy::prelude::*;
+//! # use bevy_common_assets::CommonAssetApp;
+//! #[derive(serde::Deserialize, serde::Serialize, bevy_common_assets::CommonAsset)]
+//! #[common_asset(postcard = "level.pc", saver = "postcard")]
+//! struct Level;
+//!
+//! App::new().add_common_asset::<Level>();
+//! ```
+//!
+#![cfg_attr(feature = "cbor", doc = "```no_run")]
+#![cfg_attr(not(feature = "cbor"), doc = "```compile_fail")]
+//! # use bevy::prelude::*;
+//! # use bevy_common_assets::CommonAssetApp;
+//! #[derive(serde::Deserialize, serde::Serialize, bevy_common_assets::CommonAsset)]
+//! #[common_asset(cbor = "level.cbor", saver = "cbor")]
+//! struct Level;
+//!
+//! App::new().add_common_asset::<Level>();
+//! ```
+
+#[doc(hidden)]
+#[macro_export]
+#[cfg(feature = "json")]
+macro_rules! __common_asset_json {
+    ($plugin:expr, $extension:expr) => {
+        $plugin.json(&[$extension])
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+#[cfg(not(feature = "json"))]
+macro_rules! __common_asset_json {
+    ($plugin:expr, $extension:expr) => {
+        ::core::compile_error!("loading `CommonAsset`s from json files requires the `json` feature of bevy_common_assets")
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+#[cfg(feature = "ron")]
+macro_rules! __common_asset_ron {
+    ($plugin:expr, $extension:expr) => {
+        $plugin.ron(&[$extension])
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+#[cfg(not(feature = "ron"))]
+macro_rules! __common_asset_ron {
+    ($plugin:expr, $extension:expr) => {
+        ::core::compile_error!(
+            "loading `CommonAsset`s from ron files requires the `ron` feature of bevy_common_assets"
+        )
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+#[cfg(feature = "toml")]
+macro_rules! __common_asset_toml {
+    ($plugin:expr, $extension:expr) => {
+        $plugin.toml(&[$extension])
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+#[cfg(not(feature = "toml"))]
+macro_rules! __common_asset_toml {
+    ($plugin:expr, $extension:expr) => {
+        ::core::compile_error!("loading `CommonAsset`s from toml files requires the `toml` feature of bevy_common_assets")
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+#[cfg(feature = "yaml")]
+macro_rules! __common_asset_yaml {
+    ($plugin:expr, $extension:expr) => {
+        $plugin.yaml(&[$extension])
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+#[cfg(not(feature = "yaml"))]
+macro_rules! __common_asset_yaml {
+    ($plugin:expr, $extension:expr) => {
+        ::core::compile_error!("loading `CommonAsset`s from yaml files requires the `yaml` feature of bevy_common_assets")
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+#[cfg(feature = "msgpack")]
+macro_rules! __common_asset_msgpack {
+    ($plugin:expr, $extension:expr) => {
+        $plugin.msgpack(&[$extension])
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+#[cfg(not(feature = "msgpack"))]
+macro_rules! __common_asset_msgpack {
+    ($plugin:expr, $extension:expr) => {
+        ::core::compile_error!("loading `CommonAsset`s from msgpack files requires the `msgpack` feature of bevy_common_assets")
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+#[cfg(feature = "xml")]
+macro_rules! __common_asset_xml {
+    ($plugin:expr, $extension:expr) => {
+        $plugin.xml(&[$extension])
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+#[cfg(not(feature = "xml"))]
+macro_rules! __common_asset_xml {
+    ($plugin:expr, $extension:expr) => {
+        ::core::compile_error!(
+            "loading `CommonAsset`s from xml files requires the `xml` feature of bevy_common_assets"
+        )
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+#[cfg(feature = "postcard")]
+macro_rules! __common_asset_postcard {
+    ($plugin:expr, $extension:expr) => {
+        $plugin.postcard(&[$extension])
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+#[cfg(not(feature = "postcard"))]
+macro_rules! __common_asset_postcard {
+    ($plugin:expr, $extension:expr) => {
+        ::core::compile_error!("loading `CommonAsset`s from postcard files requires the `postcard` feature of bevy_common_assets")
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+#[cfg(feature = "cbor")]
+macro_rules! __common_asset_cbor {
+    ($plugin:expr, $extension:expr) => {
+        $plugin.cbor(&[$extension])
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+#[cfg(not(feature = "cbor"))]
+macro_rules! __common_asset_cbor {
+    ($plugin:expr, $extension:expr) => {
+        ::core::compile_error!("loading `CommonAsset`s from cbor files requires the `cbor` feature of bevy_common_assets")
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+#[cfg(feature = "postcard")]
+macro_rules! __common_asset_postcard_saver {
+    ($plugin:expr) => {
+        $plugin.postcard_saver()
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+#[cfg(not(feature = "postcard"))]
+macro_rules! __common_asset_postcard_saver {
+    ($plugin:expr) => {
+        ::core::compile_error!("saving `CommonAsset`s as postcard requires the `postcard` feature of bevy_common_assets")
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+#[cfg(feature = "cbor")]
+macro_rules! __common_asset_cbor_saver {
+    ($plugin:expr) => {
+        $plugin.cbor_saver()
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+#[cfg(not(feature = "cbor"))]
+macro_rules! __common_asset_cbor_saver {
+    ($plugin:expr) => {
+        ::core::compile_error!(
+            "saving `CommonAsset`s as cbor requires the `cbor` feature of bevy_common_assets"
+        )
+    };
+}
@@ -0,0 +1,1431 @@
+use crate::data::{DataFormat, read_document, write_definition};
+use crate::value::{Difference, Value, diff};
+use serde::Serialize;
+#[cfg(any(feature = "ron", feature = "yaml"))]
+use std::ops::Range;
+use thiserror::Error;
+
+/// Apply the values of `definition` to the text of a document in `format`, changing as little
+/// of the text as possible
+///
+/// The document and the definition are read into [`Value`] trees and only the fields that
+/// differ between them are rewritten, added or removed. Comments, key order and the formatting
+/// of all other values stay as they are. Rewritten values are formatted like the serializer
+/// of the format writes them and indented to fit in.
+///
+/// TOML documents are edited with `toml_edit`. RON and YAML documents are edited in their
+/// text; a change inside a YAML flow collection like `[1, 2]` rewrites the whole collection.
+/// RON, TOML and YAML are supported.
+///
+/// ```
+/// # #[cfg(feature = "toml")]
+/// # {
+/// # use bevy_common_assets::data::DataFormat;
+/// # use bevy_common_assets::edit::edit_document;
+/// #[derive(serde::Serialize)]
+/// struct Audio {
+///     volume: f64,
+///     device: String,
+/// }
+///
+/// let source = "# Mixer settings\nvolume = 0.5 # between 0 and 1\ndevice = \"default\"\n";
+/// let audio = Audio {
+///     volume: 0.8,
+///     device: "default".to_owned(),
+/// };
+/// assert_eq!(
+///     edit_document(DataFormat::Toml, source, &audio).unwrap(),
+///     "# Mixer settings\nvolume = 0.8 # between 0 and 1\ndevice = \"default\"\n"
+/// );
+/// # }
+/// ```
+pub fn edit_document<T: Serialize + ?Sized>(
+    format: DataFormat,
+    source: &str,
+    definition: &T,
+) -> Result<String, EditError> {
+    if !supports(format) {
+        return Err(EditError::Unsupported(format));
+    }
+    let written = write_definition(format, definition)
+        .map_err(|error| EditError::Serialize(error.to_string()))?;
+    let written =
+        String::from_utf8(written).map_err(|error| EditError::Serialize(error.to_string()))?;
+    edit_text(format, source, &written)
+}
+
+/// Possible errors when editing a document
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum EditError {
+    /// Documents of the format can not be edited
+    #[error("{0:?} documents can not be edited in place")]
+    Unsupported(DataFormat),
+    /// The definition could not be serialized
+    #[error("Could not serialize the definition: {0}")]
+    Serialize(String),
+    /// The document could not be parsed
+    #[error("Could not parse the document: {0}")]
+    Parse(String),
+    /// The document uses syntax the editor does not understand
+    #[error("The document uses syntax that can not be edited in place")]
+    Layout,
+}
+
+/// Whether documents in `format` can be edited in place
+pub(crate) fn supports(format: DataFormat) -> bool {
+    match format {
+        #[cfg(feature = "ron")]
+        DataFormat::Ron => true,
+        #[cfg(feature = "toml")]
+        DataFormat::Toml => true,
+        #[cfg(feature = "yaml")]
+        DataFormat::Yaml => true,
+        #[cfg(any(
+            feature = "json",
+            feature = "msgpack",
+            feature = "cbor",
+            feature = "xml",
+            feature = "postcard"
+        ))]
+        _ => false,
+    }
+}
+
+/// Edit the document `source` to have the values of the document `written` in the same format
+pub(crate) fn edit_text(
+    format: DataFormat,
+    source: &str,
+    written: &str,
+) -> Result<String, EditError> {
+    let parse =
+        |error: Box<dyn std::error::Error + Send + Sync>| EditError::Parse(error.to_string());
+    let old = read_document(format, source.as_bytes()).map_err(parse)?;
+    let new = read_document(format, written.as_bytes()).map_err(parse)?;
+    let changes = changes(&old, &new);
+    if changes.is_empty() {
+        return Ok(source.to_owned());
+    }
+    match format {
+        #[cfg(feature = "ron")]
+        DataFormat::Ron => Ok(TextEdit {
+            syntax: Syntax::Ron,
+            source,
+            old: ron_tree(source)?,
+            written,
+            new: ron_tree(written)?,
+        }
+        .apply(changes)),
+        #[cfg(feature = "toml")]
+        DataFormat::Toml => edit_toml(source, written, &changes),
+        #[cfg(feature = "yaml")]
+        DataFormat::Yaml => Ok(TextEdit {
+            syntax: Syntax::Yaml,
+            source,
+            old: YamlScanner::tree(source)?,
+            written,
+            new: YamlScanner::tree(written)?,
+        }
+        .apply(changes)),
+        #[cfg(any(
+            feature = "json",
+            feature = "msgpack",
+            feature = "cbor",
+            feature = "xml",
+            feature = "postcard"
+        ))]
+        format => Err(EditError::Unsupported(format)),
+    }
+}
+
+/// A difference between two value trees at a path of map keys and sequence indices
+#[derive(PartialEq)]
+enum Change {
+    /// The value at the path changed
+    Replace(Vec<String>),
+    /// The map entry at the path was added
+    Insert(Vec<String>),
+    /// The map entry at the path was removed
+    Remove(Vec<String>),
+}
+
+impl Change {
+    #[cfg(feature = "toml")]
+    fn path(&self) -> &[String] {
+        match self {
+            Change::Replace(path) | Change::Insert(path) | Change::Remove(path) => path,
+        }
+    }
+}
+
+/// Collect the changes turning `old` into `new`
+///
+/// Items added to or removed from the end of a sequence replace the whole sequence.
+fn changes(old: &Value, new: &Value) -> Vec<Change> {
+    let mut changes: Vec<Change> = vec![];
+    for Difference {
+        mut path,
+        old: before,
+        new: after,
+    } in diff(old, new)
+    {
+        let change = match (before, after) {
+            (Some(_), Some(_)) => Change::Replace(path),
+            _ if in_sequence(old, &path) => {
+                path.pop();
+                Change::Replace(path)
+            }
+            (None, _) => Change::Insert(path),
+            (_, None) => Change::Remove(path),
+        };
+        if changes.last() != Some(&change) {
+            changes.push(change);
+        }
+    }
+    changes
+}
+
+/// Whether the value at `path` is an item of a sequence in `tree`
+fn in_sequence(tree: &Value, path: &[String]) -> bool {
+    path.split_last()
+        .and_then(|(_, parent)| {
+            parent
+                .iter()
+                .try_fold(tree, |value, segment| value.child(segment))
+        })
+        .is_some_and(|parent| matches!(parent.unnamed(), Value::Seq(_)))
+}
+
+#[cfg(feature = "toml")]
+fn edit_toml(source: &str, written: &str, changes: &[Change]) -> Result<String, EditError> {
+    use toml_edit::DocumentMut;
+
+    let parse = |error: toml_edit::TomlError| EditError::Parse(error.to_string());
+    let mut document: DocumentMut = source.parse().map_err(parse)?;
+    let new: DocumentMut = written.parse().map_err(parse)?;
+    for change in changes {
+        let applied = match change {
+            Change::Replace(path) => {
+                toml_items::replace(document.as_item_mut(), new.as_item(), path)
+            }
+            Change::Insert(path) => toml_items::insert(document.as_item_mut(), new.as_item(), path),
+            Change::Remove(path) => toml_items::remove(document.as_item_mut(), path),
+        };
+        if applied {
+            continue;
+        }
+        // Replace the closest parent that exists in both documents instead
+        let mut path = change.path();
+        loop {
+            let Some((_, parent)) = path.split_last() else {
+                return Ok(written.to_owned());
+            };
+            path = parent;
+            if !path.is_empty() && toml_items::replace(document.as_item_mut(), new.as_item(), path)
+            {
+                break;
+            }
+        }
+    }
+    Ok(document.to_string())
+}
+
+#[cfg(feature = "toml")]
+mod toml_items {
+    use toml_edit::{Item, Value};
+
+    fn get<'a>(item: &'a Item, path: &[String]) -> Option<&'a Item> {
+        path.iter()
+            .try_fold(item, |item, segment| match segment.parse::<usize>() {
+                Ok(index) if item.is_array() || item.is_array_of_tables() => item.get(index),
+                _ => item.get(segment.as_str()),
+            })
+    }
+
+    fn get_mut<'a>(item: &'a mut Item, path: &[String]) -> Option<&'a mut Item> {
+        path.iter()
+            .try_fold(item, |item, segment| match segment.parse::<usize>() {
+                Ok(index) if item.is_array() || item.is_array_of_tables() => item.get_mut(index),
+                _ => item.get_mut(segment.as_str()),
+            })
+    }
+
+    /// Replace the item at `path` by the item of the new document, keeping its style and
+    /// surrounding comments
+    pub(super) fn replace(document: &mut Item, new: &Item, path: &[String]) -> bool {
+        let (Some(slot), Some(new)) = (get_mut(document, path), get(new, path)) else {
+            return false;
+        };
+        let mut replacement = match (&*slot, new.clone()) {
+            (Item::Value(_), new @ (Item::Table(_) | Item::ArrayOfTables(_))) => {
+                new.into_value().map(Item::Value).unwrap_or_else(|new| new)
+            }
+            (Item::Table(_), Item::Value(Value::InlineTable(table))) => {
+                Item::Table(table.into_table())
+            }
+            (Item::ArrayOfTables(_), new @ Item::Value(Value::Array(_))) => new
+                .into_array_of_tables()
+                .map(Item::ArrayOfTables)
+                .unwrap_or_else(|new| new),
+            (_, new) => new,
+        };
+        match (&*slot, &mut replacement) {
+            (Item::Value(old), Item::Value(new)) => *new.decor_mut() = old.decor().clone(),
+            (Item::Table(old), Item::Table(new)) => {
+                *new.decor_mut() = old.decor().clone();
+                new.set_position(old.position());
+            }
+            _ => {}
+        }
+        *slot = replacement;
+        true
+    }
+
+    /// Add the entry at `path` of the new document to its table
+    pub(super) fn insert(document: &mut Item, new: &Item, path: &[String]) -> bool {
+        let Some((key, parent)) = path.split_last() else {
+            return false;
+        };
+        let (Some(parent), Some(item)) = (get_mut(document, parent), get(new, path)) else {
+            return false;
+        };
+        match parent {
+            Item::Table(table) => {
+                table.insert(key, item.clone());
+                true
+            }
+            Item::Value(Value::InlineTable(table)) => match item.clone().into_value() {
+                Ok(mut value) => {
+                    // The space before the closing brace moves behind the new last value
+                    let suffix = table.iter_mut().last().and_then(|(_, last)| {
+                        let suffix = last.decor().suffix().cloned()?;
+                        last.decor_mut().set_suffix("");
+                        Some(suffix)
+                    });
+                    if let Some(suffix) = suffix {
+                        value.decor_mut().set_suffix(suffix);
+                    }
+                    table.insert(key, value);
+                    true
+                }
+                Err(_) => false,
+            },
+            _ => false,
+        }
+    }
+
+    /// Remove the entry at `path` from its table
+    pub(super) fn remove(document: &mut Item, path: &[String]) -> bool {
+        let Some((key, parent)) = path.split_last() else {
+            return false;
+        };
+        match get_mut(document, parent) {
+            Some(Item::Table(table)) => table.remove(key).is_some(),
+            Some(Item::Value(Value::InlineTable(table))) => table.remove(key).is_some(),
+            _ => false,
+        }
+    }
+}
+
+/// The position of a value in the text of a document
+#[cfg(any(feature = "ron", feature = "yaml"))]
+struct Node {
+    span: Range<usize>,
+    /// The entries of maps and items of sequences, `None` for other values
+    entries: Option<Vec<Entry>>,
+}
+
+/// A map entry or sequence item in the text of a document
+#[cfg(any(feature = "ron", feature = "yaml"))]
+struct Entry {
+    /// The map key or the index of the item
+    key: String,
+    /// Where the key or the item marker starts, if it could be found
+    start: Option<usize>,
+    value: Node,
+}
+
+#[cfg(any(feature = "ron", feature = "yaml"))]
+impl Node {
+    fn entries(&self) -> &[Entry] {
+        self.entries.as_deref().unwrap_or_default()
+    }
+
+    fn node(&self, path: &[String]) -> Option<&Node> {
+        path.iter().try_fold(self, |node, segment| {
+            node.entries()
+                .iter()
+                .find(|entry| &entry.key == segment)
+                .map(|entry| &entry.value)
+        })
+    }
+
+    fn entry(&self, path: &[String]) -> Option<&Entry> {
+        let (key, parent) = path.split_last()?;
+        self.node(parent)?
+            .entries()
+            .iter()
+            .find(|entry| &entry.key == key)
+    }
+}
+
+/// The text based formats
+#[cfg(any(feature = "ron", feature = "yaml"))]
+#[derive(Clone, Copy)]
+enum Syntax {
+    #[cfg(feature = "ron")]
+    Ron,
+    #[cfg(feature = "yaml")]
+    Yaml,
+}
+
+/// Changes of a map, applied together to keep separators intact
+#[cfg(any(feature = "ron", feature = "yaml"))]
+#[derive(Default)]
+struct MapChanges {
+    removed: Vec<String>,
+    inserted: Vec<String>,
+}
+
+/// Edits of a document in RON or YAML text
+#[cfg(any(feature = "ron", feature = "yaml"))]
+struct TextEdit<'a> {
+    syntax: Syntax,
+    source: &'a str,
+    old: Node,
+    written: &'a str,
+    new: Node,
+}
+
+#[cfg(any(feature = "ron", feature = "yaml"))]
+impl TextEdit<'_> {
+    fn apply(&self, changes: Vec<Change>) -> String {
+        let mut replaced = vec![];
+        let mut maps: Vec<(Vec<String>, MapChanges)> = vec![];
+        for change in changes {
+            let (mut path, insert) = match change {
+                Change::Replace(path) => {
+                    replaced.push(path);
+                    continue;
+                }
+                Change::Insert(path) => (path, true),
+                Change::Remove(path) => (path, false),
+            };
+            let Some(key) = path.pop() else {
+                continue;
+            };
+            let index = match maps.iter().position(|(parent, _)| *parent == path) {
+                Some(index) => index,
+                None => {
+                    maps.push((path, MapChanges::default()));
+                    maps.len() - 1
+                }
+            };
+            let map = &mut maps[index].1;
+            if insert {
+                map.inserted.push(key);
+            } else {
+                map.removed.push(key);
+            }
+        }
+        maps.retain(|(parent, map)| {
+            let editable = self.editable_map(parent, map);
+            if !editable {
+                replaced.push(parent.clone());
+            }
+            editable
+        });
+
+        // Replaced values need to exist in both documents, otherwise their parent is replaced
+        for path in &mut replaced {
+            while !path.is_empty()
+                && (self.old.entry(path).is_none() || self.new.entry(path).is_none())
+            {
+                path.pop();
+            }
+        }
+        if replaced.iter().any(Vec::is_empty) {
+            return self.written.to_owned();
+        }
+        replaced.sort();
+        replaced.dedup();
+        let covered = |path: &[String], replaced: &[Vec<String>]| {
+            replaced
+                .iter()
+                .any(|other| path.len() > other.len() && path.starts_with(other))
+        };
+        let replaced: Vec<_> = replaced
+            .iter()
+            .filter(|path| !covered(path, &replaced))
+            .cloned()
+            .collect();
+        maps.retain(|(parent, _)| !replaced.iter().any(|path| parent.starts_with(path)));
+
+        let mut edits = vec![];
+        for path in &replaced {
+            edits.push(self.replace(path));
+        }
+        for (parent, map) in &maps {
+            self.edit_map(parent, map, &mut edits);
+        }
+        edits.sort_by_key(|(range, _)| range.start);
+        let mut text = String::with_capacity(self.source.len());
+        let mut position = 0;
+        for (range, replacement) in edits {
+            text.push_str(&self.source[position..range.start]);
+            text.push_str(&replacement);
+            position = range.end;
+        }
+        text.push_str(&self.source[position..]);
+        text
+    }
+
+    /// Whether the removed and inserted entries of the map at `parent` can be edited without
+    /// rewriting the map
+    fn editable_map(&self, parent: &[String], map: &MapChanges) -> bool {
+        let Some(entries) = self.old.node(parent).and_then(|node| node.entries.as_ref()) else {
+            return false;
+        };
+        let removed = |entry: &Entry| map.removed.contains(&entry.key);
+        let removals = map.removed.is_empty()
+            || (entries.iter().all(|entry| {
+                entry
+                    .start
+                    .is_some_and(|start| !removed(entry) || self.own_line(start))
+            }) && map
+                .removed
+                .iter()
+                .all(|key| entries.iter().any(|entry| &entry.key == key)));
+        let insertions = map.inserted.is_empty()
+            || (entries
+                .last()
+                .is_some_and(|last| last.start.is_some() && !removed(last))
+                && map.inserted.iter().all(|key| {
+                    let mut path = parent.to_vec();
+                    path.push(key.clone());
+                    self.new
+                        .entry(&path)
+                        .is_some_and(|entry| entry.start.is_some())
+                }));
+        entries.iter().any(|entry| !removed(entry)) && removals && insertions
+    }
+
+    /// Whether an entry starting at `position` can be removed with its lines
+    #[cfg_attr(not(feature = "yaml"), allow(unused_variables))]
+    fn own_line(&self, position: usize) -> bool {
+        match self.syntax {
+            #[cfg(feature = "ron")]
+            Syntax::Ron => true,
+            #[cfg(feature = "yaml")]
+            Syntax::Yaml => self.source[line_start(self.source, position)..position]
+                .trim()
+                .is_empty(),
+        }
+    }
+
+    /// Where removing an entry starting at `position` starts
+    fn removal_start(&self, position: usize) -> usize {
+        match self.syntax {
+            #[cfg(feature = "ron")]
+            Syntax::Ron => position,
+            #[cfg(feature = "yaml")]
+            Syntax::Yaml => line_start(self.source, position),
+        }
+    }
+
+    /// The indentation that nested lines of the value at `position` are relative to
+    ///
+    /// YAML nests relative to the column of keys, which can follow an item marker.
+    fn indentation(&self, text: &str, position: usize) -> usize {
+        let line = &text[line_start(text, position)..position];
+        match self.syntax {
+            #[cfg(feature = "ron")]
+            Syntax::Ron => line.len() - line.trim_start_matches(' ').len(),
+            #[cfg(feature = "yaml")]
+            Syntax::Yaml => line.chars().count(),
+        }
+    }
+
+    /// The edit replacing the entry at `path` with the one of the new document
+    fn replace(&self, path: &[String]) -> (Range<usize>, String) {
+        let (Some(old), Some(new)) = (self.old.entry(path), self.new.entry(path)) else {
+            unreachable!("replaced entries exist in both documents");
+        };
+        let (old_start, new_start) = match self.syntax {
+            #[cfg(feature = "ron")]
+            Syntax::Ron => (old.value.span.start, new.value.span.start),
+            #[cfg(feature = "yaml")]
+            Syntax::Yaml => (
+                old.start.unwrap_or(old.value.span.start),
+                new.start.unwrap_or(new.value.span.start),
+            ),
+        };
+        (
+            old_start..old.value.span.end,
+            reindent(
+                &self.written[new_start..new.value.span.end],
+                self.indentation(self.written, new_start),
+                self.indentation(self.source, old_start),
+            ),
+        )
+    }
+
+    /// The edits removing and inserting entries of the map at `parent`
+    fn edit_map(
+        &self,
+        parent: &[String],
+        map: &MapChanges,
+        edits: &mut Vec<(Range<usize>, String)>,
+    ) {
+        let Some(node) = self.old.node(parent) else {
+            return;
+        };
+        let entries = node.entries();
+        let start = |entry: &Entry| entry.start.unwrap_or(entry.value.span.start);
+        let mut index = 0;
+        while index < entries.len() {
+            if !map.removed.contains(&entries[index].key) {
+                index += 1;
+                continue;
+            }
+            let first = index;
+            while index < entries.len() && map.removed.contains(&entries[index].key) {
+                index += 1;
+            }
+            let last = &entries[index - 1];
+            let range = match (self.syntax, entries.get(index)) {
+                (_, Some(next)) => {
+                    self.removal_start(start(&entries[first]))..self.removal_start(start(next))
+                }
+                #[cfg(feature = "ron")]
+                (Syntax::Ron, None) => {
+                    let kept_end = entries[first - 1].value.span.end;
+                    let end = line_end(self.source, last.value.span.end);
+                    let rest = self.source[last.value.span.end..end].trim_start_matches([',', ' ']);
+                    if self.source[kept_end..start(&entries[first])].contains('\n')
+                        && (rest.is_empty() || rest.starts_with("//"))
+                    {
+                        // Remove the lines of the entries with their comments
+                        line_end(self.source, kept_end)..end
+                    } else {
+                        kept_end..last.value.span.end
+                    }
+                }
+                #[cfg(feature = "yaml")]
+                (Syntax::Yaml, None) => {
+                    line_start(self.source, start(&entries[first]))
+                        ..next_line(self.source, last.value.span.end)
+                }
+            };
+            edits.push((range, String::new()));
+        }
+
+        if map.inserted.is_empty() {
+            return;
+        }
+        let Some(last) = entries.last() else {
+            return;
+        };
+        let indent = self.indentation(self.source, start(last));
+        let inserted: Vec<String> = map
+            .inserted
+            .iter()
+            .filter_map(|key| {
+                let mut path = parent.to_vec();
+                path.push(key.clone());
+                let entry = self.new.entry(&path)?;
+                let start = entry.start?;
+                Some(reindent(
+                    &self.written[start..entry.value.span.end],
+                    self.indentation(self.written, start),
+                    indent,
+                ))
+            })
+            .collect();
+        let value_end = last.value.span.end;
+        match self.syntax {
+            #[cfg(feature = "ron")]
+            Syntax::Ron => {
+                let after = &self.source[value_end..node.span.end];
+                let comma = after.trim_start().starts_with(',');
+                let position = match after.find(',') {
+                    Some(index) if comma => value_end + index + 1,
+                    _ => value_end,
+                };
+                // The closing bracket is on the line of the last entry
+                let inline = !after.contains('\n');
+                let separator = if inline {
+                    " ".to_owned()
+                } else {
+                    format!("\n{}", " ".repeat(indent))
+                };
+                let mut text = String::new();
+                for entry in &inserted {
+                    if !comma {
+                        text.push(',');
+                    }
+                    text.push_str(&separator);
+                    text.push_str(entry);
+                    if comma {
+                        text.push(',');
+                    }
+                }
+                let end = line_end(self.source, position);
+                if !inline && self.source[position..end].trim_start().starts_with("//") {
+                    // Keep the comment behind the entry it belongs to
+                    if !comma {
+                        edits.push((position..position, text.remove(0).to_string()));
+                    }
+                    edits.push((end..end, text));
+                } else {
+                    edits.push((position..position, text));
+                }
+            }
+            #[cfg(feature = "yaml")]
+            Syntax::Yaml => {
+                let end = line_end(self.source, value_end);
+                let mut text = String::new();
+                for entry in &inserted {
+                    text.push('\n');
+                    text.push_str(&" ".repeat(indent));
+                    text.push_str(entry);
+                }
+                edits.push((end..end, text));
+            }
+        }
+    }
+}
+
+#[cfg(any(feature = "ron", feature = "yaml"))]
+fn line_start(text: &str, position: usize) -> usize {
+    text[..position].rfind('\n').map_or(0, |index| index + 1)
+}
+
+#[cfg(any(feature = "ron", feature = "yaml"))]
+fn line_end(text: &str, position: usize) -> usize {
+    text[position..]
+        .find('\n')
+        .map_or(text.len(), |index| position + index)
+}
+
+#[cfg(feature = "yaml")]
+fn next_line(text: &str, position: usize) -> usize {
+    text[position..]
+        .find('\n')
+        .map_or(text.len(), |index| position + index + 1)
+}
+
+/// Move all lines of `text` after the first from the indentation `from` to `to`
+#[cfg(any(feature = "ron", feature = "yaml"))]
+fn reindent(text: &str, from: usize, to: usize) -> String {
+    let mut lines = text.split('\n');
+    let mut reindented = lines.next().unwrap_or_default().to_owned();
+    for line in lines {
+        reindented.push('\n');
+        if !line.trim().is_empty() {
+            let spaces = line.len() - line.trim_start_matches(' ').len();
+            reindented.push_str(&" ".repeat(to));
+            reindented.push_str(&line[spaces.min(from)..]);
+        }
+    }
+    reindented
+}
+
+/// The positions of all values in a RON document
+#[cfg(feature = "ron")]
+fn ron_tree(text: &str) -> Result<Node, EditError> {
+    let raw = serde_ron::from_str::<&serde_ron::value::RawValue>(text)
+        .map_err(|error| EditError::Parse(error.to_string()))?;
+    ron_node(raw, text)
+}
+
+#[cfg(feature = "ron")]
+fn ron_node(raw: &serde_ron::value::RawValue, text: &str) -> Result<Node, EditError> {
+    let raw = raw.trim();
+    let start = raw.get_ron().as_ptr() as usize - text.as_ptr() as usize;
+    let span = start..start + raw.get_ron().len();
+    let entries = match raw
+        .into_rust::<RonNode>()
+        .map_err(|error| EditError::Parse(error.to_string()))?
+    {
+        RonNode::Some(inner) => {
+            return Ok(Node {
+                span,
+                ..ron_node(inner, text)?
+            });
+        }
+        RonNode::Scalar => None,
+        RonNode::Seq(items) => Some(
+            items
+                .into_iter()
+                .enumerate()
+                .map(|(index, item)| {
+                    let value = ron_node(item, text)?;
+                    Ok(Entry {
+                        key: index.to_string(),
+                        start: Some(value.span.start),
+                        value,
+                    })
+                })
+                .collect::<Result<_, EditError>>()?,
+        ),
+        RonNode::Map(entries) => Some(
+            entries
+                .into_iter()
+                .map(|(key, item)| {
+                    let value = ron_node(item, text)?;
+                    Ok(Entry {
+                        key,
+                        start: ron_key_start(text, value.span.start),
+                        value,
+                    })
+                })
+                .collect::<Result<_, EditError>>()?,
+        ),
+    };
+    Ok(Node { span, entries })
+}
+
+/// Where the key of the map entry with the value at `value_start` starts
+#[cfg(feature = "ron")]
+fn ron_key_start(text: &str, value_start: usize) -> Option<usize> {
+    let before = text[..value_start].trim_end().strip_suffix(':')?.trim_end();
+    if let Some(quoted) = before.strip_suffix('"') {
+        let mut end = quoted.len();
+        loop {
+            let quote = quoted[..end].rfind('"')?;
+            if !quoted[..quote].ends_with('\\') {
+                return Some(quote);
+            }
+            end = quote;
+        }
+    }
+    let start = before
+        .trim_end_matches(|character: char| character.is_alphanumeric() || "_#".contains(character))
+        .len();
+    (start < before.len()).then_some(start)
+}
+
+/// One level of a RON document with the raw text of all nested values
+///
+/// The nested values borrow the text of the document, so their position in it is known.
+#[cfg(feature = "ron")]
+enum RonNode<'a> {
+    Scalar,
+    Some(&'a serde_ron::value::RawValue),
+    Seq(Vec<&'a serde_ron::value::RawValue>),
+    Map(Vec<(String, &'a serde_ron::value::RawValue)>),
+}
+
+#[cfg(feature = "ron")]
+impl<'de: 'a, 'a> serde::Deserialize<'de> for RonNode<'a> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(RonNodeVisitor)
+    }
+}
+
+#[cfg(feature = "ron")]
+struct RonNodeVisitor;
+
+#[cfg(feature = "ron")]
+impl<'de> serde::de::Visitor<'de> for RonNodeVisitor {
+    type Value = RonNode<'de>;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("any RON value")
+    }
+
+    fn visit_bool<E>(self, _: bool) -> Result<RonNode<'de>, E> {
+        Ok(RonNode::Scalar)
+    }
+
+    fn visit_i64<E>(self, _: i64) -> Result<RonNode<'de>, E> {
+        Ok(RonNode::Scalar)
+    }
+
+    fn visit_i128<E>(self, _: i128) -> Result<RonNode<'de>, E> {
+        Ok(RonNode::Scalar)
+    }
+
+    fn visit_u64<E>(self, _: u64) -> Result<RonNode<'de>, E> {
+        Ok(RonNode::Scalar)
+    }
+
+    fn visit_u128<E>(self, _: u128) -> Result<RonNode<'de>, E> {
+        Ok(RonNode::Scalar)
+    }
+
+    fn visit_f64<E>(self, _: f64) -> Result<RonNode<'de>, E> {
+        Ok(RonNode::Scalar)
+    }
+
+    fn visit_str<E>(self, _: &str) -> Result<RonNode<'de>, E> {
+        Ok(RonNode::Scalar)
+    }
+
+    fn visit_bytes<E>(self, _: &[u8]) -> Result<RonNode<'de>, E> {
+        Ok(RonNode::Scalar)
+    }
+
+    fn visit_none<E>(self) -> Result<RonNode<'de>, E> {
+        Ok(RonNode::Scalar)
+    }
+
+    fn visit_unit<E>(self) -> Result<RonNode<'de>, E> {
+        Ok(RonNode::Scalar)
+    }
+
+    fn visit_some<D: serde::Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<RonNode<'de>, D::Error> {
+        serde::Deserialize::deserialize(deserializer).map(RonNode::Some)
+    }
+
+    fn visit_newtype_struct<D: serde::Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<RonNode<'de>, D::Error> {
+        serde::Deserialize::deserialize(deserializer).map(|inner| RonNode::Seq(vec![inner]))
+    }
+
+    fn visit_seq<S: serde::de::SeqAccess<'de>>(
+        self,
+        mut access: S,
+    ) -> Result<RonNode<'de>, S::Error> {
+        let mut items = vec![];
+        while let Some(item) = access.next_element()? {
+            items.push(item);
+        }
+        Ok(RonNode::Seq(items))
+    }
+
+    fn visit_map<M: serde::de::MapAccess<'de>>(
+        self,
+        mut access: M,
+    ) -> Result<RonNode<'de>, M::Error> {
+        let mut entries = vec![];
+        while let Some((key, value)) = access.next_entry::<Value, _>()? {
+            let key = crate::value::key_to_string(key).map_err(serde::de::Error::custom)?;
+            entries.push((key, value));
+        }
+        Ok(RonNode::Map(entries))
+    }
+}
+
+/// A line of a YAML document with content
+#[cfg(feature = "yaml")]
+#[derive(Clone, Copy)]
+struct Line {
+    start: usize,
+    /// The offset of the content from the start of the line
+    indent: usize,
+    /// The end of the content without trailing comments
+    end: usize,
+}
+
+/// Finds the positions of the values of YAML documents in block style
+///
+/// Flow collections, block scalars, anchors and tags are treated as values without entries.
+#[cfg(feature = "yaml")]
+struct YamlScanner<'a> {
+    text: &'a str,
+    lines: Vec<Line>,
+    next: usize,
+}
+
+#[cfg(feature = "yaml")]
+impl<'a> YamlScanner<'a> {
+    fn tree(text: &'a str) -> Result<Node, EditError> {
+        let mut scanner = YamlScanner {
+            text,
+            lines: yaml_lines(text),
+            next: 0,
+        };
+        if scanner.lines.is_empty() {
+            return Ok(Node {
+                span: 0..0,
+                entries: None,
+            });
+        }
+        let node = scanner.node(None).ok_or(EditError::Layout)?;
+        if scanner.next < scanner.lines.len() {
+            return Err(EditError::Layout);
+        }
+        Ok(node)
+    }
+
+    fn content(&self, line: Line) -> &'a str {
+        &self.text[line.start + line.indent..line.end]
+    }
+
+    /// The block node starting at the next line, inside a block at the column `parent`
+    fn node(&mut self, parent: Option<usize>) -> Option<Node> {
+        let line = *self.lines.get(self.next)?;
+        let content = self.content(line);
+        if is_item(content) {
+            self.sequence(line.indent)
+        } else if yaml_key(content).is_some() {
+            self.mapping(line.indent)
+        } else {
+            self.inline(line.start + line.indent, parent)
+        }
+    }
+
+    fn sequence(&mut self, column: usize) -> Option<Node> {
+        let mut entries = vec![];
+        while let Some(line) = self.lines.get(self.next).copied() {
+            if line.indent != column || !is_item(self.content(line)) {
+                break;
+            }
+            let start = line.start + column;
+            let rest = &self.text[start + 1..line.end];
+            let value = if rest.trim().is_empty() {
+                self.nested(start + 1, column, false)?
+            } else {
+                // The item continues on the same line, like a mapping with following keys
+                // at the column of its first key
+                self.lines[self.next].indent = line.end - line.start - rest.trim_start().len();
+                self.node(Some(column))?
+            };
+            entries.push(Entry {
+                key: entries.len().to_string(),
+                start: Some(start),
+                value,
+            });
+        }
+        block(entries)
+    }
+
+    fn mapping(&mut self, column: usize) -> Option<Node> {
+        let mut entries = vec![];
+        while let Some(line) = self.lines.get(self.next).copied() {
+            if line.indent != column {
+                break;
+            }
+            let start = line.start + column;
+            let Some((key, colon)) = yaml_key(self.content(line)) else {
+                break;
+            };
+            let after = start + colon + 1;
+            let rest = &self.text[after..line.end];
+            let value = if rest.trim().is_empty() {
+                self.nested(after, column, true)?
+            } else {
+                self.inline(line.end - rest.trim_start().len(), Some(column))?
+            };
+            entries.push(Entry {
+                key,
+                start: Some(start),
+                value,
+            });
+        }
+        block(entries)
+    }
+
+    /// The value on the lines after a key or item marker ending at `end`
+    fn nested(&mut self, end: usize, column: usize, key: bool) -> Option<Node> {
+        self.next += 1;
+        match self.lines.get(self.next) {
+            Some(next) if next.indent > column => self.node(Some(column)),
+            Some(next) if key && next.indent == column && is_item(self.content(*next)) => {
+                self.node(Some(column))
+            }
+            _ => Some(Node {
+                span: end..end,
+                entries: None,
+            }),
+        }
+    }
+
+    /// The value without entries starting at `start` on the next line
+    fn inline(&mut self, start: usize, parent: Option<usize>) -> Option<Node> {
+        let line = self.lines[self.next];
+        let deeper = |line: &Line| parent.is_none_or(|parent| line.indent > parent);
+        let mut end = match self.text[start..].chars().next() {
+            Some('[' | '{') => flow_end(self.text, start)?,
+            Some('|' | '>') => {
+                let mut end = line.end;
+                let mut position = next_line(self.text, line.end);
+                for raw in self.text[position..].split_inclusive('\n') {
+                    let content = raw.trim_end();
+                    let indent = content.len() - content.trim_start_matches(' ').len();
+                    if !content.is_empty() {
+                        if parent.is_some_and(|parent| indent <= parent) {
+                            break;
+                        }
+                        end = position + content.len();
+                    }
+                    position += raw.len();
+                }
+                end
+            }
+            _ => line.end,
+        };
+        self.next += 1;
+        while self
+            .lines
+            .get(self.next)
+            .is_some_and(|line| line.start < end)
+        {
+            self.next += 1;
+        }
+        // Multi-line plain scalars and nested blocks after anchors or tags
+        while let Some(line) = self.lines.get(self.next).filter(|line| deeper(line)) {
+            end = end.max(line.end);
+            self.next += 1;
+        }
+        Some(Node {
+            span: start..end,
+            entries: None,
+        })
+    }
+}
+
+#[cfg(feature = "yaml")]
+fn block(entries: Vec<Entry>) -> Option<Node> {
+    let start = entries.first()?.start?;
+    let end = entries.last()?.value.span.end;
+    Some(Node {
+        span: start..end,
+        entries: Some(entries),
+    })
+}
+
+/// The lines of `text` with content, leaving out comments and document markers
+#[cfg(feature = "yaml")]
+fn yaml_lines(text: &str) -> Vec<Line> {
+    let mut lines = vec![];
+    let mut start = 0;
+    for raw in text.split_inclusive('\n') {
+        let line = raw.trim_end_matches(['\n', '\r']);
+        let indent = line.len() - line.trim_start_matches(' ').len();
+        let content = &line[indent..];
+        let content = &content[..comment_start(content)].trim_end();
+        let marker = indent == 0 && matches!(*content, "---" | "...");
+        if !(content.is_empty() || marker || (indent == 0 && content.starts_with('%'))) {
+            lines.push(Line {
+                start,
+                indent,
+                end: start + indent + content.len(),
+            });
+        }
+        start += raw.len();
+    }
+    lines
+}
+
+/// Where the comment of a line starts, or its length without comment
+#[cfg(feature = "yaml")]
+fn comment_start(line: &str) -> usize {
+    let mut quote = None;
+    let mut previous = ' ';
+    let mut characters = line.char_indices();
+    while let Some((index, character)) = characters.next() {
+        match (quote, character) {
+            (Some('"'), '\\') => {
+                characters.next();
+            }
+            (Some(open), character) if character == open => quote = None,
+            (Some(_), _) => {}
+            (None, '#') if previous.is_whitespace() => return index,
+            (None, '"' | '\'') if " \t:[{,-".contains(previous) => quote = Some(character),
+            (None, _) => {}
+        }
+        previous = character;
+    }
+    line.len()
+}
+
+#[cfg(feature = "yaml")]
+fn is_item(content: &str) -> bool {
+    content == "-" || content.starts_with("- ") || content.starts_with("-\t")
+}
+
+/// The key of a mapping entry and the position of its colon
+#[cfg(feature = "yaml")]
+fn yaml_key(content: &str) -> Option<(String, usize)> {
+    let (key, rest) = if content.starts_with(['"', '\'']) {
+        let end = flow_end(content, 0)?;
+        let key = serde_yaml::from_str::<String>(&content[..end]).ok()?;
+        (key, end)
+    } else {
+        if content.starts_with(['[', '{', '!', '&', '*', '|', '>', '%', '@', '`', '?', '#']) {
+            return None;
+        }
+        let colon = content
+            .match_indices(':')
+            .map(|(index, _)| index)
+            .find(|index| {
+                content[index + 1..]
+                    .chars()
+                    .next()
+                    .is_none_or(char::is_whitespace)
+            })?;
+        (content[..colon].trim_end().to_owned(), colon)
+    };
+    let colon = rest + content[rest..].len() - content[rest..].trim_start().len();
+    content[colon..].starts_with(':').then_some((key, colon))
+}
+
+/// The end of the flow collection or quoted scalar starting at `start`
+#[cfg(feature = "yaml")]
+fn flow_end(text: &str, start: usize) -> Option<usize> {
+    let mut depth = 0;
+    let mut quote = None;
+    let mut previous = ' ';
+    let mut characters = text[start..].char_indices();
+    while let Some((index, character)) = characters.next() {
+        match (quote, character) {
+            (Some('"'), '\\') => {
+                characters.next();
+            }
+            (Some(open), character) if character == open => {
+                quote = None;
+                if depth == 0 {
+                    return Some(start + index + 1);
+                }
+            }
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(character),
+            (None, '[' | '{') => depth += 1,
+            (None, ']' | '}') => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(start + index + 1);
+                }
+            }
+            (None, '#') if previous.is_whitespace() => {
+                for (_, character) in characters.by_ref() {
+                    if character == '\n' {
+                        break;
+                    }
+                }
+            }
+            (None, _) => {}
+        }
+        previous = character;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edit(format: DataFormat, source: &str, written: &str) -> String {
+        edit_text(format, source, written).unwrap()
+    }
+
+    #[cfg(feature = "ron")]
+    #[test]
+    fn ron_replaced_values_keep_comments() {
+        let source = "// Level\n(\n    name: \"a\", // the name\n    hp: 1,\n)\n";
+        let written = "(\n    name: \"b\",\n    hp: 1,\n)";
+        assert_eq!(
+            edit(DataFormat::Ron, source, written),
+            "// Level\n(\n    name: \"b\", // the name\n    hp: 1,\n)\n"
+        );
+    }
+
+    #[cfg(feature = "ron")]
+    #[test]
+    fn ron_inserted_entries_follow_the_last_entry() {
+        let written = "(\n    name: \"a\",\n    hp: 1,\n    speed: 2.0,\n)";
+        assert_eq!(
+            edit(
+                DataFormat::Ron,
+                "// Level\n(\n    name: \"a\", // the name\n    hp: 1, // health\n)\n",
+                written
+            ),
+            "// Level\n(\n    name: \"a\", // the name\n    hp: 1, // health\n    speed: 2.0,\n)\n"
+        );
+        assert_eq!(
+            edit(DataFormat::Ron, "(name: \"a\", hp: 1)\n", written),
+            "(name: \"a\", hp: 1, speed: 2.0)\n"
+        );
+    }
+
+    #[cfg(feature = "ron")]
+    #[test]
+    fn ron_removed_entries_take_their_lines() {
+        let source = "// Level\n(\n    name: \"a\", // the name\n    hp: 1, // health\n    speed: 2.0, // speed\n)\n";
+        assert_eq!(
+            edit(
+                DataFormat::Ron,
+                source,
+                "(\n    name: \"a\",\n    speed: 2.0,\n)"
+            ),
+            "// Level\n(\n    name: \"a\", // the name\n    speed: 2.0, // speed\n)\n"
+        );
+        assert_eq!(
+            edit(
+                DataFormat::Ron,
+                source,
+                "(\n    name: \"a\",\n    hp: 1,\n)"
+            ),
+            "// Level\n(\n    name: \"a\", // the name\n    hp: 1, // health\n)\n"
+        );
+    }
+
+    #[cfg(feature = "ron")]
+    #[test]
+    fn ron_parents_are_replaced_if_entries_can_not_be_edited() {
+        // A sequence that grows
+        assert_eq!(
+            edit(
+                DataFormat::Ron,
+                "// Level\n(\n    name: \"a\", // the name\n    items: [1, 2], // items\n)\n",
+                "(\n    name: \"a\",\n    items: [\n        1,\n        2,\n        3,\n    ],\n)"
+            ),
+            "// Level\n(\n    name: \"a\", // the name\n    items: [\n        1,\n        2,\n        3,\n    ], // items\n)\n"
+        );
+        // A map without any of its entries left
+        assert_eq!(
+            edit(
+                DataFormat::Ron,
+                "// Level\n(\n    stats: (hp: 1), // stats\n)\n",
+                "(\n    stats: (\n        mp: 2,\n    ),\n)"
+            ),
+            "// Level\n(\n    stats: (\n        mp: 2,\n    ), // stats\n)\n"
+        );
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn yaml_entries_are_edited_in_place() {
+        let source = "# Level\nname: a # the name\nhp: 1 # health\n";
+        assert_eq!(
+            edit(DataFormat::Yaml, source, "name: b\nhp: 1\n"),
+            "# Level\nname: b # the name\nhp: 1 # health\n"
+        );
+        assert_eq!(
+            edit(DataFormat::Yaml, source, "name: a\nhp: 1\nspeed: 2.0\n"),
+            "# Level\nname: a # the name\nhp: 1 # health\nspeed: 2.0\n"
+        );
+        assert_eq!(
+            edit(
+                DataFormat::Yaml,
+                "# Level\nname: a # the name\nhp: 1 # health\nspeed: 2.0 # speed\n",
+                "name: a\nspeed: 2.0\n"
+            ),
+            "# Level\nname: a # the name\nspeed: 2.0 # speed\n"
+        );
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn yaml_flow_collections_are_rewritten() {
+        assert_eq!(
+            edit(
+                DataFormat::Yaml,
+                "# Level\nname: a # the name\nitems: [1, 2] # items\n",
+                "name: a\nitems:\n- 1\n- 3\n"
+            ),
+            "# Level\nname: a # the name\nitems:\n- 1\n- 3 # items\n"
+        );
+        assert_eq!(
+            edit(
+                DataFormat::Yaml,
+                "# Level\nname: a # the name\nstats: {hp: 1, mp: 2} # stats\n",
+                "name: a\nstats:\n  hp: 1\n"
+            ),
+            "# Level\nname: a # the name\nstats:\n  hp: 1 # stats\n"
+        );
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn yaml_parents_are_replaced_if_entries_can_not_be_edited() {
+        assert_eq!(
+            edit(
+                DataFormat::Yaml,
+                "# Level\nname: a # the name\nstats:\n  hp: 1 # health\n",
+                "name: a\nstats:\n  mp: 2\n"
+            ),
+            "# Level\nname: a # the name\nstats:\n  mp: 2 # health\n"
+        );
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn toml_entries_are_edited_in_place() {
+        assert_eq!(
+            edit(
+                DataFormat::Toml,
+                "# Level\nname = \"a\" # the name\nhp = 1\n",
+                "name = \"b\"\nhp = 1\n"
+            ),
+            "# Level\nname = \"b\" # the name\nhp = 1\n"
+        );
+        assert_eq!(
+            edit(
+                DataFormat::Toml,
+                "# Level\nname = \"a\" # the name\n\n[stats] # stats\nhp = 1 # health\n",
+                "name = \"a\"\n\n[stats]\nhp = 1\nmp = 2\n"
+            ),
+            "# Level\nname = \"a\" # the name\n\n[stats] # stats\nhp = 1 # health\nmp = 2\n"
+        );
+        assert_eq!(
+            edit(
+                DataFormat::Toml,
+                "# Level\nname = \"a\" # the name\nhp = 1 # health\nspeed = 2.0\n",
+                "name = \"a\"\nspeed = 2.0\n"
+            ),
+            "# Level\nname = \"a\" # the name\nspeed = 2.0\n"
+        );
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn toml_inline_collections_keep_their_comments() {
+        assert_eq!(
+            edit(
+                DataFormat::Toml,
+                "# Level\nname = \"a\" # the name\nitems = [1, 2] # items\n",
+                "name = \"a\"\nitems = [1, 3]\n"
+            ),
+            "# Level\nname = \"a\" # the name\nitems = [1, 3] # items\n"
+        );
+        assert_eq!(
+            edit(
+                DataFormat::Toml,
+                "# Level\nname = \"a\" # the name\nstats = { hp = 1 } # stats\n",
+                "name = \"a\"\nstats = { mp = 2 }\n"
+            ),
+            "# Level\nname = \"a\" # the name\nstats = { mp = 2 } # stats\n"
+        );
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn toml_parents_are_replaced_if_entries_can_not_be_edited() {
+        assert_eq!(
+            edit(
+                DataFormat::Toml,
+                "# Enemies\nenemies = [{ name = \"a\" }] # enemies\n",
+                "enemies = [{ name = \"a\", hp = 1 }]\n"
+            ),
+            "# Enemies\nenemies = [{ name = \"a\", hp = 1 }] # enemies\n"
+        );
+    }
+
+    #[test]
+    fn unchanged_documents_are_kept() {
+        #[cfg(feature = "ron")]
+        assert_eq!(
+            edit(DataFormat::Ron, "(hp: 1) // health\n", "(hp: 1)"),
+            "(hp: 1) // health\n"
+        );
+        #[cfg(feature = "toml")]
+        assert_eq!(
+            edit(DataFormat::Toml, "hp = 1 # health\n", "hp = 1\n"),
+            "hp = 1 # health\n"
+        );
+        #[cfg(feature = "yaml")]
+        assert_eq!(
+            edit(DataFormat::Yaml, "hp: 1 # health\n", "hp: 1\n"),
+            "hp: 1 # health\n"
+        );
+    }
+}
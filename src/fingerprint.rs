@@ -0,0 +1,362 @@
+use crate::DataAsset;
+use bevy_reflect::{TypeInfo, Typed, VariantInfo};
+use thiserror::Error;
+
+/// Magic bytes at the start of every fingerprint envelope
+const MAGIC: &[u8; 8] = b"\0BCAFPR\0";
+/// Version of the envelope layout
+const VERSION: u8 = 1;
+
+/// Identifies the type and schema a binary asset was saved with
+///
+/// Postcard carries no type information and CBOR files saved for an older version of a type
+/// can still deserialize into wrong data. Savers with a fingerprint write an envelope in front
+/// of the data, containing a magic number, the envelope version, the [`TypePath`](bevy_reflect::TypePath)
+/// of the asset and a hash of the schema of its [`Definition`](DataAsset::Definition), which is
+/// the data the savers write. Loaders with a fingerprint refuse files without a matching
+/// envelope, asking to re-process them.
+///
+/// The schema hash covers the reflected structure of the definition: names and types of all
+/// fields and enum variants, recursing into field types that implement [`Typed`]. Serde
+/// attributes like `rename` or `skip` are not part of it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Fingerprint {
+    type_path: &'static str,
+    schema_hash: u64,
+}
+
+impl Fingerprint {
+    /// The fingerprint of the asset type `A`, with the schema of its definition
+    pub fn of<A>() -> Self
+    where
+        A: DataAsset<Definition: Typed>,
+    {
+        let mut hasher = Fnv1a::default();
+        hash_type(A::Definition::type_info(), &mut hasher, &mut vec![]);
+        Self {
+            type_path: A::type_path(),
+            schema_hash: hasher.0,
+        }
+    }
+
+    /// The type path of the fingerprinted asset type
+    pub fn type_path(&self) -> &'static str {
+        self.type_path
+    }
+
+    /// The hash of the schema of the definition of the fingerprinted asset type
+    pub fn schema_hash(&self) -> u64 {
+        self.schema_hash
+    }
+
+    /// Write the envelope for this fingerprint
+    pub(crate) fn envelope(&self) -> Vec<u8> {
+        let type_path = self.type_path.as_bytes();
+        let mut envelope = Vec::with_capacity(MAGIC.len() + 11 + type_path.len());
+        envelope.extend_from_slice(MAGIC);
+        envelope.push(VERSION);
+        envelope.extend_from_slice(&(type_path.len() as u16).to_le_bytes());
+        envelope.extend_from_slice(type_path);
+        envelope.extend_from_slice(&self.schema_hash.to_le_bytes());
+        envelope
+    }
+}
+
+/// Possible errors when checking the fingerprint envelope of a file
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum FingerprintError {
+    /// The loader expects a fingerprint, but the file has none
+    #[error(
+        "The file has no fingerprint of `{type_path}`. It is a stale processed asset, re-process it"
+    )]
+    Missing {
+        /// The type path of the loaded asset
+        type_path: &'static str,
+    },
+    /// The envelope was written by an unknown version
+    #[error("The fingerprint has the unsupported version {0}. Re-process the asset")]
+    UnsupportedVersion(u8),
+    /// The envelope ends before its type path and schema hash
+    #[error("The fingerprint of the file is truncated")]
+    Truncated,
+    /// The file was saved for another asset type
+    #[error(
+        "The file was saved for `{found}` instead of `{expected}`. It is a stale processed asset, re-process it"
+    )]
+    TypeMismatch {
+        /// The type path of the loaded asset
+        expected: &'static str,
+        /// The type path in the envelope
+        found: String,
+    },
+    /// The schema of the asset type changed since the file was saved
+    #[error(
+        "The schema of `{type_path}` changed since the file was saved. It is a stale processed asset, re-process it"
+    )]
+    SchemaMismatch {
+        /// The type path of the loaded asset
+        type_path: &'static str,
+    },
+}
+
+/// Check the envelope at the start of `bytes` and return the data following it
+///
+/// Files without an envelope are only accepted if the loader has no fingerprint. An envelope
+/// is always checked against the type path of the loaded asset.
+pub(crate) fn strip_envelope<'a>(
+    bytes: &'a [u8],
+    type_path: &'static str,
+    fingerprint: Option<&Fingerprint>,
+) -> Result<&'a [u8], FingerprintError> {
+    let Some(rest) = bytes.strip_prefix(MAGIC) else {
+        return match fingerprint {
+            Some(fingerprint) => Err(FingerprintError::Missing {
+                type_path: fingerprint.type_path,
+            }),
+            None => Ok(bytes),
+        };
+    };
+    let (&version, rest) = rest.split_first().ok_or(FingerprintError::Truncated)?;
+    if version != VERSION {
+        return Err(FingerprintError::UnsupportedVersion(version));
+    }
+    let (length, rest) = rest
+        .split_first_chunk::<2>()
+        .ok_or(FingerprintError::Truncated)?;
+    let length = u16::from_le_bytes(*length) as usize;
+    if rest.len() < length {
+        return Err(FingerprintError::Truncated);
+    }
+    let (found, rest) = rest.split_at(length);
+    let (schema_hash, rest) = rest
+        .split_first_chunk::<8>()
+        .ok_or(FingerprintError::Truncated)?;
+    if found != type_path.as_bytes() {
+        return Err(FingerprintError::TypeMismatch {
+            expected: type_path,
+            found: String::from_utf8_lossy(found).into_owned(),
+        });
+    }
+    if let Some(fingerprint) = fingerprint
+        && fingerprint.schema_hash != u64::from_le_bytes(*schema_hash)
+    {
+        return Err(FingerprintError::SchemaMismatch { type_path });
+    }
+    Ok(rest)
+}
+
+/// Whether `bytes` start with an envelope
+pub(crate) fn has_envelope(bytes: &[u8]) -> bool {
+    bytes.starts_with(MAGIC)
+}
+
+/// The data following the envelope at the start of `bytes`, or `bytes` if it has no envelope
+///
+/// Used to detect the format of the data without checking the envelope.
+pub(crate) fn skip_envelope(bytes: &[u8]) -> &[u8] {
+    let Some(rest) = bytes.strip_prefix(MAGIC) else {
+        return bytes;
+    };
+    rest.get(1..3)
+        .map(|length| 3 + usize::from(u16::from_le_bytes([length[0], length[1]])) + 8)
+        .and_then(|length| rest.get(length..))
+        .unwrap_or(bytes)
+}
+
+/// 64 bit FNV-1a, which is stable across platforms and Rust versions
+struct Fnv1a(u64);
+
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Fnv1a {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    /// Write a string terminated by a zero byte, so consecutive strings stay distinguishable
+    fn write_str(&mut self, text: &str) {
+        self.write(text.as_bytes());
+        self.write(&[0]);
+    }
+}
+
+fn hash_type(info: &'static TypeInfo, hasher: &mut Fnv1a, visited: &mut Vec<&'static str>) {
+    let type_path = info.type_path();
+    hasher.write_str(type_path);
+    if visited.contains(&type_path) {
+        return;
+    }
+    visited.push(type_path);
+    let mut hash_field = |name: &str, type_path: &str, info: Option<&'static TypeInfo>| {
+        hasher.write_str(name);
+        match info {
+            Some(info) => hash_type(info, hasher, visited),
+            None => hasher.write_str(type_path),
+        }
+    };
+    match info {
+        TypeInfo::Struct(info) => {
+            for field in info.iter() {
+                hash_field(field.name(), field.type_path(), field.type_info());
+            }
+        }
+        TypeInfo::TupleStruct(info) => {
+            for field in info.iter() {
+                hash_field("", field.type_path(), field.type_info());
+            }
+        }
+        TypeInfo::Tuple(info) => {
+            for field in info.iter() {
+                hash_field("", field.type_path(), field.type_info());
+            }
+        }
+        TypeInfo::List(info) => hash_field("", info.item_ty().path(), info.item_info()),
+        TypeInfo::Array(info) => {
+            hash_field(
+                &info.capacity().to_string(),
+                info.item_ty().path(),
+                info.item_info(),
+            );
+        }
+        TypeInfo::Map(info) => {
+            hash_field("", info.key_ty().path(), info.key_info());
+            hash_field("", info.value_ty().path(), info.value_info());
+        }
+        TypeInfo::Set(info) => hash_field("", info.value_ty().path(), None),
+        TypeInfo::Enum(info) => {
+            for variant in info.iter() {
+                match variant {
+                    VariantInfo::Struct(variant) => {
+                        hash_field(variant.name(), "struct", None);
+                        for field in variant.iter() {
+                            hash_field(field.name(), field.type_path(), field.type_info());
+                        }
+                    }
+                    VariantInfo::Tuple(variant) => {
+                        hash_field(variant.name(), "tuple", None);
+                        for field in variant.iter() {
+                            hash_field("", field.type_path(), field.type_info());
+                        }
+                    }
+                    VariantInfo::Unit(variant) => hash_field(variant.name(), "unit", None),
+                }
+            }
+        }
+        TypeInfo::Opaque(_) => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy_asset::Asset;
+    use bevy_reflect::Reflect;
+    use serde::Deserialize;
+
+    #[derive(Asset, Reflect, Deserialize)]
+    struct Level {
+        name: String,
+        spawns: Vec<Spawn>,
+    }
+
+    #[derive(Reflect, Deserialize)]
+    enum Spawn {
+        Orc { hp: u32 },
+        Elf(f32),
+    }
+
+    mod changed {
+        use bevy_asset::Asset;
+        use bevy_reflect::Reflect;
+        use serde::Deserialize;
+
+        #[derive(Asset, Reflect, Deserialize)]
+        pub(super) struct Level {
+            name: String,
+            spawns: Vec<Spawn>,
+        }
+
+        #[derive(Reflect, Deserialize)]
+        enum Spawn {
+            Orc { hp: u64 },
+            Elf(f32),
+        }
+    }
+
+    const TYPE_PATH: &str = "game::Level";
+
+    fn fingerprint(schema_hash: u64) -> Fingerprint {
+        Fingerprint {
+            type_path: TYPE_PATH,
+            schema_hash,
+        }
+    }
+
+    #[test]
+    fn schema_hash_covers_nested_types() {
+        let fingerprint = Fingerprint::of::<Level>();
+        assert_eq!(fingerprint, Fingerprint::of::<Level>());
+        assert_ne!(
+            fingerprint.schema_hash(),
+            Fingerprint::of::<changed::Level>().schema_hash()
+        );
+    }
+
+    #[test]
+    fn envelope_round_trips() {
+        let mut bytes = fingerprint(7).envelope();
+        bytes.extend_from_slice(b"data");
+        assert_eq!(
+            strip_envelope(&bytes, TYPE_PATH, Some(&fingerprint(7))).unwrap(),
+            b"data"
+        );
+        assert_eq!(strip_envelope(&bytes, TYPE_PATH, None).unwrap(), b"data");
+        assert_eq!(skip_envelope(&bytes), b"data");
+        assert_eq!(skip_envelope(b"data"), b"data");
+        assert!(has_envelope(&bytes));
+        assert!(!has_envelope(b"data"));
+    }
+
+    #[test]
+    fn mismatching_envelopes_are_refused() {
+        let mut bytes = fingerprint(7).envelope();
+        bytes.extend_from_slice(b"data");
+        assert!(matches!(
+            strip_envelope(&bytes, TYPE_PATH, Some(&fingerprint(8))),
+            Err(FingerprintError::SchemaMismatch { .. })
+        ));
+        assert!(matches!(
+            strip_envelope(&bytes, "game::Other", None),
+            Err(FingerprintError::TypeMismatch { found, .. }) if found == TYPE_PATH
+        ));
+        assert!(matches!(
+            strip_envelope(b"data", TYPE_PATH, Some(&fingerprint(7))),
+            Err(FingerprintError::Missing { .. })
+        ));
+        bytes[MAGIC.len()] = VERSION + 1;
+        assert!(matches!(
+            strip_envelope(&bytes, TYPE_PATH, None),
+            Err(FingerprintError::UnsupportedVersion(2))
+        ));
+    }
+
+    #[test]
+    fn truncated_envelopes_are_refused() {
+        let envelope = fingerprint(7).envelope();
+        for length in MAGIC.len()..envelope.len() {
+            assert!(matches!(
+                strip_envelope(&envelope[..length], TYPE_PATH, None),
+                Err(FingerprintError::Truncated)
+            ));
+            assert_eq!(skip_envelope(&envelope[..length]), &envelope[..length]);
+        }
+    }
+}
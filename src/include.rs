@@ -0,0 +1,274 @@
+use crate::data::{DataFormat, read_document};
+use crate::value::{Value, escape};
+use bevy_asset::{AssetPath, LoadContext, ReadAssetBytesError};
+use std::future::Future;
+use std::path::Component;
+use std::pin::Pin;
+use thiserror::Error;
+
+/// The only field of maps that are replaced by the document they include
+const INCLUDE_FIELD: &str = "$include";
+/// The yaml tag and ron enum variant of values that are replaced by the document they include
+const INCLUDE_TAG: &str = "include";
+
+/// Possible errors when splicing included documents into a document
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum IncludeError {
+    /// An include does not name an asset path
+    #[error("The include at `{pointer}` in `{path}` must be an asset path")]
+    InvalidInclude {
+        /// The path of the including document
+        path: String,
+        /// [JSON Pointer](https://www.rfc-editor.org/rfc/rfc6901) to the include
+        pointer: String,
+    },
+    /// An include is no valid asset path
+    #[error("Invalid include `{include}` in `{path}`")]
+    InvalidPath {
+        /// The path of the including document
+        path: String,
+        /// The include as written in the document
+        include: String,
+    },
+    /// An include points outside of the asset source of the including document
+    #[error("The include `{include}` in `{path}` is outside of its asset source")]
+    Escape {
+        /// The path of the including document
+        path: String,
+        /// The include as written in the document
+        include: String,
+    },
+    /// An included document could not be read
+    #[error("Could not read the include `{include}`: {source}")]
+    Read {
+        /// The path of the included document
+        include: String,
+        /// The [error](ReadAssetBytesError) reading the document
+        source: Box<ReadAssetBytesError>,
+    },
+    /// An included document could not be parsed in the format of the including document
+    #[error("Could not parse the include `{include}`: {message}")]
+    Parse {
+        /// The path of the included document
+        include: String,
+        /// Description of the problem
+        message: String,
+    },
+    /// A document includes itself through its includes
+    #[error("Include cycle: {}", chain.join(" -> "))]
+    Cycle {
+        /// The paths of the documents in the cycle, starting and ending with the same path
+        chain: Vec<String>,
+    },
+}
+
+/// Replace all includes in `document`, which was read from `path`, by the included documents
+///
+/// Included documents are read in `format` as dependencies of the loaded asset.
+pub(crate) async fn include(
+    document: &mut Value,
+    path: &AssetPath<'static>,
+    format: DataFormat,
+    load_context: &mut LoadContext<'_>,
+) -> Result<(), IncludeError> {
+    let mut chain = vec![path.clone()];
+    resolve(document, path, format, &mut chain, load_context).await
+}
+
+/// Resolves the includes of a document, boxed for the recursion into included documents
+fn resolve<'a, 'b>(
+    document: &'a mut Value,
+    path: &'a AssetPath<'static>,
+    format: DataFormat,
+    chain: &'a mut Vec<AssetPath<'static>>,
+    load_context: &'a mut LoadContext<'b>,
+) -> Pin<Box<dyn Future<Output = Result<(), IncludeError>> + Send + 'a>> {
+    Box::pin(async move {
+        let mut includes = vec![];
+        find_includes(document, String::new(), &mut includes);
+        for (pointer, include) in includes {
+            let include = include.ok_or_else(|| IncludeError::InvalidInclude {
+                path: path.to_string(),
+                pointer: pointer.clone(),
+            })?;
+            let included_path =
+                path.resolve_embed(&include)
+                    .map_err(|_| IncludeError::InvalidPath {
+                        path: path.to_string(),
+                        include: include.clone(),
+                    })?;
+            if included_path.source() != path.source() || escapes(path, &include, &included_path) {
+                return Err(IncludeError::Escape {
+                    path: path.to_string(),
+                    include,
+                });
+            }
+            if chain.contains(&included_path) {
+                let mut chain: Vec<String> = chain.iter().map(ToString::to_string).collect();
+                chain.push(included_path.to_string());
+                return Err(IncludeError::Cycle { chain });
+            }
+            let bytes = load_context
+                .read_asset_bytes(&included_path)
+                .await
+                .map_err(|source| IncludeError::Read {
+                    include: included_path.to_string(),
+                    source: Box::new(source),
+                })?;
+            let mut included =
+                read_document(format, &bytes).map_err(|error| IncludeError::Parse {
+                    include: included_path.to_string(),
+                    message: error.to_string(),
+                })?;
+            chain.push(included_path.clone());
+            resolve(&mut included, &included_path, format, chain, load_context).await?;
+            chain.pop();
+            if let Some(value) = document.pointer_mut(&pointer) {
+                *value = included;
+            }
+        }
+        Ok(())
+    })
+}
+
+/// Collect the pointers to all includes in `value` with the included paths
+///
+/// Includes without a path are collected with `None`.
+fn find_includes(value: &Value, pointer: String, includes: &mut Vec<(String, Option<String>)>) {
+    if let Some(include) = include_of(value) {
+        includes.push((pointer, include.map(str::to_owned)));
+        return;
+    }
+    match value.unnamed() {
+        Value::Map(map) => {
+            for (key, child) in map {
+                find_includes(child, format!("{pointer}/{}", escape(key)), includes);
+            }
+        }
+        Value::Seq(seq) => {
+            for (index, child) in seq.iter().enumerate() {
+                find_includes(child, format!("{pointer}/{index}"), includes);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// The included path if `value` is an include
+fn include_of(value: &Value) -> Option<Option<&str>> {
+    match value {
+        Value::Named(name, include) if name == INCLUDE_TAG => Some(match include.as_ref() {
+            // `include("path")` in ron is read as tuple
+            Value::Seq(items) if items.len() == 1 => items[0].as_str(),
+            include => include.as_str(),
+        }),
+        Value::Map(map) if map.len() == 1 => map.get(INCLUDE_FIELD).map(Value::as_str),
+        _ => None,
+    }
+}
+
+/// Whether `include` in the document at `path` leaves the root of its asset source
+///
+/// Resolving asset paths keeps `..` segments above the root only partially, so the segments
+/// of the include are checked as written as well as the resolved path.
+fn escapes(path: &AssetPath, include: &str, included_path: &AssetPath) -> bool {
+    let include = include.split('#').next().unwrap_or_default();
+    let (mut depth, relative) = match include.strip_prefix('/') {
+        Some(relative) => (0, relative),
+        None => (
+            path.path()
+                .parent()
+                .map_or(0, |parent| parent.components().count()),
+            include,
+        ),
+    };
+    for segment in relative.split(['/', '\\']) {
+        match segment {
+            "" | "." => {}
+            ".." if depth == 0 => return true,
+            ".." => depth -= 1,
+            _ => depth += 1,
+        }
+    }
+    included_path
+        .path()
+        .components()
+        .any(|component| !matches!(component, Component::Normal(_) | Component::CurDir))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn map<const N: usize>(entries: [(&str, Value); N]) -> Value {
+        Value::Map(
+            entries
+                .into_iter()
+                .map(|(key, value)| (key.to_owned(), value))
+                .collect(),
+        )
+    }
+
+    fn includes(value: &Value) -> Vec<(String, Option<String>)> {
+        let mut includes = vec![];
+        find_includes(value, String::new(), &mut includes);
+        includes
+    }
+
+    fn escaping(path: &str, include: &str) -> bool {
+        let path = AssetPath::parse(path);
+        let included_path = path.resolve_embed(include).unwrap();
+        escapes(&path, include, &included_path)
+    }
+
+    #[test]
+    fn includes_are_found_in_all_syntaxes() {
+        let named = |include: Value| Value::Named(INCLUDE_TAG.to_owned(), Box::new(include));
+        let document = map([
+            ("stats", map([("$include", "stats.json".into())])),
+            (
+                "enemies",
+                Value::Seq(vec![
+                    named("goblin.yaml".into()),
+                    named(Value::Seq(vec!["orc.ron".into()])),
+                ]),
+            ),
+            ("a/b", map([("$include", 1.into())])),
+            ("kept", map([("$include", "x".into()), ("other", 1.into())])),
+            (
+                "other",
+                Value::Named("other".to_owned(), Box::new("x".into())),
+            ),
+        ]);
+        assert_eq!(
+            includes(&document),
+            [
+                ("/stats".to_owned(), Some("stats.json".to_owned())),
+                ("/enemies/0".to_owned(), Some("goblin.yaml".to_owned())),
+                ("/enemies/1".to_owned(), Some("orc.ron".to_owned())),
+                ("/a~1b".to_owned(), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn the_whole_document_can_be_an_include() {
+        assert_eq!(
+            includes(&map([("$include", "base.json".into())])),
+            [(String::new(), Some("base.json".to_owned()))]
+        );
+    }
+
+    #[test]
+    fn includes_must_stay_in_their_asset_source() {
+        assert!(!escaping("levels/forest.json", "stats.json"));
+        assert!(!escaping("levels/forest.json", "../shared/stats.json"));
+        assert!(!escaping("levels/forest.json", "/shared/stats.json"));
+        assert!(!escaping("levels/forest.json", "./a/../stats.json"));
+        assert!(escaping("levels/forest.json", "../../stats.json"));
+        assert!(escaping("forest.json", "../stats.json"));
+        assert!(escaping("levels/forest.json", "/../stats.json"));
+        assert!(escaping("levels/forest.json", "a/../../../stats.json"));
+    }
+}
@@ -0,0 +1,538 @@
+use crate::data::{DataFormat, read_document};
+use crate::include::{IncludeError, include};
+use crate::value::{Value, unescape};
+use bevy_asset::{AssetPath, LoadContext, ReadAssetBytesError};
+use std::future::Future;
+use std::mem::take;
+use std::pin::Pin;
+use thiserror::Error;
+
+/// The default field of documents naming the documents they extend
+const EXTENDS_FIELD: &str = "$extends";
+
+/// Inheritance between documents of the json, ron, toml and yaml plugins
+///
+/// With `with_inheritance` on their plugins, a document can name the documents it extends in
+/// its `$extends` field, like `{"$extends": "base_unit.json", "hp": 200}`. The bases are read
+/// as loader dependencies, so changing a base reloads all documents extending it. Bases can
+/// extend other documents themselves, and a list of bases is merged in order before the
+/// document is merged onto the result.
+///
+/// Merging replaces values of the base with the values of the document, except for maps,
+/// which are merged key by key. How sequences and maps are merged can be configured for the
+/// whole document and for single paths. Paths use the syntax of
+/// [JSON Pointers](https://www.rfc-editor.org/rfc/rfc6901) and a `*` segment matches every
+/// key or index.
+///
+/// ```
+/// # use bevy_common_assets::inheritance::{ArrayMerge, Inheritance, MapMerge};
+/// let inheritance = Inheritance::default()
+///     .with_arrays(ArrayMerge::Append)
+///     .with_array_rule("/resistances", ArrayMerge::Replace)
+///     .with_map_rule("/weapon", MapMerge::Replace);
+/// ```
+///
+/// RON structs can not have a `$extends` field; use map syntax for such documents
+/// (`{"$extends": "base_unit.ron", "hp": 200}`) or [another field](Self::with_field).
+#[derive(Clone, Debug)]
+pub struct Inheritance {
+    field: String,
+    arrays: ArrayMerge,
+    maps: MapMerge,
+    rules: Vec<PathRule>,
+}
+
+impl Default for Inheritance {
+    fn default() -> Self {
+        Inheritance {
+            field: EXTENDS_FIELD.to_owned(),
+            arrays: ArrayMerge::default(),
+            maps: MapMerge::default(),
+            rules: vec![],
+        }
+    }
+}
+
+/// How a sequence of a document is merged onto the sequence of its base
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ArrayMerge {
+    /// Use the sequence of the document
+    #[default]
+    Replace,
+    /// Append the items of the document to the items of the base
+    Append,
+    /// Put the items of the document in front of the items of the base
+    Prepend,
+    /// Merge the items at the same index; additional items of either sequence are kept
+    ByIndex,
+}
+
+/// How a map of a document is merged onto the map of its base
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MapMerge {
+    /// Merge the values of keys in both maps and keep all other keys
+    #[default]
+    Deep,
+    /// Use the map of the document
+    Replace,
+}
+
+/// A merge rule for the values at a path
+#[derive(Clone, Debug)]
+struct PathRule {
+    segments: Vec<String>,
+    rule: MergeRule,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum MergeRule {
+    Arrays(ArrayMerge),
+    Maps(MapMerge),
+}
+
+impl Inheritance {
+    /// Name the bases of documents in `field` instead of `$extends`
+    pub fn with_field(mut self, field: impl Into<String>) -> Self {
+        self.field = field.into();
+        self
+    }
+
+    /// Merge all sequences with `merge`, unless a rule for their path says otherwise
+    pub fn with_arrays(mut self, merge: ArrayMerge) -> Self {
+        self.arrays = merge;
+        self
+    }
+
+    /// Merge all maps with `merge`, unless a rule for their path says otherwise
+    pub fn with_maps(mut self, merge: MapMerge) -> Self {
+        self.maps = merge;
+        self
+    }
+
+    /// Merge sequences at `path` with `merge`
+    ///
+    /// Later rules take precedence over earlier rules matching the same path.
+    pub fn with_array_rule(mut self, path: &str, merge: ArrayMerge) -> Self {
+        self.rules.push(PathRule {
+            segments: segments(path),
+            rule: MergeRule::Arrays(merge),
+        });
+        self
+    }
+
+    /// Merge maps at `path` with `merge`
+    ///
+    /// Later rules take precedence over earlier rules matching the same path.
+    pub fn with_map_rule(mut self, path: &str, merge: MapMerge) -> Self {
+        self.rules.push(PathRule {
+            segments: segments(path),
+            rule: MergeRule::Maps(merge),
+        });
+        self
+    }
+
+    fn arrays_at(&self, path: &[String]) -> ArrayMerge {
+        self.rules_at(path)
+            .find_map(|rule| match rule {
+                MergeRule::Arrays(merge) => Some(merge),
+                MergeRule::Maps(_) => None,
+            })
+            .unwrap_or(self.arrays)
+    }
+
+    fn maps_at(&self, path: &[String]) -> MapMerge {
+        self.rules_at(path)
+            .find_map(|rule| match rule {
+                MergeRule::Maps(merge) => Some(merge),
+                MergeRule::Arrays(_) => None,
+            })
+            .unwrap_or(self.maps)
+    }
+
+    /// The rules matching `path`, latest first
+    fn rules_at<'a>(&'a self, path: &'a [String]) -> impl Iterator<Item = MergeRule> + 'a {
+        self.rules
+            .iter()
+            .rev()
+            .filter(move |rule| {
+                rule.segments.len() == path.len()
+                    && rule
+                        .segments
+                        .iter()
+                        .zip(path)
+                        .all(|(segment, key)| segment == "*" || segment == key)
+            })
+            .map(|rule| rule.rule)
+    }
+}
+
+fn segments(path: &str) -> Vec<String> {
+    let path = path.trim_start_matches('/');
+    if path.is_empty() {
+        return vec![];
+    }
+    path.split('/').map(unescape).collect()
+}
+
+/// Possible errors when resolving the bases of a document
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum InheritanceError {
+    /// The field naming the bases is neither an asset path nor a list of asset paths
+    #[error("The `{field}` field of `{path}` must be an asset path or a list of asset paths")]
+    InvalidField {
+        /// The path of the document
+        path: String,
+        /// The field naming the bases
+        field: String,
+    },
+    /// A base is no valid asset path
+    #[error("Invalid base `{base}` in `{path}`")]
+    InvalidPath {
+        /// The path of the document
+        path: String,
+        /// The base as written in the document
+        base: String,
+    },
+    /// A base could not be read
+    #[error("Could not read the base `{base}`: {source}")]
+    Read {
+        /// The path of the base
+        base: String,
+        /// The [error](ReadAssetBytesError) reading the base
+        source: Box<ReadAssetBytesError>,
+    },
+    /// A base could not be parsed in the format of the document
+    #[error("Could not parse the base `{base}`: {message}")]
+    Parse {
+        /// The path of the base
+        base: String,
+        /// Description of the problem
+        message: String,
+    },
+    /// The includes of a base could not be resolved
+    #[error(transparent)]
+    Include(#[from] IncludeError),
+    /// A document extends itself through its bases
+    #[error("Inheritance cycle: {}", chain.join(" -> "))]
+    Cycle {
+        /// The paths of the documents in the cycle, starting and ending with the same path
+        chain: Vec<String>,
+    },
+}
+
+/// Merge `document` onto the documents named in its bases field
+///
+/// Bases are read in `format` as dependencies of the loaded asset. With `includes`, the
+/// included documents of bases are spliced into them before merging.
+pub(crate) async fn inherit(
+    document: &mut Value,
+    format: DataFormat,
+    inheritance: &Inheritance,
+    includes: bool,
+    load_context: &mut LoadContext<'_>,
+) -> Result<(), InheritanceError> {
+    let path = load_context.asset_path().clone_owned();
+    let mut chain = vec![path.clone()];
+    resolve(
+        document,
+        &path,
+        format,
+        inheritance,
+        includes,
+        &mut chain,
+        load_context,
+    )
+    .await
+}
+
+/// Resolves the bases of a document, boxed for the recursion into bases
+fn resolve<'a, 'b>(
+    document: &'a mut Value,
+    path: &'a AssetPath<'static>,
+    format: DataFormat,
+    inheritance: &'a Inheritance,
+    includes: bool,
+    chain: &'a mut Vec<AssetPath<'static>>,
+    load_context: &'a mut LoadContext<'b>,
+) -> Pin<Box<dyn Future<Output = Result<(), InheritanceError>> + Send + 'a>> {
+    Box::pin(async move {
+        let Some(field) = document
+            .as_map_mut()
+            .and_then(|map| map.shift_remove(&inheritance.field))
+        else {
+            return Ok(());
+        };
+        let invalid_field = || InheritanceError::InvalidField {
+            path: path.to_string(),
+            field: inheritance.field.clone(),
+        };
+        let bases = match field.unnamed() {
+            Value::String(base) => vec![base.clone()],
+            Value::Seq(bases) => bases
+                .iter()
+                .map(|base| base.as_str().map(str::to_owned).ok_or_else(invalid_field))
+                .collect::<Result<_, _>>()?,
+            _ => return Err(invalid_field()),
+        };
+        let mut merged: Option<Value> = None;
+        for base in bases {
+            let base_path =
+                path.resolve_embed(&base)
+                    .map_err(|_| InheritanceError::InvalidPath {
+                        path: path.to_string(),
+                        base: base.clone(),
+                    })?;
+            if chain.contains(&base_path) {
+                let mut chain: Vec<String> = chain.iter().map(ToString::to_string).collect();
+                chain.push(base_path.to_string());
+                return Err(InheritanceError::Cycle { chain });
+            }
+            let bytes = load_context
+                .read_asset_bytes(&base_path)
+                .await
+                .map_err(|source| InheritanceError::Read {
+                    base: base_path.to_string(),
+                    source: Box::new(source),
+                })?;
+            let mut base_document =
+                read_document(format, &bytes).map_err(|error| InheritanceError::Parse {
+                    base: base_path.to_string(),
+                    message: error.to_string(),
+                })?;
+            if includes {
+                include(&mut base_document, &base_path, format, load_context).await?;
+            }
+            chain.push(base_path.clone());
+            resolve(
+                &mut base_document,
+                &base_path,
+                format,
+                inheritance,
+                includes,
+                chain,
+                load_context,
+            )
+            .await?;
+            chain.pop();
+            merged = Some(match merged {
+                Some(merged) => merge(merged, base_document, inheritance, &mut vec![]),
+                None => base_document,
+            });
+        }
+        if let Some(merged) = merged {
+            *document = merge(merged, take(document), inheritance, &mut vec![]);
+        }
+        Ok(())
+    })
+}
+
+/// Merge `child` onto `base` at `path`
+pub(crate) fn merge(
+    base: Value,
+    child: Value,
+    inheritance: &Inheritance,
+    path: &mut Vec<String>,
+) -> Value {
+    let (base_name, base) = split_name(base);
+    let (child_name, child) = split_name(child);
+    // different enum variants or tagged values have nothing in common
+    let different_names = matches!(
+        (&base_name, &child_name),
+        (Some(base_name), Some(child_name))
+            if !base_name.is_empty() && !child_name.is_empty() && base_name != child_name
+    );
+    if different_names {
+        return Value::Named(child_name.unwrap_or_default(), Box::new(child));
+    }
+    let merged = match (base, child) {
+        (Value::Map(mut base), Value::Map(child))
+            if inheritance.maps_at(path) == MapMerge::Deep =>
+        {
+            for (key, value) in child {
+                match base.get_mut(&key) {
+                    Some(base_value) => {
+                        path.push(key.clone());
+                        *base_value = merge(take(base_value), value, inheritance, path);
+                        path.pop();
+                    }
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+            Value::Map(base)
+        }
+        (Value::Seq(mut base), Value::Seq(mut child)) => match inheritance.arrays_at(path) {
+            ArrayMerge::Replace => Value::Seq(child),
+            ArrayMerge::Append => {
+                base.append(&mut child);
+                Value::Seq(base)
+            }
+            ArrayMerge::Prepend => {
+                child.append(&mut base);
+                Value::Seq(child)
+            }
+            ArrayMerge::ByIndex => {
+                let mut child = child.into_iter();
+                for (index, base_value) in base.iter_mut().enumerate() {
+                    let Some(value) = child.next() else {
+                        break;
+                    };
+                    path.push(index.to_string());
+                    *base_value = merge(take(base_value), value, inheritance, path);
+                    path.pop();
+                }
+                base.extend(child);
+                Value::Seq(base)
+            }
+        },
+        (_, child) => child,
+    };
+    match child_name.or(base_name) {
+        Some(name) => Value::Named(name, Box::new(merged)),
+        None => merged,
+    }
+}
+
+fn split_name(value: Value) -> (Option<String>, Value) {
+    match value {
+        Value::Named(name, value) => (Some(name), *value),
+        value => (None, value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn map<const N: usize>(entries: [(&str, Value); N]) -> Value {
+        Value::Map(
+            entries
+                .into_iter()
+                .map(|(key, value)| (key.to_owned(), value))
+                .collect(),
+        )
+    }
+
+    fn seq<const N: usize>(items: [i32; N]) -> Value {
+        Value::Seq(items.into_iter().map(Value::from).collect())
+    }
+
+    fn merged(base: Value, child: Value, inheritance: &Inheritance) -> Value {
+        merge(base, child, inheritance, &mut vec![])
+    }
+
+    #[test]
+    fn maps_are_merged_deeply_by_default() {
+        let base = map([
+            ("hp", 100.into()),
+            (
+                "weapon",
+                map([("name", "sword".into()), ("damage", 5.into())]),
+            ),
+            ("tags", seq([1, 2])),
+        ]);
+        let child = map([
+            ("hp", 200.into()),
+            ("weapon", map([("damage", 7.into())])),
+            ("tags", seq([3])),
+        ]);
+        assert_eq!(
+            merged(base, child, &Inheritance::default()),
+            map([
+                ("hp", 200.into()),
+                (
+                    "weapon",
+                    map([("name", "sword".into()), ("damage", 7.into())])
+                ),
+                ("tags", seq([3])),
+            ])
+        );
+    }
+
+    #[test]
+    fn array_merges() {
+        let merged_with = |merge| {
+            merged(
+                seq([1, 2, 3]),
+                seq([4, 5]),
+                &Inheritance::default().with_arrays(merge),
+            )
+        };
+        assert_eq!(merged_with(ArrayMerge::Replace), seq([4, 5]));
+        assert_eq!(merged_with(ArrayMerge::Append), seq([1, 2, 3, 4, 5]));
+        assert_eq!(merged_with(ArrayMerge::Prepend), seq([4, 5, 1, 2, 3]));
+        assert_eq!(merged_with(ArrayMerge::ByIndex), seq([4, 5, 3]));
+    }
+
+    #[test]
+    fn rules_match_paths_with_wildcards() {
+        let inheritance = Inheritance::default()
+            .with_arrays(ArrayMerge::Append)
+            .with_array_rule("/units/*/tags", ArrayMerge::Replace)
+            .with_map_rule("/weapon", MapMerge::Replace);
+        let base = map([
+            ("units", map([("orc", map([("tags", seq([1]))]))])),
+            ("weapon", map([("name", "sword".into())])),
+            ("tags", seq([1])),
+        ]);
+        let child = map([
+            ("units", map([("orc", map([("tags", seq([2]))]))])),
+            ("weapon", map([("damage", 7.into())])),
+            ("tags", seq([2])),
+        ]);
+        assert_eq!(
+            merged(base, child, &inheritance),
+            map([
+                ("units", map([("orc", map([("tags", seq([2]))]))])),
+                ("weapon", map([("damage", 7.into())])),
+                ("tags", seq([1, 2])),
+            ])
+        );
+    }
+
+    #[test]
+    fn rules_match_keys_with_slashes_and_tildes() {
+        let inheritance = Inheritance::default()
+            .with_array_rule("/items/a~1b", ArrayMerge::Append)
+            .with_map_rule("/c~0d", MapMerge::Replace);
+        let base = map([
+            ("items", map([("a/b", seq([1]))])),
+            ("c~d", map([("x", 1.into())])),
+        ]);
+        let child = map([
+            ("items", map([("a/b", seq([2]))])),
+            ("c~d", map([("y", 2.into())])),
+        ]);
+        assert_eq!(
+            merged(base, child, &inheritance),
+            map([
+                ("items", map([("a/b", seq([1, 2]))])),
+                ("c~d", map([("y", 2.into())])),
+            ])
+        );
+    }
+
+    #[test]
+    fn names_are_kept_unless_they_differ() {
+        let named = |name: &str, value| Value::Named(name.to_owned(), Box::new(value));
+        let inheritance = Inheritance::default();
+        assert_eq!(
+            merged(
+                named("Unit", map([("hp", 1.into()), ("mp", 2.into())])),
+                map([("hp", 3.into())]),
+                &inheritance,
+            ),
+            named("Unit", map([("hp", 3.into()), ("mp", 2.into())]))
+        );
+        assert_eq!(
+            merged(
+                named("Sword", map([("damage", 1.into())])),
+                named("Bow", map([("range", 2.into())])),
+                &inheritance,
+            ),
+            named("Bow", map([("range", 2.into())]))
+        );
+    }
+}
@@ -1,49 +1,124 @@
+use crate::data::DataFormat;
+use crate::loader::{
+    AssetCheck, DocumentSettings, LoaderOptions, loader_options_methods, text_options_methods,
+};
+#[cfg(feature = "schema")]
+use crate::schema::{RegisterSchema, SchemaFormat, register_schema};
+use crate::stream::{Decoded, LoadedStream, read_records};
+use crate::value::Value;
+use crate::{DataAsset, DataAssetError};
 use bevy_app::{App, Plugin};
 use bevy_asset::io::Reader;
-use bevy_asset::{Asset, AssetApp, AssetLoader, LoadContext};
-use serde_json::from_slice;
+use bevy_asset::saver::AssetSaver;
+use bevy_asset::{AssetApp, AssetLoader, AsyncWriteExt, LoadContext};
+use bevy_reflect::TypePath;
+use serde::Serialize;
+use serde_json::{from_slice, to_vec};
 use std::marker::PhantomData;
 use thiserror::Error;
 
 /// Plugin to load your asset type `A` from json files.
 pub struct JsonAssetPlugin<A> {
     extensions: Vec<&'static str>,
-    _marker: PhantomData<A>,
+    options: LoaderOptions<A>,
+    #[cfg(feature = "schema")]
+    schema: Option<RegisterSchema>,
 }
 
 impl<A> Plugin for JsonAssetPlugin<A>
 where
-    for<'de> A: serde::Deserialize<'de> + Asset,
+    A: DataAsset,
 {
     fn build(&self, app: &mut App) {
-        app.init_asset::<A>()
-            .register_asset_loader(JsonAssetLoader::<A> {
-                extensions: self.extensions.clone(),
-                _marker: PhantomData,
-            });
+        crate::data_asset::init_data_asset::<A>(app);
+        self.register_loader(app);
     }
 }
 
 impl<A> JsonAssetPlugin<A>
 where
-    for<'de> A: serde::Deserialize<'de> + Asset,
+    A: DataAsset,
 {
     /// Create a new plugin that will load assets from files with the given extensions.
     pub fn new(extensions: &[&'static str]) -> Self {
         Self {
             extensions: extensions.to_owned(),
-            _marker: PhantomData,
+            options: LoaderOptions::default(),
+            #[cfg(feature = "schema")]
+            schema: None,
         }
     }
+
+    pub(crate) fn with_check(mut self, check: AssetCheck<A>) -> Self {
+        self.options.check = check;
+        self
+    }
+
+    /// Register the loader of this plugin without initializing the asset
+    pub(crate) fn register_loader(&self, app: &mut App) {
+        let options = self.options.build(app);
+        app.register_asset_loader(JsonAssetLoader::<A> {
+            extensions: self.extensions.clone(),
+            options,
+        });
+        #[cfg(feature = "schema")]
+        if let Some(register_schema) = self.schema {
+            register_schema(app, SchemaFormat::Json, &self.extensions);
+        }
+    }
+
+    loader_options_methods!();
+    text_options_methods!();
+}
+
+#[cfg(feature = "validation")]
+impl<A> JsonAssetPlugin<A>
+where
+    A: DataAsset,
+{
+    /// Validate loaded documents against a JSON Schema before deserializing them.
+    ///
+    /// See [`DocumentSettings::schema`](crate::DocumentSettings::schema) for where the schema
+    /// comes from. All places in a document not matching the schema are reported in one error.
+    pub fn with_schema_validation(mut self) -> Self {
+        self.options.validate = true;
+        self
+    }
+}
+
+#[cfg(feature = "schema")]
+impl<A> JsonAssetPlugin<A>
+where
+    A: DataAsset,
+    A::Definition: schemars::JsonSchema,
+{
+    /// Register the JSON Schema of the asset definition for the extensions of this plugin
+    ///
+    /// The schemas of all registered types can be written to disk with
+    /// [`AssetSchemas::write`](crate::schema::AssetSchemas::write).
+    pub fn with_schema(mut self) -> Self {
+        self.schema = Some(register_schema::<A>);
+        self
+    }
 }
 
 /// Loads your asset type `A` from json files
 pub struct JsonAssetLoader<A> {
     extensions: Vec<&'static str>,
-    _marker: PhantomData<A>,
+    options: LoaderOptions<A>,
 }
 
-/// Possible errors that can be produced by [`JsonAssetLoader`]
+impl<A> JsonAssetLoader<A> {
+    /// Create a loader without extensions for other loaders delegating to it
+    pub(crate) fn from_options(options: LoaderOptions<A>) -> Self {
+        Self {
+            extensions: vec![],
+            options,
+        }
+    }
+}
+
+/// Possible errors that can be produced by [`JsonAssetLoader`], [`JsonStreamAssetLoader`] or [`JsonStreamAssetSaver`]
 #[non_exhaustive]
 #[derive(Debug, Error)]
 pub enum JsonLoaderError {
@@ -53,13 +128,88 @@ pub enum JsonLoaderError {
     /// A [JSON Error](serde_json::error::Error)
     #[error("Could not parse the JSON: {0}")]
     JsonError(#[from] serde_json::error::Error),
+    /// An error of the format independent loading steps
+    #[error(transparent)]
+    DataAssetError(#[from] DataAssetError),
 }
 
 impl<A> AssetLoader for JsonAssetLoader<A>
 where
-    for<'de> A: serde::Deserialize<'de> + Asset,
+    A: DataAsset,
 {
     type Asset = A;
+    type Settings = DocumentSettings;
+    type Error = JsonLoaderError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        settings: &DocumentSettings,
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        if self.options.uses_value() {
+            let document = from_slice::<Value>(&bytes)?;
+            return Ok(self
+                .options
+                .load_document(document, DataFormat::Json, settings, load_context)
+                .await?);
+        }
+        let definition = from_slice::<A::Definition>(&bytes)?;
+        Ok(self.options.create_asset(definition, load_context).await?)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &self.extensions
+    }
+}
+
+/// Plugin to load streams of your asset type `A` from [JSON Lines](https://jsonlines.org/) files.
+///
+/// Every line of the file holds one record. The records are collected in a [`LoadedStream`] asset.
+pub struct JsonStreamAssetPlugin<A> {
+    extensions: Vec<&'static str>,
+    _marker: PhantomData<A>,
+}
+
+impl<A> Plugin for JsonStreamAssetPlugin<A>
+where
+    for<'de> A: serde::Deserialize<'de> + TypePath + Send + Sync + 'static,
+{
+    fn build(&self, app: &mut App) {
+        app.init_asset::<LoadedStream<A>>()
+            .register_asset_loader(JsonStreamAssetLoader::<A> {
+                extensions: self.extensions.clone(),
+                _marker: PhantomData,
+            });
+    }
+}
+
+impl<A> JsonStreamAssetPlugin<A>
+where
+    for<'de> A: serde::Deserialize<'de> + TypePath + Send + Sync + 'static,
+{
+    /// Create a new plugin that will load assets from files with the given extensions.
+    pub fn new(extensions: &[&'static str]) -> Self {
+        Self {
+            extensions: extensions.to_owned(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+/// Loads streams of your asset type `A` from JSON Lines files
+pub struct JsonStreamAssetLoader<A> {
+    extensions: Vec<&'static str>,
+    _marker: PhantomData<A>,
+}
+
+impl<A> AssetLoader for JsonStreamAssetLoader<A>
+where
+    for<'de> A: serde::Deserialize<'de> + TypePath + Send + Sync + 'static,
+{
+    type Asset = LoadedStream<A>;
     type Settings = ();
     type Error = JsonLoaderError;
 
@@ -69,13 +219,77 @@ where
         _settings: &(),
         _load_context: &mut LoadContext<'_>,
     ) -> Result<Self::Asset, Self::Error> {
-        let mut bytes = Vec::new();
-        reader.read_to_end(&mut bytes).await?;
-        let asset = from_slice::<A>(&bytes)?;
-        Ok(asset)
+        let records = read_records(
+            reader,
+            |bytes, scanned, end_of_file| -> Result<_, JsonLoaderError> {
+                let length = match bytes[scanned..].iter().position(|byte| *byte == b'\n') {
+                    Some(line_end) => scanned + line_end + 1,
+                    None if end_of_file => bytes.len(),
+                    None => return Ok(Decoded::Incomplete),
+                };
+                let line = &bytes[..length];
+                if line.iter().all(u8::is_ascii_whitespace) {
+                    return Ok(Decoded::Skip(length));
+                }
+                Ok(Decoded::Record(from_slice::<A>(line)?, length))
+            },
+        )
+        .await?;
+        Ok(LoadedStream { records })
     }
 
     fn extensions(&self) -> &[&str] {
         &self.extensions
     }
 }
+
+/// Saves streams of your asset type `A` to JSON Lines files
+pub struct JsonStreamAssetSaver<A> {
+    _marker: PhantomData<A>,
+}
+
+impl<A> Default for JsonStreamAssetSaver<A> {
+    fn default() -> Self {
+        Self {
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<A> JsonStreamAssetSaver<A>
+where
+    A: Serialize,
+{
+    /// Write `record` at the end of `writer`, see [`LoadedStream`]
+    pub async fn append_record(
+        writer: &mut bevy_asset::io::Writer,
+        record: &A,
+    ) -> Result<(), JsonLoaderError> {
+        let mut line = to_vec(record)?;
+        line.push(b'\n');
+        writer.write_all(&line).await?;
+        Ok(())
+    }
+}
+
+impl<A> AssetSaver for JsonStreamAssetSaver<A>
+where
+    for<'de> A: serde::Deserialize<'de> + Serialize + TypePath + Send + Sync + 'static,
+{
+    type Asset = LoadedStream<A>;
+    type Settings = ();
+    type OutputLoader = JsonStreamAssetLoader<A>;
+    type Error = JsonLoaderError;
+
+    async fn save(
+        &self,
+        writer: &mut bevy_asset::io::Writer,
+        asset: bevy_asset::saver::SavedAsset<'_, Self::Asset>,
+        _settings: &Self::Settings,
+    ) -> Result<<Self::OutputLoader as AssetLoader>::Settings, Self::Error> {
+        for record in asset.get().records.iter() {
+            Self::append_record(writer, record).await?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,125 @@
+use crate::value::{Value, escape, unescape};
+use crate::{DataAsset, DataAssetError};
+use bevy_app::App;
+use bevy_asset::{AssetApp, Assets, LoadContext};
+use serde::Deserialize;
+use std::future::Future;
+use std::pin::Pin;
+
+/// Deserializes a value and adds it as labeled sub-asset of the given type
+type AddLabeledAsset =
+    for<'a, 'b> fn(
+        String,
+        Value,
+        &'a mut LoadContext<'b>,
+    ) -> Pin<Box<dyn Future<Output = Result<(), DataAssetError>> + Send + 'a>>;
+
+/// A path inside of documents that is added as labeled sub-asset
+#[derive(Clone)]
+pub(crate) struct LabeledPath {
+    /// Segments of the path; `*` matches every key or index
+    segments: Vec<String>,
+    add_labeled_asset: AddLabeledAsset,
+    init_asset: fn(&mut App),
+}
+
+impl LabeledPath {
+    pub(crate) fn new<T: DataAsset>(path: &str) -> Self {
+        LabeledPath {
+            segments: path
+                .trim_start_matches('/')
+                .split('/')
+                .map(unescape)
+                .collect(),
+            add_labeled_asset: |label, value, load_context| {
+                Box::pin(add_labeled_asset::<T>(label, value, load_context))
+            },
+            init_asset: |app| {
+                if !app.world().contains_resource::<Assets<T>>() {
+                    app.init_asset::<T>();
+                }
+            },
+        }
+    }
+
+    pub(crate) fn init_asset(&self, app: &mut App) {
+        (self.init_asset)(app);
+    }
+
+    /// Add labeled sub-assets for all values in `document` matching this path
+    pub(crate) async fn add_labeled_assets(
+        &self,
+        document: &Value,
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<(), DataAssetError> {
+        let mut matches = vec![(String::new(), document)];
+        for segment in &self.segments {
+            let mut next = vec![];
+            for (label, value) in matches {
+                let children: Vec<(String, &Value)> = match value.unnamed() {
+                    Value::Map(map) if segment == "*" => map
+                        .iter()
+                        .map(|(key, child)| (escape(key), child))
+                        .collect(),
+                    Value::Seq(list) if segment == "*" => list
+                        .iter()
+                        .enumerate()
+                        .map(|(index, child)| (index.to_string(), child))
+                        .collect(),
+                    Value::Map(map) => map
+                        .get(segment)
+                        .map(|child| (escape(segment), child))
+                        .into_iter()
+                        .collect(),
+                    Value::Seq(list) => segment
+                        .parse::<usize>()
+                        .ok()
+                        .and_then(|index| list.get(index))
+                        .map(|child| (segment.clone(), child))
+                        .into_iter()
+                        .collect(),
+                    _ => vec![],
+                };
+                if children.is_empty() && segment != "*" {
+                    return Err(DataAssetError::PathNotFound(join(&label, &escape(segment))));
+                }
+                next.extend(
+                    children
+                        .into_iter()
+                        .map(|(key, child)| (join(&label, &key), child)),
+                );
+            }
+            matches = next;
+        }
+        for (label, value) in matches {
+            (self.add_labeled_asset)(label, value.clone(), load_context).await?;
+        }
+        Ok(())
+    }
+}
+
+async fn add_labeled_asset<T: DataAsset>(
+    label: String,
+    value: Value,
+    load_context: &mut LoadContext<'_>,
+) -> Result<(), DataAssetError> {
+    let definition =
+        T::Definition::deserialize(value).map_err(|source| DataAssetError::Deserialize {
+            path: label.clone(),
+            source,
+        })?;
+    let mut asset_context = load_context.begin_labeled_asset();
+    let asset = T::from_definition(definition, &mut asset_context)
+        .await
+        .map_err(DataAssetError::Definition)?;
+    load_context.add_loaded_labeled_asset(label, asset_context.finish(asset));
+    Ok(())
+}
+
+fn join(path: &str, segment: &str) -> String {
+    if path.is_empty() {
+        segment.to_owned()
+    } else {
+        format!("{path}/{segment}")
+    }
+}
@@ -52,30 +52,166 @@
 #[cfg_attr(docsrs, doc(cfg(feature = "cbor")))]
 #[cfg(feature = "cbor")]
 pub mod cbor;
+/// Module containing the messages reporting the changed fields of modified assets.
+#[cfg(any(
+    feature = "json",
+    feature = "ron",
+    feature = "toml",
+    feature = "yaml",
+    feature = "msgpack",
+    feature = "xml",
+    feature = "postcard",
+    feature = "cbor"
+))]
+pub mod change;
+/// Module containing collection assets that can be loaded by every format plugin.
+pub mod collection;
+/// Module containing a Bevy plugin keeping a resource in sync with a config file.
+#[cfg(any(
+    feature = "json",
+    feature = "ron",
+    feature = "toml",
+    feature = "yaml",
+    feature = "msgpack",
+    feature = "xml",
+    feature = "postcard",
+    feature = "cbor"
+))]
+pub mod config;
 /// Module containing a Bevy plugin to load assets from `csv` files with custom file extensions.
 #[cfg_attr(docsrs, doc(cfg(feature = "csv")))]
 #[cfg(feature = "csv")]
 pub mod csv;
+/// Module containing a Bevy plugin to load assets from files of any enabled format, detected from their content.
+#[cfg(any(
+    feature = "json",
+    feature = "msgpack",
+    feature = "ron",
+    feature = "toml",
+    feature = "xml",
+    feature = "yaml",
+    feature = "postcard",
+    feature = "cbor"
+))]
+pub mod data;
+/// Module containing the editing of documents that keeps their comments and formatting.
+#[cfg(any(feature = "ron", feature = "toml", feature = "yaml"))]
+pub mod edit;
+/// Module containing the fingerprint envelope that binary savers can write to detect stale assets.
+#[cfg(any(feature = "postcard", feature = "cbor"))]
+pub mod fingerprint;
+/// Module containing the errors of including documents in documents of the text formats.
+#[cfg(any(feature = "json", feature = "ron", feature = "toml", feature = "yaml"))]
+pub mod include;
+/// Module containing the inheritance between documents of the text formats.
+#[cfg(any(feature = "json", feature = "ron", feature = "toml", feature = "yaml"))]
+pub mod inheritance;
 /// Module containing a Bevy plugin to load assets from `json` files with custom file extensions.
 #[cfg_attr(docsrs, doc(cfg(feature = "json")))]
 #[cfg(feature = "json")]
 pub mod json;
+/// Module containing versioned asset types and the migrations of their documents.
+pub mod migration;
 /// Module containing a Bevy plugin to load assets from `MessagePack` files with custom file extensions.
 #[cfg_attr(docsrs, doc(cfg(feature = "msgpack")))]
 #[cfg(feature = "msgpack")]
 pub mod msgpack;
+/// Module containing layers of data from other asset sources applied to documents of the text formats.
+#[cfg(any(feature = "json", feature = "ron", feature = "toml", feature = "yaml"))]
+pub mod overlay;
 /// Module containing a Bevy plugin to load assets from `postcard` files with custom file extensions.
 #[cfg_attr(docsrs, doc(cfg(feature = "postcard")))]
 #[cfg(feature = "postcard")]
 pub mod postcard;
+/// Module containing the user preferences loaded from and written back to their files.
+#[cfg(any(
+    feature = "json",
+    feature = "ron",
+    feature = "toml",
+    feature = "yaml",
+    feature = "msgpack",
+    feature = "xml",
+    feature = "postcard",
+    feature = "cbor"
+))]
+pub mod preferences;
 /// Module containing a Bevy plugin to load assets from `ron` files with custom file extensions.
 #[cfg_attr(docsrs, doc(cfg(feature = "ron")))]
 #[cfg(feature = "ron")]
 pub mod ron;
+/// Module containing the runtime API writing modified assets back to their files.
+#[cfg(any(
+    feature = "json",
+    feature = "ron",
+    feature = "toml",
+    feature = "yaml",
+    feature = "msgpack",
+    feature = "xml",
+    feature = "postcard",
+    feature = "cbor"
+))]
+pub mod save;
+/// Module containing save games in numbered slots with checksums, compression and backups.
+#[cfg_attr(docsrs, doc(cfg(feature = "save_game")))]
+#[cfg(all(
+    feature = "save_game",
+    any(
+        feature = "json",
+        feature = "ron",
+        feature = "toml",
+        feature = "yaml",
+        feature = "msgpack",
+        feature = "xml",
+        feature = "postcard",
+        feature = "cbor"
+    )
+))]
+pub mod save_game;
+/// Module containing loaders and savers for Bevy scenes in the data formats.
+#[cfg_attr(docsrs, doc(cfg(feature = "scene")))]
+#[cfg(all(
+    feature = "scene",
+    any(
+        feature = "json",
+        feature = "ron",
+        feature = "toml",
+        feature = "yaml",
+        feature = "msgpack",
+        feature = "cbor"
+    )
+))]
+pub mod scene;
+/// Module containing the JSON Schemas of asset types for editor autocompletion and validation.
+#[cfg_attr(docsrs, doc(cfg(feature = "schema")))]
+#[cfg(all(
+    feature = "schema",
+    any(feature = "json", feature = "toml", feature = "yaml")
+))]
+pub mod schema;
+/// Module containing the asset type shared by the loaders for streams of records.
+#[cfg(any(
+    feature = "json",
+    feature = "msgpack",
+    feature = "postcard",
+    feature = "cbor"
+))]
+pub mod stream;
+/// Module containing the substitution of variables into documents of the text formats.
+#[cfg(any(feature = "json", feature = "ron", feature = "toml", feature = "yaml"))]
+pub mod substitution;
 /// Module containing a Bevy plugin to load assets from `toml` files with custom file extensions.
 #[cfg_attr(docsrs, doc(cfg(feature = "toml")))]
 #[cfg(feature = "toml")]
 pub mod toml;
+/// Module containing the validation of documents against JSON Schemas while loading them.
+#[cfg_attr(docsrs, doc(cfg(feature = "validation")))]
+#[cfg(feature = "validation")]
+pub mod validation;
+/// Module containing the format independent tree of values in a document.
+pub mod value;
+/// Module containing the profile variants of documents of the text formats.
+#[cfg(any(feature = "json", feature = "ron", feature = "toml", feature = "yaml"))]
+pub mod variant;
 /// Module containing a Bevy plugin to load assets from `xml` files with custom file extensions.
 #[cfg_attr(docsrs, doc(cfg(feature = "xml")))]
 #[cfg(feature = "xml")]
@@ -85,6 +221,83 @@ pub mod xml;
 #[cfg(feature = "yaml")]
 pub mod yaml;
 
+mod data_asset;
+#[cfg(any(feature = "json", feature = "ron", feature = "toml", feature = "yaml"))]
+mod dependency;
+#[cfg(feature = "derive")]
+mod derive;
+#[cfg(any(
+    feature = "json",
+    feature = "msgpack",
+    feature = "ron",
+    feature = "toml",
+    feature = "yaml",
+    feature = "cbor"
+))]
+mod label;
+#[cfg(any(
+    feature = "json",
+    feature = "msgpack",
+    feature = "ron",
+    feature = "toml",
+    feature = "xml",
+    feature = "yaml",
+    feature = "postcard",
+    feature = "cbor"
+))]
+mod loader;
+
+#[cfg(any(
+    feature = "json",
+    feature = "msgpack",
+    feature = "ron",
+    feature = "toml",
+    feature = "xml",
+    feature = "yaml",
+    feature = "postcard",
+    feature = "cbor"
+))]
+mod plugin;
+
+pub use data_asset::{DataAsset, DefinitionError, ToDefinition, ValidateAsset, ValidationMode};
+#[cfg(any(
+    feature = "json",
+    feature = "msgpack",
+    feature = "ron",
+    feature = "toml",
+    feature = "xml",
+    feature = "yaml",
+    feature = "postcard",
+    feature = "cbor"
+))]
+pub use loader::DataAssetError;
+#[cfg(any(feature = "json", feature = "ron", feature = "toml", feature = "yaml"))]
+pub use loader::DocumentSettings;
+#[cfg(any(
+    feature = "json",
+    feature = "msgpack",
+    feature = "ron",
+    feature = "toml",
+    feature = "xml",
+    feature = "yaml",
+    feature = "postcard",
+    feature = "cbor"
+))]
+pub use plugin::{CommonAsset, CommonAssetApp, CommonAssetsPlugin};
+
+/// Derive macro for [`CommonAsset`]
+#[cfg_attr(docsrs, doc(cfg(feature = "derive")))]
+#[cfg(feature = "derive")]
+pub use bevy_common_assets_derive::CommonAsset;
+
+/// Items used by the code generated by the derive macro
+#[doc(hidden)]
+#[cfg(feature = "derive")]
+pub mod __private {
+    pub use bevy_asset;
+    pub use bevy_reflect;
+}
+
 #[cfg(all(
     feature = "json",
     feature = "msgpack",
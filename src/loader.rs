@@ -0,0 +1,600 @@
+#[cfg(any(
+    feature = "json",
+    feature = "msgpack",
+    feature = "ron",
+    feature = "toml",
+    feature = "yaml",
+    feature = "cbor"
+))]
+use crate::label::LabeledPath;
+use crate::migration::MigrationError;
+use crate::value::Value;
+use crate::{DataAsset, DefinitionError, ValidateAsset, ValidationMode};
+use bevy_app::App;
+use bevy_asset::LoadContext;
+#[cfg(any(
+    feature = "json",
+    feature = "msgpack",
+    feature = "ron",
+    feature = "toml",
+    feature = "yaml",
+    feature = "cbor"
+))]
+use serde::Deserialize;
+#[cfg(any(feature = "json", feature = "ron", feature = "toml", feature = "yaml"))]
+use serde::Serialize;
+#[cfg(any(feature = "json", feature = "ron", feature = "toml", feature = "yaml"))]
+use std::collections::BTreeMap;
+use std::marker::PhantomData;
+use thiserror::Error;
+use tracing::warn;
+
+/// Possible errors of the format independent loading steps
+///
+/// The errors of all format loaders that support these steps can be created from this error.
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum DataAssetError {
+    /// A path configured for labeled sub-assets does not exist in the document
+    #[error("There is no value at the path `{0}`")]
+    PathNotFound(String),
+    /// The value at a path could not be deserialized
+    #[error("Could not deserialize the value at `{path}`: {source}")]
+    Deserialize {
+        /// The path of the value; empty for the whole document
+        path: String,
+        /// The [deserialization error](serde::de::value::Error)
+        source: serde::de::value::Error,
+    },
+    /// The document could not be migrated to the current version of the asset type
+    #[error("Could not migrate the document: {0}")]
+    Migration(#[from] MigrationError),
+    /// The asset could not be created from its definition, see [`DataAsset::from_definition`]
+    #[error("Could not create the asset from its definition: {0}")]
+    Definition(DefinitionError),
+    /// The asset failed its [`ValidateAsset`] check
+    #[error("The asset is invalid: {0}")]
+    Invalid(String),
+    /// The includes of the document could not be resolved
+    #[cfg(any(feature = "json", feature = "ron", feature = "toml", feature = "yaml"))]
+    #[error(transparent)]
+    Include(#[from] crate::include::IncludeError),
+    /// The bases of the document could not be resolved
+    #[cfg(any(feature = "json", feature = "ron", feature = "toml", feature = "yaml"))]
+    #[error(transparent)]
+    Inheritance(#[from] crate::inheritance::InheritanceError),
+    /// The profile variants of the document could not be applied
+    #[cfg(any(feature = "json", feature = "ron", feature = "toml", feature = "yaml"))]
+    #[error(transparent)]
+    Variant(#[from] crate::variant::VariantError),
+    /// The placeholders of the document could not be substituted
+    #[cfg(any(feature = "json", feature = "ron", feature = "toml", feature = "yaml"))]
+    #[error(transparent)]
+    Substitution(#[from] crate::substitution::SubstitutionError),
+    /// The overlays of the document could not be applied
+    #[cfg(any(feature = "json", feature = "ron", feature = "toml", feature = "yaml"))]
+    #[error(transparent)]
+    Overlay(#[from] crate::overlay::OverlayError),
+    /// The document does not match its schema
+    #[cfg(feature = "validation")]
+    #[error(transparent)]
+    Validation(#[from] crate::validation::ValidationError),
+}
+
+/// Migrates a document to the current version of the asset and returns whether it changed
+type MigrateDocument = fn(&mut Value) -> Result<bool, MigrationError>;
+
+/// Settings of the json, ron, toml and yaml loaders
+///
+/// They can be set per file in its `.meta` file or when loading it with
+/// [`AssetServer::load_with_settings`](bevy_asset::AssetServer::load_with_settings).
+#[cfg(any(feature = "json", feature = "ron", feature = "toml", feature = "yaml"))]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DocumentSettings {
+    /// Path of the JSON Schema file to validate documents against
+    ///
+    /// With `with_schema_validation` on the json, toml and yaml plugins, documents are checked
+    /// against this schema or the one referenced by their `$schema` field after migrating and
+    /// before deserializing them. Documents without a schema are loaded without validation. Relative paths are
+    /// resolved from the directory of the document and paths starting with `/` from the root of
+    /// its asset source. This overrides the `$schema` field of the document.
+    #[cfg(feature = "validation")]
+    pub schema: Option<String>,
+    /// Variables for the placeholders of the document
+    ///
+    /// With `with_substitution` on the plugins, these take precedence over the variables of
+    /// the [`Substitution`](crate::substitution::Substitution).
+    pub variables: BTreeMap<String, Value>,
+}
+
+/// Checks an asset and describes its problems, see [`ValidateAsset::validate`]
+type Validate<A> = fn(&A) -> Result<(), String>;
+
+/// Checks loaded assets with their [`ValidateAsset`] implementation
+pub(crate) struct AssetCheck<A> {
+    validate: Option<Validate<A>>,
+    mode: ValidationMode,
+}
+
+impl<A> Default for AssetCheck<A> {
+    fn default() -> Self {
+        AssetCheck {
+            validate: None,
+            mode: ValidationMode::Fail,
+        }
+    }
+}
+
+impl<A> Clone for AssetCheck<A> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<A> Copy for AssetCheck<A> {}
+
+impl<A> AssetCheck<A> {
+    pub(crate) fn new(mode: ValidationMode) -> Self
+    where
+        A: ValidateAsset,
+    {
+        AssetCheck {
+            validate: Some(A::validate),
+            mode,
+        }
+    }
+
+    /// Check a loaded asset, failing or warning depending on the [`ValidationMode`]
+    pub(crate) fn check(
+        &self,
+        asset: &A,
+        load_context: &LoadContext,
+    ) -> Result<(), DataAssetError> {
+        match self.require(asset) {
+            Err(DataAssetError::Invalid(message)) if self.mode == ValidationMode::Warn => {
+                warn!(
+                    "The asset '{}' is invalid: {message}",
+                    load_context.asset_path()
+                );
+                Ok(())
+            }
+            result => result,
+        }
+    }
+
+    /// Check an asset, failing if it is invalid regardless of the [`ValidationMode`]
+    pub(crate) fn require(&self, asset: &A) -> Result<(), DataAssetError> {
+        match self.validate {
+            Some(validate) => validate(asset).map_err(DataAssetError::Invalid),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Configuration of the format independent loading steps shared by the format plugins
+pub(crate) struct LoaderOptions<A> {
+    #[cfg(any(
+        feature = "json",
+        feature = "msgpack",
+        feature = "ron",
+        feature = "toml",
+        feature = "yaml",
+        feature = "cbor"
+    ))]
+    pub(crate) labeled_paths: Vec<LabeledPath>,
+    /// Migrates documents to the current version of the asset
+    pub(crate) migrate: Option<MigrateDocument>,
+    /// Checks loaded assets
+    pub(crate) check: AssetCheck<A>,
+    /// Validates documents against their schema
+    #[cfg(feature = "validation")]
+    pub(crate) validate: bool,
+    /// Splices included documents into documents
+    #[cfg(any(feature = "json", feature = "ron", feature = "toml", feature = "yaml"))]
+    pub(crate) includes: bool,
+    /// Merges documents onto the documents they extend
+    #[cfg(any(feature = "json", feature = "ron", feature = "toml", feature = "yaml"))]
+    pub(crate) inheritance: Option<crate::inheritance::Inheritance>,
+    /// Merges the variants of documents for the active profiles onto them
+    #[cfg(any(feature = "json", feature = "ron", feature = "toml", feature = "yaml"))]
+    pub(crate) variants: Option<crate::variant::Variants>,
+    /// Applies layers from other asset sources to documents
+    #[cfg(any(feature = "json", feature = "ron", feature = "toml", feature = "yaml"))]
+    pub(crate) overlays: Option<crate::overlay::Overlays>,
+    /// Replaces placeholders in documents by the values of variables
+    #[cfg(any(feature = "json", feature = "ron", feature = "toml", feature = "yaml"))]
+    pub(crate) substitution: Option<crate::substitution::Substitution>,
+    /// Registers the change messages of the asset
+    pub(crate) changes: Option<fn(&mut App)>,
+    _marker: PhantomData<fn() -> A>,
+}
+
+impl<A> Default for LoaderOptions<A> {
+    fn default() -> Self {
+        LoaderOptions {
+            #[cfg(any(
+                feature = "json",
+                feature = "msgpack",
+                feature = "ron",
+                feature = "toml",
+                feature = "yaml",
+                feature = "cbor"
+            ))]
+            labeled_paths: vec![],
+            migrate: None,
+            check: AssetCheck::default(),
+            #[cfg(feature = "validation")]
+            validate: false,
+            #[cfg(any(feature = "json", feature = "ron", feature = "toml", feature = "yaml"))]
+            inheritance: None,
+            #[cfg(any(feature = "json", feature = "ron", feature = "toml", feature = "yaml"))]
+            variants: None,
+            #[cfg(any(feature = "json", feature = "ron", feature = "toml", feature = "yaml"))]
+            overlays: None,
+            #[cfg(any(feature = "json", feature = "ron", feature = "toml", feature = "yaml"))]
+            substitution: None,
+            #[cfg(any(feature = "json", feature = "ron", feature = "toml", feature = "yaml"))]
+            includes: false,
+            changes: None,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<A> Clone for LoaderOptions<A> {
+    fn clone(&self) -> Self {
+        LoaderOptions {
+            #[cfg(any(
+                feature = "json",
+                feature = "msgpack",
+                feature = "ron",
+                feature = "toml",
+                feature = "yaml",
+                feature = "cbor"
+            ))]
+            labeled_paths: self.labeled_paths.clone(),
+            migrate: self.migrate,
+            check: self.check,
+            #[cfg(feature = "validation")]
+            validate: self.validate,
+            #[cfg(any(feature = "json", feature = "ron", feature = "toml", feature = "yaml"))]
+            inheritance: self.inheritance.clone(),
+            #[cfg(any(feature = "json", feature = "ron", feature = "toml", feature = "yaml"))]
+            variants: self.variants.clone(),
+            #[cfg(any(feature = "json", feature = "ron", feature = "toml", feature = "yaml"))]
+            overlays: self.overlays.clone(),
+            #[cfg(any(feature = "json", feature = "ron", feature = "toml", feature = "yaml"))]
+            substitution: self.substitution.clone(),
+            #[cfg(any(feature = "json", feature = "ron", feature = "toml", feature = "yaml"))]
+            includes: self.includes,
+            changes: self.changes,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<A: DataAsset> LoaderOptions<A> {
+    /// Register everything the configured steps need and return the options for the loaders
+    pub(crate) fn build(&self, app: &mut App) -> Self {
+        #[cfg(any(
+            feature = "json",
+            feature = "msgpack",
+            feature = "ron",
+            feature = "toml",
+            feature = "yaml",
+            feature = "cbor"
+        ))]
+        for labeled_path in &self.labeled_paths {
+            labeled_path.init_asset(app);
+        }
+        if let Some(register) = self.changes {
+            register(app);
+        }
+        let options = self.clone();
+        #[cfg(any(feature = "json", feature = "ron", feature = "toml", feature = "yaml"))]
+        let options = LoaderOptions {
+            variants: self
+                .variants
+                .as_ref()
+                .map(|variants| variants.connected(app)),
+            overlays: self
+                .overlays
+                .as_ref()
+                .map(|overlays| overlays.connected(app)),
+            ..options
+        };
+        options
+    }
+
+    #[cfg(any(
+        feature = "json",
+        feature = "msgpack",
+        feature = "ron",
+        feature = "toml",
+        feature = "yaml",
+        feature = "cbor"
+    ))]
+    /// Whether documents need to be loaded through a [`Value`] tree instead of directly
+    /// deserializing the asset
+    pub(crate) fn uses_value(&self) -> bool {
+        #[cfg(feature = "validation")]
+        if self.validate {
+            return true;
+        }
+        #[cfg(any(feature = "json", feature = "ron", feature = "toml", feature = "yaml"))]
+        if self.inheritance.is_some()
+            || self.includes
+            || self.variants.is_some()
+            || self.overlays.is_some()
+            || self.substitution.is_some()
+        {
+            return true;
+        }
+        !self.labeled_paths.is_empty() || self.migrate.is_some()
+    }
+
+    /// Splice included documents into `document`, merge it onto the documents it extends, merge
+    /// its profile variants onto it and apply its overlays if configured
+    ///
+    /// Included documents, bases, variants and layers are read in `format`, the format of the document.
+    #[cfg(any(feature = "json", feature = "ron", feature = "toml", feature = "yaml"))]
+    pub(crate) async fn compose(
+        &self,
+        document: &mut Value,
+        format: crate::data::DataFormat,
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<(), DataAssetError> {
+        if self.includes {
+            let path = load_context.asset_path().clone_owned();
+            crate::include::include(document, &path, format, load_context).await?;
+        }
+        if let Some(inheritance) = &self.inheritance {
+            crate::inheritance::inherit(document, format, inheritance, self.includes, load_context)
+                .await?;
+        }
+        if let Some(variants) = &self.variants {
+            crate::variant::apply(document, format, variants, self.includes, load_context).await?;
+        }
+        if let Some(overlays) = &self.overlays {
+            crate::overlay::apply(document, format, overlays, load_context).await?;
+        }
+        Ok(())
+    }
+
+    /// Compose `document`, substitute its placeholders, migrate it and validate it if
+    /// configured, run all other steps on it and create the asset
+    #[cfg(any(feature = "json", feature = "ron", feature = "toml", feature = "yaml"))]
+    pub(crate) async fn load_document(
+        &self,
+        document: Value,
+        format: crate::data::DataFormat,
+        settings: &DocumentSettings,
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<A, DataAssetError> {
+        let document = self
+            .prepare_document(document, format, settings, load_context)
+            .await?;
+        self.load_migrated(document, load_context).await
+    }
+
+    /// Compose `document`, substitute its placeholders, migrate it and validate it if
+    /// configured
+    ///
+    /// Documents are validated after migrating them, so schemas only need to describe the
+    /// current version of the asset. The files read by these steps are dependencies of the
+    /// asset of `load_context`.
+    #[cfg(any(feature = "json", feature = "ron", feature = "toml", feature = "yaml"))]
+    pub(crate) async fn prepare_document(
+        &self,
+        mut document: Value,
+        format: crate::data::DataFormat,
+        settings: &DocumentSettings,
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<Value, DataAssetError> {
+        self.compose(&mut document, format, load_context).await?;
+        if let Some(substitution) = &self.substitution {
+            crate::substitution::substitute(
+                &mut document,
+                format,
+                substitution,
+                &settings.variables,
+                load_context,
+            )
+            .await?;
+        }
+        if let Some(migrate) = self.migrate {
+            migrate(&mut document)?;
+        }
+        #[cfg(feature = "validation")]
+        if self.validate {
+            crate::validation::validate(&mut document, settings, load_context).await?;
+        }
+        Ok(document)
+    }
+
+    /// Run all configured steps on `document` and create the asset from it
+    #[cfg(any(feature = "msgpack", feature = "cbor"))]
+    pub(crate) async fn load_value(
+        &self,
+        mut document: Value,
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<A, DataAssetError> {
+        if let Some(migrate) = self.migrate {
+            migrate(&mut document)?;
+        }
+        self.load_migrated(document, load_context).await
+    }
+
+    #[cfg(any(
+        feature = "json",
+        feature = "msgpack",
+        feature = "ron",
+        feature = "toml",
+        feature = "yaml",
+        feature = "cbor"
+    ))]
+    /// Add the labeled sub-assets of the migrated `document` and create the asset from it
+    pub(crate) async fn load_migrated(
+        &self,
+        document: Value,
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<A, DataAssetError> {
+        for labeled_path in &self.labeled_paths {
+            labeled_path
+                .add_labeled_assets(&document, load_context)
+                .await?;
+        }
+        let definition =
+            A::Definition::deserialize(document).map_err(|source| DataAssetError::Deserialize {
+                path: String::new(),
+                source,
+            })?;
+        self.create_asset(definition, load_context).await
+    }
+
+    #[cfg(any(
+        feature = "json",
+        feature = "msgpack",
+        feature = "ron",
+        feature = "toml",
+        feature = "yaml",
+        feature = "cbor"
+    ))]
+    /// Create the asset from its deserialized definition and check it
+    pub(crate) async fn create_asset(
+        &self,
+        definition: A::Definition,
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<A, DataAssetError> {
+        let asset = A::from_definition(definition, load_context)
+            .await
+            .map_err(DataAssetError::Definition)?;
+        self.check.check(&asset, load_context)?;
+        Ok(asset)
+    }
+}
+
+/// Adds the builder methods for the format independent loading steps to the plugin of a
+/// self-describing format
+macro_rules! loader_options_methods {
+    () => {
+        /// Add the values at `path` inside of loaded documents as labeled sub-assets of type `T`.
+        ///
+        /// Paths use the syntax of [JSON Pointers](https://www.rfc-editor.org/rfc/rfc6901)
+        /// without the leading `/`. The label of a sub-asset is its path, so the value at
+        /// `weapons/sword` can be loaded from `data.json#weapons/sword`. A `*` segment matches
+        /// every key of a map or index of a sequence, e.g. `weapons/*` adds every weapon as
+        /// sub-asset. Loading a document fails if it has no value at a path without wildcards.
+        #[cfg(any(
+            feature = "json",
+            feature = "msgpack",
+            feature = "ron",
+            feature = "toml",
+            feature = "yaml",
+            feature = "cbor"
+        ))]
+        pub fn with_labeled_path<T: $crate::DataAsset>(mut self, path: &str) -> Self {
+            self.options
+                .labeled_paths
+                .push($crate::label::LabeledPath::new::<T>(path));
+            self
+        }
+
+        /// Migrate loaded documents to the current version of `A` before deserializing them.
+        ///
+        /// See [`Migrate`](crate::migration::Migrate) for how versions and migrations are
+        /// declared.
+        pub fn with_migrations(mut self) -> Self
+        where
+            A: $crate::migration::Migrate,
+        {
+            self.options.migrate = Some($crate::migration::migrate::<A>);
+            self
+        }
+
+        /// Check loaded assets with their [`ValidateAsset`](crate::ValidateAsset)
+        /// implementation and fail or warn for invalid assets depending on `mode`.
+        pub fn with_asset_validation(mut self, mode: $crate::ValidationMode) -> Self
+        where
+            A: $crate::ValidateAsset,
+        {
+            self.options.check = $crate::loader::AssetCheck::new(mode);
+            self
+        }
+
+        /// Send a [`DataAssetChanged`](crate::change::DataAssetChanged) message with the
+        /// changed fields whenever an asset of type `A` is modified, e.g. by hot reloading.
+        pub fn with_change_events(mut self) -> Self
+        where
+            A: $crate::ToDefinition,
+        {
+            self.options.changes = Some($crate::change::register::<A>);
+            self
+        }
+    };
+}
+pub(crate) use loader_options_methods;
+
+/// Methods of the plugins of text formats, whose documents can include and extend other
+/// documents
+#[cfg(any(feature = "json", feature = "ron", feature = "toml", feature = "yaml"))]
+macro_rules! text_options_methods {
+    () => {
+        /// Replace includes in loaded documents by the documents they name.
+        ///
+        /// Maps with only an `$include` field (`{"$include": "enemies.json"}`, or
+        /// `{ "$include" = "enemies.toml" }` in toml), values tagged with `!include` in yaml
+        /// (`!include enemies.yaml`) and `include("enemies.ron")` in ron are includes. Included
+        /// documents are read in the format of this plugin as dependencies of the loaded asset and
+        /// can include other documents. Their paths are relative to the including document and
+        /// can not leave its asset source.
+        pub fn with_includes(mut self) -> Self {
+            self.options.includes = true;
+            self
+        }
+
+        /// Merge loaded documents onto the documents named in their `$extends` field.
+        ///
+        /// Bases are read in the format of this plugin as dependencies of the loaded asset.
+        /// See [`Inheritance`](crate::inheritance::Inheritance) for how documents are merged.
+        pub fn with_inheritance(mut self, inheritance: $crate::inheritance::Inheritance) -> Self {
+            self.options.inheritance = Some(inheritance);
+            self
+        }
+
+        /// Merge the variants of loaded documents for the active profiles onto them.
+        ///
+        /// See [`Variants`](crate::variant::Variants) for where variants are found and which
+        /// profiles are active.
+        pub fn with_variants(mut self, variants: $crate::variant::Variants) -> Self {
+            self.options.variants = Some(variants);
+            self
+        }
+
+        /// Replace placeholders like `${vars.tile_size}` in loaded documents by the values of
+        /// variables.
+        ///
+        /// See [`Substitution`](crate::substitution::Substitution) for the syntax and where
+        /// variables come from.
+        pub fn with_substitution(
+            mut self,
+            substitution: $crate::substitution::Substitution,
+        ) -> Self {
+            self.options.substitution = Some(substitution);
+            self
+        }
+
+        /// Apply the layers of `overlays` from other asset sources to loaded documents.
+        ///
+        /// See [`Overlays`](crate::overlay::Overlays) for where layers are found and how they
+        /// are applied. The file each value comes from is added as labeled
+        /// [`Provenance`](crate::overlay::Provenance) sub-asset.
+        pub fn with_overlays(mut self, overlays: $crate::overlay::Overlays) -> Self {
+            self.options.overlays = Some(overlays);
+            self
+        }
+    };
+}
+#[cfg(any(feature = "json", feature = "ron", feature = "toml", feature = "yaml"))]
+pub(crate) use text_options_methods;
@@ -0,0 +1,265 @@
+use crate::value::Value;
+use std::collections::BTreeMap;
+use thiserror::Error;
+
+/// Asset types whose documents are migrated from older versions before they are deserialized
+///
+/// Documents store their version in a top-level field (`version` by default). Loading a
+/// document runs the migrations from its version up to [`Migrate::VERSION`] on the
+/// [`Value`] tree of the document and sets the version field to the current version.
+/// Enable migrations with `with_migrations` on the plugins of self-describing formats.
+///
+/// ```
+/// # use bevy_common_assets::migration::{Migrate, Migrations};
+/// # use bevy_common_assets::value::Value;
+/// struct Level;
+///
+/// impl Migrate for Level {
+///     const VERSION: u32 = 3;
+///
+///     fn migrations() -> Migrations {
+///         Migrations::new()
+///             // version 2 renamed `trees` to `positions`
+///             .add(1, |document| {
+///                 let map = document.as_map_mut().ok_or("expected a map")?;
+///                 let trees = map.shift_remove("trees").unwrap_or_default();
+///                 map.insert("positions".to_owned(), trees);
+///                 Ok(())
+///             })
+///             // version 3 added the `name` field
+///             .add(2, |document| {
+///                 let map = document.as_map_mut().ok_or("expected a map")?;
+///                 map.insert("name".to_owned(), Value::from("unnamed"));
+///                 Ok(())
+///             })
+///     }
+/// }
+/// ```
+pub trait Migrate {
+    /// The current version of the asset type
+    const VERSION: u32;
+
+    /// The top-level field holding the version of a document
+    const VERSION_FIELD: &'static str = "version";
+
+    /// The version of documents without a version field
+    ///
+    /// With `None`, documents without a version field fail to load.
+    const UNVERSIONED: Option<u32> = None;
+
+    /// The migrations from all supported older versions to their next version
+    fn migrations() -> Migrations;
+}
+
+/// A migration of a document from one version to the next
+pub type Migration = fn(&mut Value) -> Result<(), String>;
+
+/// The chain of migrations of a [`Migrate`] type
+#[derive(Default)]
+pub struct Migrations {
+    steps: BTreeMap<u32, Migration>,
+}
+
+impl Migrations {
+    /// Create an empty chain of migrations
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the migration from version `from` to version `from + 1`
+    pub fn add(mut self, from: u32, migration: Migration) -> Self {
+        self.steps.insert(from, migration);
+        self
+    }
+}
+
+/// Possible errors when migrating a document
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum MigrationError {
+    /// The document has no version field and the asset type has no
+    /// [version for unversioned documents](Migrate::UNVERSIONED)
+    #[error("The document has no `{0}` field")]
+    MissingVersion(&'static str),
+    /// The version field does not hold a version number
+    #[error("The `{0}` field of the document is no version number")]
+    InvalidVersion(&'static str),
+    /// The document was written for a newer version of the asset type
+    #[error("The document has version {version}, but the newest supported version is {current}")]
+    UnsupportedVersion {
+        /// The version of the document
+        version: u32,
+        /// The current version of the asset type
+        current: u32,
+    },
+    /// There is no migration from a version in the chain
+    #[error("There is no migration from version {0} to version {next}", next = .0 + 1)]
+    MissingMigration(u32),
+    /// A migration failed
+    #[error("Migration from version {from} failed: {message}")]
+    Failed {
+        /// The version the failed migration migrates from
+        from: u32,
+        /// The error returned by the migration
+        message: String,
+    },
+}
+
+/// Migrate `document` to the current version of `A`
+///
+/// Returns whether the document was changed.
+pub fn migrate<A: Migrate>(document: &mut Value) -> Result<bool, MigrationError> {
+    let version = match document.get(A::VERSION_FIELD) {
+        Some(version) => version
+            .as_u64()
+            .and_then(|version| u32::try_from(version).ok())
+            .ok_or(MigrationError::InvalidVersion(A::VERSION_FIELD))?,
+        None => A::UNVERSIONED.ok_or(MigrationError::MissingVersion(A::VERSION_FIELD))?,
+    };
+    if version > A::VERSION {
+        return Err(MigrationError::UnsupportedVersion {
+            version,
+            current: A::VERSION,
+        });
+    }
+    if version == A::VERSION && document.get(A::VERSION_FIELD).is_some() {
+        return Ok(false);
+    }
+    let migrations = A::migrations();
+    for from in version..A::VERSION {
+        let migration = migrations
+            .steps
+            .get(&from)
+            .ok_or(MigrationError::MissingMigration(from))?;
+        migration(document).map_err(|message| MigrationError::Failed { from, message })?;
+    }
+    if let Some(map) = document.as_map_mut() {
+        map.insert(A::VERSION_FIELD.to_owned(), Value::from(A::VERSION));
+    }
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Level;
+
+    impl Migrate for Level {
+        const VERSION: u32 = 3;
+
+        fn migrations() -> Migrations {
+            Migrations::new()
+                .add(1, |document| {
+                    let map = document.as_map_mut().ok_or("expected a map")?;
+                    let trees = map.shift_remove("trees").unwrap_or_default();
+                    map.insert("positions".to_owned(), trees);
+                    Ok(())
+                })
+                .add(2, |document| {
+                    let map = document.as_map_mut().ok_or("expected a map")?;
+                    map.insert("name".to_owned(), Value::from("unnamed"));
+                    Ok(())
+                })
+        }
+    }
+
+    /// Treats documents without its `schema` field as version 0
+    struct Unit;
+
+    impl Migrate for Unit {
+        const VERSION: u32 = 1;
+        const VERSION_FIELD: &'static str = "schema";
+        const UNVERSIONED: Option<u32> = Some(0);
+
+        fn migrations() -> Migrations {
+            Migrations::new().add(0, |document| {
+                let map = document.as_map_mut().ok_or("expected a map")?;
+                let hp = map.shift_remove("health").ok_or("no health")?;
+                map.insert("hp".to_owned(), hp);
+                Ok(())
+            })
+        }
+    }
+
+    fn map<const N: usize>(entries: [(&str, Value); N]) -> Value {
+        Value::Map(
+            entries
+                .into_iter()
+                .map(|(key, value)| (key.to_owned(), value))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn migrations_run_from_the_version_of_the_document() {
+        let mut document = map([("version", 1.into()), ("trees", Value::Seq(vec![]))]);
+        assert!(migrate::<Level>(&mut document).unwrap());
+        assert_eq!(
+            document,
+            map([
+                ("version", 3.into()),
+                ("positions", Value::Seq(vec![])),
+                ("name", "unnamed".into()),
+            ])
+        );
+        let mut document = map([("version", 2.into()), ("name", "forest".into())]);
+        assert!(migrate::<Level>(&mut document).unwrap());
+        assert_eq!(document.get("name"), Some(&"unnamed".into()));
+    }
+
+    #[test]
+    fn current_documents_are_unchanged() {
+        let mut document = map([("version", 3.into())]);
+        assert!(!migrate::<Level>(&mut document).unwrap());
+        assert_eq!(document, map([("version", 3.into())]));
+    }
+
+    #[test]
+    fn named_documents_are_migrated() {
+        // `Level(version: 2)` in RON
+        let mut document = Value::Named("Level".to_owned(), Box::new(map([("version", 2.into())])));
+        assert!(migrate::<Level>(&mut document).unwrap());
+        assert_eq!(document.get("version"), Some(&3.into()));
+    }
+
+    #[test]
+    fn invalid_versions_fail() {
+        assert!(matches!(
+            migrate::<Level>(&mut map([])),
+            Err(MigrationError::MissingVersion("version"))
+        ));
+        assert!(matches!(
+            migrate::<Level>(&mut map([("version", "1".into())])),
+            Err(MigrationError::InvalidVersion("version"))
+        ));
+        assert!(matches!(
+            migrate::<Level>(&mut map([("version", (-1).into())])),
+            Err(MigrationError::InvalidVersion("version"))
+        ));
+        assert!(matches!(
+            migrate::<Level>(&mut map([("version", 4.into())])),
+            Err(MigrationError::UnsupportedVersion {
+                version: 4,
+                current: 3
+            })
+        ));
+        assert!(matches!(
+            migrate::<Level>(&mut map([("version", 0.into())])),
+            Err(MigrationError::MissingMigration(0))
+        ));
+        let error = migrate::<Unit>(&mut map([("hp", 5.into())])).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Migration from version 0 failed: no health"
+        );
+    }
+
+    #[test]
+    fn unversioned_documents_get_a_version_field() {
+        let mut document = map([("health", 5.into())]);
+        assert!(migrate::<Unit>(&mut document).unwrap());
+        assert_eq!(document, map([("hp", 5.into()), ("schema", 1.into())]));
+        assert!(!migrate::<Unit>(&mut document).unwrap());
+    }
+}
@@ -1,49 +1,80 @@
+use crate::loader::{AssetCheck, LoaderOptions, loader_options_methods};
+use crate::stream::{Decoded, LoadedStream, read_records};
+use crate::value::Value;
+use crate::{DataAsset, DataAssetError};
 use bevy_app::{App, Plugin};
 use bevy_asset::io::Reader;
-use bevy_asset::{Asset, AssetApp, AssetLoader, LoadContext};
-use rmp_serde::from_slice;
+use bevy_asset::saver::AssetSaver;
+use bevy_asset::{AssetApp, AssetLoader, AsyncWriteExt, LoadContext};
+use bevy_reflect::TypePath;
+use rmp_serde::{Deserializer, from_slice, to_vec_named};
+use serde::Serialize;
+use std::io::ErrorKind;
 use std::marker::PhantomData;
 use thiserror::Error;
 
 /// Plugin to load your asset type `A` from `MessagePack` files.
 pub struct MsgPackAssetPlugin<A> {
     extensions: Vec<&'static str>,
-    _marker: PhantomData<A>,
+    options: LoaderOptions<A>,
 }
 
 impl<A> Plugin for MsgPackAssetPlugin<A>
 where
-    for<'de> A: serde::Deserialize<'de> + Asset,
+    A: DataAsset,
 {
     fn build(&self, app: &mut App) {
-        app.init_asset::<A>()
-            .register_asset_loader(MsgPackAssetLoader::<A> {
-                extensions: self.extensions.clone(),
-                _marker: PhantomData,
-            });
+        crate::data_asset::init_data_asset::<A>(app);
+        self.register_loader(app);
     }
 }
 
 impl<A> MsgPackAssetPlugin<A>
 where
-    for<'de> A: serde::Deserialize<'de> + Asset,
+    A: DataAsset,
 {
     /// Create a new plugin that will load assets from files with the given extensions.
     pub fn new(extensions: &[&'static str]) -> Self {
         Self {
             extensions: extensions.to_owned(),
-            _marker: PhantomData,
+            options: LoaderOptions::default(),
         }
     }
+
+    pub(crate) fn with_check(mut self, check: AssetCheck<A>) -> Self {
+        self.options.check = check;
+        self
+    }
+
+    /// Register the loader of this plugin without initializing the asset
+    pub(crate) fn register_loader(&self, app: &mut App) {
+        let options = self.options.build(app);
+        app.register_asset_loader(MsgPackAssetLoader::<A> {
+            extensions: self.extensions.clone(),
+            options,
+        });
+    }
+
+    loader_options_methods!();
 }
 
 /// Loads your asset type `A` from `MessagePack` files
 pub struct MsgPackAssetLoader<A> {
     extensions: Vec<&'static str>,
-    _marker: PhantomData<A>,
+    options: LoaderOptions<A>,
 }
 
-/// Possible errors that can be produced by [`MsgPackAssetLoader`]
+impl<A> MsgPackAssetLoader<A> {
+    /// Create a loader without extensions for other loaders delegating to it
+    pub(crate) fn from_options(options: LoaderOptions<A>) -> Self {
+        Self {
+            extensions: vec![],
+            options,
+        }
+    }
+}
+
+/// Possible errors that can be produced by the `MessagePack` asset loaders and savers
 #[non_exhaustive]
 #[derive(Debug, Error)]
 pub enum MsgPackLoaderError {
@@ -53,11 +84,17 @@ pub enum MsgPackLoaderError {
     /// A [`MessagePack` Error](rmp_serde::decode::Error)
     #[error("Could not parse MessagePack: {0}")]
     MsgPackError(#[from] rmp_serde::decode::Error),
+    /// A [`MessagePack` encoding Error](rmp_serde::encode::Error)
+    #[error("Could not encode MessagePack: {0}")]
+    MsgPackEncodeError(#[from] rmp_serde::encode::Error),
+    /// An error of the format independent loading steps
+    #[error(transparent)]
+    DataAssetError(#[from] DataAssetError),
 }
 
 impl<A> AssetLoader for MsgPackAssetLoader<A>
 where
-    for<'de> A: serde::Deserialize<'de> + Asset,
+    A: DataAsset,
 {
     type Asset = A;
     type Settings = ();
@@ -67,15 +104,143 @@ where
         &self,
         reader: &mut dyn Reader,
         _settings: &(),
-        _load_context: &mut LoadContext<'_>,
+        load_context: &mut LoadContext<'_>,
     ) -> Result<Self::Asset, Self::Error> {
         let mut bytes = Vec::new();
         reader.read_to_end(&mut bytes).await?;
-        let asset = from_slice::<A>(&bytes)?;
-        Ok(asset)
+        if self.options.uses_value() {
+            let document = from_slice::<Value>(&bytes)?;
+            return Ok(self.options.load_value(document, load_context).await?);
+        }
+        let definition = from_slice::<A::Definition>(&bytes)?;
+        Ok(self.options.create_asset(definition, load_context).await?)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &self.extensions
+    }
+}
+
+/// Plugin to load streams of your asset type `A` from concatenated `MessagePack` values.
+///
+/// The file holds one `MessagePack` value per record. The records are collected in a
+/// [`LoadedStream`] asset.
+pub struct MsgPackStreamAssetPlugin<A> {
+    extensions: Vec<&'static str>,
+    _marker: PhantomData<A>,
+}
+
+impl<A> Plugin for MsgPackStreamAssetPlugin<A>
+where
+    for<'de> A: serde::Deserialize<'de> + TypePath + Send + Sync + 'static,
+{
+    fn build(&self, app: &mut App) {
+        app.init_asset::<LoadedStream<A>>()
+            .register_asset_loader(MsgPackStreamAssetLoader::<A> {
+                extensions: self.extensions.clone(),
+                _marker: PhantomData,
+            });
+    }
+}
+
+impl<A> MsgPackStreamAssetPlugin<A>
+where
+    for<'de> A: serde::Deserialize<'de> + TypePath + Send + Sync + 'static,
+{
+    /// Create a new plugin that will load assets from files with the given extensions.
+    pub fn new(extensions: &[&'static str]) -> Self {
+        Self {
+            extensions: extensions.to_owned(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+/// Loads streams of your asset type `A` from concatenated `MessagePack` values
+pub struct MsgPackStreamAssetLoader<A> {
+    extensions: Vec<&'static str>,
+    _marker: PhantomData<A>,
+}
+
+impl<A> AssetLoader for MsgPackStreamAssetLoader<A>
+where
+    for<'de> A: serde::Deserialize<'de> + TypePath + Send + Sync + 'static,
+{
+    type Asset = LoadedStream<A>;
+    type Settings = ();
+    type Error = MsgPackLoaderError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let records = read_records(reader, |bytes, _, _| -> Result<_, MsgPackLoaderError> {
+            let mut remaining = &bytes[..];
+            match A::deserialize(&mut Deserializer::new(&mut remaining)) {
+                Ok(record) => Ok(Decoded::Record(record, bytes.len() - remaining.len())),
+                Err(
+                    rmp_serde::decode::Error::InvalidMarkerRead(error)
+                    | rmp_serde::decode::Error::InvalidDataRead(error),
+                ) if error.kind() == ErrorKind::UnexpectedEof => Ok(Decoded::Incomplete),
+                Err(error) => Err(error.into()),
+            }
+        })
+        .await?;
+        Ok(LoadedStream { records })
     }
 
     fn extensions(&self) -> &[&str] {
         &self.extensions
     }
 }
+
+/// Saves streams of your asset type `A` as concatenated `MessagePack` values
+pub struct MsgPackStreamAssetSaver<A> {
+    _marker: PhantomData<A>,
+}
+
+impl<A> Default for MsgPackStreamAssetSaver<A> {
+    fn default() -> Self {
+        Self {
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<A> MsgPackStreamAssetSaver<A>
+where
+    A: Serialize,
+{
+    /// Write `record` at the end of `writer`, see [`LoadedStream`]
+    pub async fn append_record(
+        writer: &mut bevy_asset::io::Writer,
+        record: &A,
+    ) -> Result<(), MsgPackLoaderError> {
+        writer.write_all(&to_vec_named(record)?).await?;
+        Ok(())
+    }
+}
+
+impl<A> AssetSaver for MsgPackStreamAssetSaver<A>
+where
+    for<'de> A: serde::Deserialize<'de> + Serialize + TypePath + Send + Sync + 'static,
+{
+    type Asset = LoadedStream<A>;
+    type Settings = ();
+    type OutputLoader = MsgPackStreamAssetLoader<A>;
+    type Error = MsgPackLoaderError;
+
+    async fn save(
+        &self,
+        writer: &mut bevy_asset::io::Writer,
+        asset: bevy_asset::saver::SavedAsset<'_, Self::Asset>,
+        _settings: &Self::Settings,
+    ) -> Result<<Self::OutputLoader as AssetLoader>::Settings, Self::Error> {
+        for record in asset.get().records.iter() {
+            Self::append_record(writer, record).await?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,627 @@
+use crate::data::{DataFormat, read_document};
+use crate::dependency::MissingFiles;
+use crate::inheritance::{Inheritance, merge};
+use crate::value::{Map, Value, escape, unescape};
+use bevy_app::App;
+use bevy_asset::io::{AssetReaderError, AssetSourceId};
+use bevy_asset::{
+    Asset, AssetApp, AssetPath, Assets, LoadContext, LoadDirectError, ReadAssetBytesError,
+};
+use bevy_reflect::TypePath;
+use std::collections::BTreeMap;
+use std::mem::take;
+use std::path::PathBuf;
+use thiserror::Error;
+
+/// The label of the [`Provenance`] sub-asset of documents loaded with overlays
+pub const PROVENANCE_LABEL: &str = "provenance";
+
+/// Layers of data from other asset sources applied to documents of the json, ron, toml and
+/// yaml plugins
+///
+/// With `with_overlays` on their plugins, loading a document from `levels/forest.json` also
+/// looks for layers at the same path in the overlay sources, like base content patched by a
+/// DLC and mods. Sources are applied in the order they were added, so later sources override
+/// earlier ones. Every source can have three layers, which are applied in this order if they
+/// exist:
+/// - `levels/forest.json`: a partial document, deep-merged onto the document
+/// - `levels/forest.json.merge`: a [JSON Merge Patch](https://www.rfc-editor.org/rfc/rfc7386),
+///   where `null` removes a value
+/// - `levels/forest.json.patch`: a [JSON Patch](https://www.rfc-editor.org/rfc/rfc6902), a
+///   sequence of operations like `{"op": "replace", "path": "/hp", "value": 200}`
+///
+/// All layers are written in the format of the document. Layers are loader dependencies of the
+/// asset whether they exist or not, so adding or changing them reloads the asset when watching
+/// for changes. With asset processing, only existing layers are dependencies and layers added
+/// later are picked up the next time the asset is processed. Sources that are not registered
+/// are skipped like missing layers, and so is the file of the document itself when it is
+/// loaded from an overlay source.
+///
+/// ```no_run
+/// # use bevy::prelude::*;
+/// # use bevy::asset::io::AssetSourceBuilder;
+/// # use bevy_common_assets::json::JsonAssetPlugin;
+/// # use bevy_common_assets::overlay::Overlays;
+/// # #[derive(serde::Deserialize, Asset, TypePath)]
+/// # struct Level;
+/// App::new()
+///     .register_asset_source("dlc", AssetSourceBuilder::platform_default("dlc", None))
+///     .register_asset_source("mods", AssetSourceBuilder::platform_default("mods", None))
+///     .add_plugins((
+///         DefaultPlugins,
+///         JsonAssetPlugin::<Level>::new(&["level.json"])
+///             .with_overlays(Overlays::default().with_source("dlc").with_source("mods")),
+///     ));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Overlays {
+    sources: Vec<AssetSourceId<'static>>,
+    missing: MissingFiles,
+}
+
+impl Overlays {
+    /// Apply the layers in `source` after the layers of all previously added sources
+    pub fn with_source(mut self, source: impl Into<AssetSourceId<'static>>) -> Self {
+        self.sources.push(source.into());
+        self
+    }
+
+    /// Initialize the [`Provenance`] asset if no other plugin did and watch missing layers if
+    /// the asset server of `app` watches for changes
+    pub(crate) fn connected(&self, app: &mut App) -> Self {
+        if !app.world().contains_resource::<Assets<Provenance>>() {
+            app.init_asset::<Provenance>();
+        }
+        Overlays {
+            sources: self.sources.clone(),
+            missing: MissingFiles::new(app),
+        }
+    }
+}
+
+/// The files the values of a document loaded with overlays come from
+///
+/// It is added as sub-asset with the label [`PROVENANCE_LABEL`], e.g. `forest.level.json#provenance`.
+#[derive(Asset, TypePath, Clone, Debug, Default)]
+pub struct Provenance {
+    /// The asset path of the file that last set a value by the
+    /// [JSON Pointer](https://www.rfc-editor.org/rfc/rfc6901) to every scalar, empty map and
+    /// empty sequence in the document
+    pub fields: BTreeMap<String, String>,
+}
+
+impl Provenance {
+    /// The asset path of the file that last set the value at `pointer` or its closest parent
+    pub fn origin(&self, pointer: &str) -> Option<&str> {
+        let mut pointer = pointer;
+        loop {
+            if let Some(origin) = self.fields.get(pointer) {
+                return Some(origin);
+            }
+            pointer = &pointer[..pointer.rfind('/')?];
+        }
+    }
+
+    /// Attribute all values of `after` that differ from `before` to `origin`
+    fn attribute(&mut self, before: Option<&Value>, after: &Value, pointer: &str, origin: &str) {
+        if before == Some(after) {
+            return;
+        }
+        match (before.map(Value::unnamed), after.unnamed()) {
+            (Some(Value::Map(before)), Value::Map(after)) => {
+                for key in before.keys().filter(|key| !after.contains_key(*key)) {
+                    self.remove(&format!("{pointer}/{}", escape(key)));
+                }
+                for (key, value) in after {
+                    let child = format!("{pointer}/{}", escape(key));
+                    self.attribute(before.get(key), value, &child, origin);
+                }
+            }
+            (Some(Value::Seq(before)), Value::Seq(after)) => {
+                for index in after.len()..before.len() {
+                    self.remove(&format!("{pointer}/{index}"));
+                }
+                for (index, value) in after.iter().enumerate() {
+                    let child = format!("{pointer}/{index}");
+                    self.attribute(before.get(index), value, &child, origin);
+                }
+            }
+            (_, after) => {
+                self.remove(pointer);
+                self.insert(after, pointer, origin);
+            }
+        }
+    }
+
+    /// Attribute `value` and all values in it to `origin`
+    fn insert(&mut self, value: &Value, pointer: &str, origin: &str) {
+        match value.unnamed() {
+            Value::Map(map) if !map.is_empty() => {
+                for (key, value) in map {
+                    self.insert(value, &format!("{pointer}/{}", escape(key)), origin);
+                }
+            }
+            Value::Seq(seq) if !seq.is_empty() => {
+                for (index, value) in seq.iter().enumerate() {
+                    self.insert(value, &format!("{pointer}/{index}"), origin);
+                }
+            }
+            _ => {
+                self.fields.insert(pointer.to_owned(), origin.to_owned());
+            }
+        }
+    }
+
+    /// Remove the value at `pointer` and all values in it
+    fn remove(&mut self, pointer: &str) {
+        let prefix = format!("{pointer}/");
+        self.fields
+            .retain(|field, _| field != pointer && !field.starts_with(&prefix));
+    }
+}
+
+/// Possible errors when applying overlays to a document
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum OverlayError {
+    /// A layer exists, but could not be read
+    #[error("Could not read the layer `{layer}`: {source}")]
+    Read {
+        /// The path of the layer
+        layer: String,
+        /// The [error](ReadAssetBytesError) reading the layer
+        source: Box<ReadAssetBytesError>,
+    },
+    /// A layer could not be parsed in the format of the document
+    #[error("Could not parse the layer `{layer}`: {message}")]
+    Parse {
+        /// The path of the layer
+        layer: String,
+        /// Description of the problem
+        message: String,
+    },
+    /// A missing layer could not be watched for changes
+    #[error("Could not watch the missing layer `{layer}`: {source}")]
+    Watch {
+        /// The path of the layer
+        layer: String,
+        /// The [error](LoadDirectError) loading the placeholder of the layer
+        source: Box<LoadDirectError>,
+    },
+    /// An operation of a JSON Patch failed
+    #[error("Operation {operation} of the patch `{layer}` failed: {message}")]
+    Patch {
+        /// The path of the patch
+        layer: String,
+        /// The index of the failed operation
+        operation: usize,
+        /// Description of the problem
+        message: String,
+    },
+}
+
+/// The kinds of layers in the order they are applied
+const LAYERS: [(&str, LayerKind); 3] = [
+    ("", LayerKind::Partial),
+    (".merge", LayerKind::MergePatch),
+    (".patch", LayerKind::JsonPatch),
+];
+
+#[derive(Clone, Copy)]
+enum LayerKind {
+    Partial,
+    MergePatch,
+    JsonPatch,
+}
+
+/// Apply all layers of `overlays` to `document` and add its [`Provenance`] as sub-asset
+///
+/// Layers are read in `format` as dependencies of the loaded asset.
+pub(crate) async fn apply(
+    document: &mut Value,
+    format: DataFormat,
+    overlays: &Overlays,
+    load_context: &mut LoadContext<'_>,
+) -> Result<(), OverlayError> {
+    let path = load_context.asset_path().clone_owned();
+    let mut provenance = Provenance::default();
+    provenance.attribute(None, document, "", &path.to_string());
+    for source in &overlays.sources {
+        for (suffix, kind) in LAYERS {
+            let mut file = path.path().as_os_str().to_owned();
+            file.push(suffix);
+            let file = PathBuf::from(file);
+            let layer_path = AssetPath::from_path(&file).with_source(source.clone());
+            if layer_path == path {
+                continue;
+            }
+            let layer_name = layer_path.to_string();
+            let bytes = match load_context.read_asset_bytes(&layer_path).await {
+                Ok(bytes) => bytes,
+                Err(ReadAssetBytesError::AssetReaderError(AssetReaderError::NotFound(_))) => {
+                    overlays
+                        .missing
+                        .watch(load_context, &layer_path)
+                        .await
+                        .map_err(|source| OverlayError::Watch {
+                            layer: layer_name,
+                            source,
+                        })?;
+                    continue;
+                }
+                Err(ReadAssetBytesError::MissingAssetSourceError(_)) => continue,
+                Err(source) => {
+                    return Err(OverlayError::Read {
+                        layer: layer_name,
+                        source: Box::new(source),
+                    });
+                }
+            };
+            let layer = read_document(format, &bytes).map_err(|error| OverlayError::Parse {
+                layer: layer_name.clone(),
+                message: error.to_string(),
+            })?;
+            let before = document.clone();
+            match kind {
+                LayerKind::Partial => {
+                    *document = merge(take(document), layer, &Inheritance::default(), &mut vec![]);
+                }
+                LayerKind::MergePatch => merge_patch(document, layer),
+                LayerKind::JsonPatch => json_patch(document, &layer, &layer_name)?,
+            }
+            provenance.attribute(Some(&before), document, "", &layer_name);
+        }
+    }
+    load_context.add_labeled_asset(PROVENANCE_LABEL.to_owned(), provenance);
+    Ok(())
+}
+
+/// Apply a [JSON Merge Patch](https://www.rfc-editor.org/rfc/rfc7386) to `target`
+fn merge_patch(target: &mut Value, patch: Value) {
+    let Value::Map(patch) = patch.unnamed() else {
+        *target = patch;
+        return;
+    };
+    if !matches!(target.unnamed(), Value::Map(_)) {
+        *target = Value::Map(Map::new());
+    }
+    let Value::Map(target) = target.unnamed_mut() else {
+        return;
+    };
+    for (key, value) in patch {
+        if value.is_null() {
+            target.shift_remove(key);
+        } else {
+            merge_patch(target.entry(key.clone()).or_default(), value.clone());
+        }
+    }
+}
+
+/// Apply a [JSON Patch](https://www.rfc-editor.org/rfc/rfc6902) to `document`
+fn json_patch(document: &mut Value, patch: &Value, layer: &str) -> Result<(), OverlayError> {
+    let error = |operation: usize, message: String| OverlayError::Patch {
+        layer: layer.to_owned(),
+        operation,
+        message,
+    };
+    let operations = patch
+        .as_seq()
+        .ok_or_else(|| error(0, "a patch must be a sequence of operations".to_owned()))?;
+    for (index, operation) in operations.iter().enumerate() {
+        apply_operation(document, operation).map_err(|message| error(index, message))?;
+    }
+    Ok(())
+}
+
+fn apply_operation(document: &mut Value, operation: &Value) -> Result<(), String> {
+    let field = |name: &str| {
+        operation
+            .get(name)
+            .ok_or_else(|| format!("missing `{name}`"))
+    };
+    let string_field = |name: &str| {
+        field(name)?
+            .as_str()
+            .ok_or_else(|| format!("`{name}` must be a string"))
+    };
+    let path = string_field("path")?;
+    match string_field("op")? {
+        "add" => add(document, path, field("value")?.clone()),
+        "remove" => remove(document, path).map(drop),
+        "replace" => {
+            let target = document
+                .pointer_mut(path)
+                .ok_or_else(|| format!("there is no value at `{path}`"))?;
+            *target = field("value")?.clone();
+            Ok(())
+        }
+        "move" => {
+            let from = string_field("from")?;
+            if path.starts_with(&format!("{from}/")) {
+                return Err(format!("can not move `{from}` into itself"));
+            }
+            let value = remove(document, from)?;
+            add(document, path, value)
+        }
+        "copy" => {
+            let from = string_field("from")?;
+            let value = document
+                .pointer(from)
+                .cloned()
+                .ok_or_else(|| format!("there is no value at `{from}`"))?;
+            add(document, path, value)
+        }
+        "test" => {
+            if document.pointer(path) == Some(field("value")?) {
+                Ok(())
+            } else {
+                Err(format!("the value at `{path}` does not match"))
+            }
+        }
+        op => Err(format!("unknown operation `{op}`")),
+    }
+}
+
+/// Add `value` at `pointer`, replacing existing map values and shifting sequence items
+fn add(document: &mut Value, pointer: &str, value: Value) -> Result<(), String> {
+    let Some((parent, key)) = split_pointer(pointer)? else {
+        *document = value;
+        return Ok(());
+    };
+    match document.pointer_mut(parent).map(Value::unnamed_mut) {
+        Some(Value::Map(map)) => {
+            map.insert(key, value);
+            Ok(())
+        }
+        Some(Value::Seq(seq)) => {
+            let index = match key.as_str() {
+                "-" => seq.len(),
+                index => index
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|index| *index <= seq.len())
+                    .ok_or_else(|| format!("invalid index `{index}` in `{pointer}`"))?,
+            };
+            seq.insert(index, value);
+            Ok(())
+        }
+        _ => Err(format!("there is no map or sequence at `{parent}`")),
+    }
+}
+
+/// Remove the value at `pointer` and return it
+fn remove(document: &mut Value, pointer: &str) -> Result<Value, String> {
+    let missing = || format!("there is no value at `{pointer}`");
+    let (parent, key) = split_pointer(pointer)?.ok_or("can not remove the whole document")?;
+    match document.pointer_mut(parent).map(Value::unnamed_mut) {
+        Some(Value::Map(map)) => map.shift_remove(&key).ok_or_else(missing),
+        Some(Value::Seq(seq)) => match key.parse::<usize>() {
+            Ok(index) if index < seq.len() => Ok(seq.remove(index)),
+            _ => Err(missing()),
+        },
+        _ => Err(missing()),
+    }
+}
+
+/// Split a pointer into the pointer to the parent and the unescaped last key
+///
+/// Returns `None` for the empty pointer to the whole document.
+fn split_pointer(pointer: &str) -> Result<Option<(&str, String)>, String> {
+    if pointer.is_empty() {
+        return Ok(None);
+    }
+    pointer
+        .rsplit_once('/')
+        .map(|(parent, key)| Some((parent, unescape(key))))
+        .ok_or_else(|| format!("invalid pointer `{pointer}`"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn map<const N: usize>(entries: [(&str, Value); N]) -> Value {
+        Value::Map(
+            entries
+                .into_iter()
+                .map(|(key, value)| (key.to_owned(), value))
+                .collect(),
+        )
+    }
+
+    fn seq<const N: usize>(items: [i32; N]) -> Value {
+        Value::Seq(items.into_iter().map(Value::from).collect())
+    }
+
+    fn operation<const N: usize>(op: &str, fields: [(&str, Value); N]) -> Value {
+        let mut operation = map(fields);
+        if let Value::Map(map) = &mut operation {
+            map.insert("op".to_owned(), op.into());
+        }
+        operation
+    }
+
+    fn patched<const N: usize>(document: Value, operations: [Value; N]) -> Result<Value, String> {
+        let mut document = document;
+        json_patch(
+            &mut document,
+            &Value::Seq(operations.into()),
+            "level.json.patch",
+        )
+        .map(|()| document)
+        .map_err(|error| error.to_string())
+    }
+
+    #[test]
+    fn merge_patches_replace_and_remove_values() {
+        let mut document = map([
+            ("name", "forest".into()),
+            ("hp", 100.into()),
+            ("stats", map([("speed", 1.into()), ("armor", 2.into())])),
+            ("items", seq([1, 2])),
+        ]);
+        merge_patch(
+            &mut document,
+            map([
+                ("name", Value::Null),
+                ("hp", 200.into()),
+                ("stats", map([("armor", Value::Null), ("luck", 3.into())])),
+                ("items", seq([3])),
+                ("boss", map([("hp", 500.into())])),
+            ]),
+        );
+        assert_eq!(
+            document,
+            map([
+                ("hp", 200.into()),
+                ("stats", map([("speed", 1.into()), ("luck", 3.into())])),
+                ("items", seq([3])),
+                ("boss", map([("hp", 500.into())])),
+            ])
+        );
+    }
+
+    #[test]
+    fn merge_patches_that_are_no_maps_replace_the_document() {
+        let mut document = map([("hp", 100.into())]);
+        merge_patch(&mut document, seq([1]));
+        assert_eq!(document, seq([1]));
+        merge_patch(
+            &mut document,
+            map([("hp", 1.into()), ("name", Value::Null)]),
+        );
+        assert_eq!(document, map([("hp", 1.into())]));
+    }
+
+    #[test]
+    fn json_patches_apply_their_operations_in_order() {
+        let document = map([
+            ("hp", 100.into()),
+            ("name", "forest".into()),
+            ("items", seq([1, 2])),
+            ("a~b/c", 1.into()),
+        ]);
+        let patched = patched(
+            document,
+            [
+                operation("test", [("path", "/hp".into()), ("value", 100.into())]),
+                operation("replace", [("path", "/hp".into()), ("value", 200.into())]),
+                operation("add", [("path", "/items/0".into()), ("value", 0.into())]),
+                operation("add", [("path", "/items/-".into()), ("value", 3.into())]),
+                operation("add", [("path", "/boss".into()), ("value", map([]))]),
+                operation(
+                    "move",
+                    [("from", "/name".into()), ("path", "/boss/name".into())],
+                ),
+                operation(
+                    "copy",
+                    [("from", "/hp".into()), ("path", "/boss/hp".into())],
+                ),
+                operation("remove", [("path", "/a~0b~1c".into())]),
+            ],
+        );
+        assert_eq!(
+            patched,
+            Ok(map([
+                ("hp", 200.into()),
+                ("items", seq([0, 1, 2, 3])),
+                ("boss", map([("name", "forest".into()), ("hp", 200.into())])),
+            ]))
+        );
+    }
+
+    #[test]
+    fn failed_json_patch_operations_are_reported_with_their_index() {
+        let document = || map([("hp", 100.into()), ("items", seq([1]))]);
+        let error = |operations: Value| patched(document(), [operations]).unwrap_err();
+        assert_eq!(
+            error(operation(
+                "test",
+                [("path", "/hp".into()), ("value", 1.into())]
+            )),
+            "Operation 0 of the patch `level.json.patch` failed: the value at `/hp` does not match"
+        );
+        assert_eq!(
+            error(operation("remove", [("path", "/items/1".into())])),
+            "Operation 0 of the patch `level.json.patch` failed: there is no value at `/items/1`"
+        );
+        assert_eq!(
+            error(operation(
+                "add",
+                [("path", "/items/5".into()), ("value", 1.into())]
+            )),
+            "Operation 0 of the patch `level.json.patch` failed: invalid index `5` in `/items/5`"
+        );
+        assert_eq!(
+            error(operation(
+                "move",
+                [("from", "/items".into()), ("path", "/items/0".into())]
+            )),
+            "Operation 0 of the patch `level.json.patch` failed: can not move `/items` into itself"
+        );
+        assert_eq!(
+            error(operation("rename", [("path", "/hp".into())])),
+            "Operation 0 of the patch `level.json.patch` failed: unknown operation `rename`"
+        );
+        assert_eq!(
+            error(map([("path", "/hp".into())])),
+            "Operation 0 of the patch `level.json.patch` failed: missing `op`"
+        );
+        let mut document = document();
+        assert_eq!(
+            json_patch(&mut document, &map([]), "level.json.patch")
+                .unwrap_err()
+                .to_string(),
+            "Operation 0 of the patch `level.json.patch` failed: a patch must be a sequence of operations"
+        );
+        assert_eq!(
+            patched(
+                document,
+                [
+                    operation("remove", [("path", "/hp".into())]),
+                    operation("remove", [("path", "/hp".into())]),
+                ]
+            )
+            .unwrap_err(),
+            "Operation 1 of the patch `level.json.patch` failed: there is no value at `/hp`"
+        );
+    }
+
+    #[test]
+    fn provenance_records_the_last_file_setting_each_value() {
+        let base = map([
+            ("hp", 100.into()),
+            ("stats", map([("speed", 1.into()), ("armor", 2.into())])),
+            ("items", seq([1, 2])),
+            ("tags", map([])),
+        ]);
+        let mut provenance = Provenance::default();
+        provenance.attribute(None, &base, "", "forest.json");
+        let layer = map([
+            ("hp", 100.into()),
+            ("stats", map([("speed", 5.into())])),
+            ("items", seq([1])),
+            ("tags", map([])),
+            ("a/b", 1.into()),
+        ]);
+        provenance.attribute(Some(&base), &layer, "", "dlc://forest.json");
+        let fields: Vec<(&str, &str)> = provenance
+            .fields
+            .iter()
+            .map(|(field, origin)| (field.as_str(), origin.as_str()))
+            .collect();
+        assert_eq!(
+            fields,
+            [
+                ("/a~1b", "dlc://forest.json"),
+                ("/hp", "forest.json"),
+                ("/items/0", "forest.json"),
+                ("/stats/speed", "dlc://forest.json"),
+                ("/tags", "forest.json"),
+            ]
+        );
+        assert_eq!(provenance.origin("/stats/speed"), Some("dlc://forest.json"));
+        assert_eq!(provenance.origin("/hp/missing"), Some("forest.json"));
+        assert_eq!(provenance.origin("/stats"), None);
+    }
+}
@@ -0,0 +1,514 @@
+#[cfg(any(feature = "postcard", feature = "cbor"))]
+use crate::fingerprint::Fingerprint;
+use crate::loader::AssetCheck;
+use crate::{DataAsset, ToDefinition, ValidateAsset, ValidationMode};
+use bevy_app::{App, Plugin};
+#[cfg(any(feature = "postcard", feature = "cbor"))]
+use bevy_asset::AssetApp;
+#[cfg(any(feature = "postcard", feature = "cbor"))]
+use bevy_asset::saver::AssetSaver;
+#[cfg(any(feature = "postcard", feature = "cbor"))]
+use bevy_reflect::Typed;
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/// Plugin to load your asset type `A` from several file formats at once.
+///
+/// The asset type is initialized once and a loader is registered for every added format.
+/// Building the plugin panics if two formats share an extension, since Bevy could not tell
+/// which loader to use for those files.
+///
+/// ```no_run
+/// # use bevy::prelude::*;
+/// # use bevy_common_assets::CommonAssetsPlugin;
+/// # #[derive(serde::Deserialize, serde::Serialize, Asset, TypePath)]
+/// # struct Level;
+/// App::new().add_plugins((
+///     DefaultPlugins,
+///     CommonAssetsPlugin::<Level>::new()
+///         .json(&["level.json"])
+///         .ron(&["level.ron"])
+///         .postcard(&["level.pc"])
+///         .postcard_saver(),
+/// ));
+/// ```
+pub struct CommonAssetsPlugin<A> {
+    formats: Vec<FormatLoader<A>>,
+    #[cfg(any(feature = "postcard", feature = "cbor"))]
+    savers: Vec<SaverProcessors<A>>,
+    #[cfg(any(feature = "postcard", feature = "cbor"))]
+    fingerprint: Option<Fingerprint>,
+    check: AssetCheck<A>,
+    changes: Option<fn(&mut App)>,
+}
+
+/// An asset type that knows the formats it is loaded from
+///
+/// Usually derived with `#[derive(CommonAsset)]` (requires the `derive` feature), which also
+/// derives `Asset` and `TypePath`. The formats and extensions are set in the `common_asset` attribute
+/// and the asset is registered with [`CommonAssetApp::add_common_asset`].
+///
+/// ```ignore
+/// #[derive(serde::Deserialize, serde::Serialize, CommonAsset)]
+/// #[common_asset(json = "level.json", ron = "level.ron", saver = "postcard")]
+/// struct Level {
+///     positions: Vec<[f32; 3]>,
+/// }
+///
+/// App::new().add_common_asset::<Level>();
+/// ```
+pub trait CommonAsset: DataAsset + Sized {
+    /// The plugin loading this asset from all of its formats
+    fn common_assets_plugin() -> CommonAssetsPlugin<Self>;
+}
+
+/// Extension trait to register [`CommonAsset`]s on an [`App`]
+pub trait CommonAssetApp {
+    /// Add the [`CommonAssetsPlugin`] of the asset type `A`
+    fn add_common_asset<A: CommonAsset>(&mut self) -> &mut Self;
+}
+
+impl CommonAssetApp for App {
+    fn add_common_asset<A: CommonAsset>(&mut self) -> &mut Self {
+        self.add_plugins(A::common_assets_plugin())
+    }
+}
+
+/// A format added to a [`CommonAssetsPlugin`]
+struct FormatLoader<A> {
+    format: Format,
+    extensions: Vec<&'static str>,
+    register_loader: Box<RegisterLoader<A>>,
+}
+
+/// Registers the loader of a format, reading the settings of the [`CommonAssetsPlugin`]
+type RegisterLoader<A> = dyn Fn(&CommonAssetsPlugin<A>, &mut App) + Send + Sync;
+
+/// A saver added to a [`CommonAssetsPlugin`]
+#[cfg(any(feature = "postcard", feature = "cbor"))]
+struct SaverProcessors<A> {
+    /// The format of the saved files, which needs a loader for the processed assets
+    output: Format,
+    register_output_loader: fn(&CommonAssetsPlugin<A>, &mut App),
+    register_processor: fn(&CommonAssetsPlugin<A>, &mut App, Format),
+}
+
+/// The formats a [`CommonAssetsPlugin`] can load
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Format {
+    #[cfg(feature = "json")]
+    Json,
+    #[cfg(feature = "ron")]
+    Ron,
+    #[cfg(feature = "toml")]
+    Toml,
+    #[cfg(feature = "yaml")]
+    Yaml,
+    #[cfg(feature = "msgpack")]
+    MsgPack,
+    #[cfg(feature = "xml")]
+    Xml,
+    #[cfg(feature = "postcard")]
+    Postcard,
+    #[cfg(feature = "cbor")]
+    Cbor,
+}
+
+impl Format {
+    fn name(self) -> &'static str {
+        match self {
+            #[cfg(feature = "json")]
+            Format::Json => "json",
+            #[cfg(feature = "ron")]
+            Format::Ron => "ron",
+            #[cfg(feature = "toml")]
+            Format::Toml => "toml",
+            #[cfg(feature = "yaml")]
+            Format::Yaml => "yaml",
+            #[cfg(feature = "msgpack")]
+            Format::MsgPack => "MessagePack",
+            #[cfg(feature = "xml")]
+            Format::Xml => "xml",
+            #[cfg(feature = "postcard")]
+            Format::Postcard => "postcard",
+            #[cfg(feature = "cbor")]
+            Format::Cbor => "cbor",
+        }
+    }
+}
+
+impl<A> Default for CommonAssetsPlugin<A>
+where
+    A: DataAsset,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<A> CommonAssetsPlugin<A>
+where
+    A: DataAsset,
+{
+    /// Create a new plugin without any formats
+    pub fn new() -> Self {
+        Self {
+            formats: vec![],
+            #[cfg(any(feature = "postcard", feature = "cbor"))]
+            savers: vec![],
+            #[cfg(any(feature = "postcard", feature = "cbor"))]
+            fingerprint: None,
+            check: AssetCheck::default(),
+            changes: None,
+        }
+    }
+
+    fn add_format(
+        mut self,
+        format: Format,
+        extensions: &[&'static str],
+        register_loader: impl Fn(&Self, &mut App) + Send + Sync + 'static,
+    ) -> Self {
+        self.formats.push(FormatLoader {
+            format,
+            extensions: extensions.to_owned(),
+            register_loader: Box::new(register_loader),
+        });
+        self
+    }
+
+    /// Load the asset from json files with the given extensions
+    #[cfg(feature = "json")]
+    pub fn json(self, extensions: &[&'static str]) -> Self {
+        let owned_extensions = extensions.to_owned();
+        self.add_format(Format::Json, extensions, move |plugin, app| {
+            crate::json::JsonAssetPlugin::<A>::new(&owned_extensions)
+                .with_check(plugin.check)
+                .register_loader(app)
+        })
+    }
+
+    /// Load the asset from ron files with the given extensions
+    #[cfg(feature = "ron")]
+    pub fn ron(self, extensions: &[&'static str]) -> Self {
+        let owned_extensions = extensions.to_owned();
+        self.add_format(Format::Ron, extensions, move |plugin, app| {
+            crate::ron::RonAssetPlugin::<A>::new(&owned_extensions)
+                .with_check(plugin.check)
+                .register_loader(app)
+        })
+    }
+
+    /// Load the asset from toml files with the given extensions
+    #[cfg(feature = "toml")]
+    pub fn toml(self, extensions: &[&'static str]) -> Self {
+        let owned_extensions = extensions.to_owned();
+        self.add_format(Format::Toml, extensions, move |plugin, app| {
+            crate::toml::TomlAssetPlugin::<A>::new(&owned_extensions)
+                .with_check(plugin.check)
+                .register_loader(app)
+        })
+    }
+
+    /// Load the asset from yaml files with the given extensions
+    #[cfg(feature = "yaml")]
+    pub fn yaml(self, extensions: &[&'static str]) -> Self {
+        let owned_extensions = extensions.to_owned();
+        self.add_format(Format::Yaml, extensions, move |plugin, app| {
+            crate::yaml::YamlAssetPlugin::<A>::new(&owned_extensions)
+                .with_check(plugin.check)
+                .register_loader(app)
+        })
+    }
+
+    /// Load the asset from `MessagePack` files with the given extensions
+    #[cfg(feature = "msgpack")]
+    pub fn msgpack(self, extensions: &[&'static str]) -> Self {
+        let owned_extensions = extensions.to_owned();
+        self.add_format(Format::MsgPack, extensions, move |plugin, app| {
+            crate::msgpack::MsgPackAssetPlugin::<A>::new(&owned_extensions)
+                .with_check(plugin.check)
+                .register_loader(app)
+        })
+    }
+
+    /// Load the asset from xml files with the given extensions
+    #[cfg(feature = "xml")]
+    pub fn xml(self, extensions: &[&'static str]) -> Self {
+        let owned_extensions = extensions.to_owned();
+        self.add_format(Format::Xml, extensions, move |plugin, app| {
+            crate::xml::XmlAssetPlugin::<A>::new(&owned_extensions)
+                .with_check(plugin.check)
+                .register_loader(app)
+        })
+    }
+
+    /// Load the asset from postcard files with the given extensions
+    #[cfg(feature = "postcard")]
+    pub fn postcard(self, extensions: &[&'static str]) -> Self {
+        let owned_extensions = extensions.to_owned();
+        self.add_format(Format::Postcard, extensions, move |plugin, app| {
+            crate::postcard::PostcardAssetPlugin::<A>::new(&owned_extensions)
+                .with_optional_fingerprint(plugin.fingerprint)
+                .with_check(plugin.check)
+                .register_loader(app)
+        })
+    }
+
+    /// Load the asset from cbor files with the given extensions
+    #[cfg(feature = "cbor")]
+    pub fn cbor(self, extensions: &[&'static str]) -> Self {
+        let owned_extensions = extensions.to_owned();
+        self.add_format(Format::Cbor, extensions, move |plugin, app| {
+            crate::cbor::CborAssetPlugin::<A>::new(&owned_extensions)
+                .with_optional_fingerprint(plugin.fingerprint)
+                .with_check(plugin.check)
+                .register_loader(app)
+        })
+    }
+
+    /// Write the [`Fingerprint`] of `A` with the postcard and cbor savers and only load postcard
+    /// and cbor files with a matching fingerprint.
+    ///
+    /// Processed assets saved for an older schema of `A` then fail to load, asking to re-process
+    /// them, instead of deserializing into wrong data.
+    #[cfg(any(feature = "postcard", feature = "cbor"))]
+    pub fn with_fingerprint(mut self) -> Self
+    where
+        A::Definition: Typed,
+    {
+        self.fingerprint = Some(Fingerprint::of::<A>());
+        self
+    }
+
+    /// Check loaded assets of all formats with their [`ValidateAsset`] implementation and fail
+    /// or warn for invalid assets depending on `mode`.
+    ///
+    /// The postcard and cbor savers refuse to save invalid assets regardless of `mode`, so asset
+    /// processing fails for them.
+    pub fn with_asset_validation(mut self, mode: ValidationMode) -> Self
+    where
+        A: ValidateAsset,
+    {
+        self.check = AssetCheck::new(mode);
+        self
+    }
+
+    /// Send a [`DataAssetChanged`](crate::change::DataAssetChanged) message with the changed
+    /// fields whenever an asset of type `A` is modified, e.g. by hot reloading a file of any
+    /// added format.
+    pub fn with_change_events(mut self) -> Self
+    where
+        A: ToDefinition,
+    {
+        self.changes = Some(crate::change::register::<A>);
+        self
+    }
+
+    /// Register asset processors saving the asset loaded from any added format as postcard.
+    ///
+    /// The processors are `LoadTransformAndSave` processors with an `IdentityAssetTransformer`
+    /// and can be selected in the `.meta` files of your assets.
+    #[cfg(feature = "postcard")]
+    pub fn postcard_saver(mut self) -> Self
+    where
+        A: ToDefinition,
+    {
+        self.savers.push(SaverProcessors {
+            output: Format::Postcard,
+            register_output_loader: |plugin, app| {
+                crate::postcard::PostcardAssetPlugin::<A>::new(&[])
+                    .with_optional_fingerprint(plugin.fingerprint)
+                    .with_check(plugin.check)
+                    .register_loader(app)
+            },
+            register_processor: |plugin, app, format| {
+                register_processor(
+                    app,
+                    format,
+                    crate::postcard::PostcardAssetSaver::<A>::with_optional_fingerprint(
+                        plugin.fingerprint,
+                    )
+                    .with_check(plugin.check),
+                )
+            },
+        });
+        self
+    }
+
+    /// Register asset processors saving the asset loaded from any added format as cbor.
+    ///
+    /// The processors are `LoadTransformAndSave` processors with an `IdentityAssetTransformer`
+    /// and can be selected in the `.meta` files of your assets.
+    #[cfg(feature = "cbor")]
+    pub fn cbor_saver(mut self) -> Self
+    where
+        A: ToDefinition,
+    {
+        self.savers.push(SaverProcessors {
+            output: Format::Cbor,
+            register_output_loader: |plugin, app| {
+                crate::cbor::CborAssetPlugin::<A>::new(&[])
+                    .with_optional_fingerprint(plugin.fingerprint)
+                    .with_check(plugin.check)
+                    .register_loader(app)
+            },
+            register_processor: |plugin, app, format| {
+                register_processor(
+                    app,
+                    format,
+                    crate::cbor::CborAssetSaver::<A>::with_optional_fingerprint(plugin.fingerprint)
+                        .with_check(plugin.check),
+                )
+            },
+        });
+        self
+    }
+
+    /// Describe all extensions that are used by more than one format
+    fn conflicts(&self) -> Option<String> {
+        let mut formats_by_extension: HashMap<&str, Vec<Format>> = HashMap::new();
+        for loader in &self.formats {
+            for extension in &loader.extensions {
+                let formats = formats_by_extension.entry(extension).or_default();
+                // Extensions given several times for the same format do not conflict
+                if !formats.contains(&loader.format) {
+                    formats.push(loader.format);
+                }
+            }
+        }
+        let mut conflicts: Vec<_> = formats_by_extension
+            .into_iter()
+            .filter(|(_, formats)| formats.len() > 1)
+            .collect();
+        if conflicts.is_empty() {
+            return None;
+        }
+        conflicts.sort_by_key(|(extension, _)| *extension);
+        let mut description = String::new();
+        for (extension, formats) in conflicts {
+            let formats: Vec<_> = formats.iter().map(|format| format.name()).collect();
+            let _ = write!(
+                description,
+                "\n  `{extension}` is used by {}",
+                formats.join(" and ")
+            );
+        }
+        Some(description)
+    }
+}
+
+impl<A> Plugin for CommonAssetsPlugin<A>
+where
+    A: DataAsset,
+{
+    fn build(&self, app: &mut App) {
+        if let Some(conflicts) = self.conflicts() {
+            panic!(
+                "CommonAssetsPlugin<{}> has conflicting file extensions:{conflicts}",
+                A::type_path()
+            );
+        }
+        crate::data_asset::init_data_asset::<A>(app);
+        for loader in &self.formats {
+            (loader.register_loader)(self, app);
+        }
+        if let Some(register) = self.changes {
+            register(app);
+        }
+        #[cfg(any(feature = "postcard", feature = "cbor"))]
+        for saver in &self.savers {
+            if !self
+                .formats
+                .iter()
+                .any(|loader| loader.format == saver.output)
+            {
+                (saver.register_output_loader)(self, app);
+            }
+            for loader in &self.formats {
+                (saver.register_processor)(self, app, loader.format);
+            }
+        }
+    }
+}
+
+/// Register a processor loading `A` from `format` and saving it with `saver`
+#[cfg(any(feature = "postcard", feature = "cbor"))]
+fn register_processor<A, S>(app: &mut App, format: Format, saver: S)
+where
+    A: DataAsset,
+    S: AssetSaver<Asset = A>,
+{
+    use bevy_asset::processor::LoadTransformAndSave;
+    use bevy_asset::transformer::IdentityAssetTransformer;
+
+    macro_rules! register {
+        ($loader:ty) => {
+            app.register_asset_processor::<LoadTransformAndSave<$loader, IdentityAssetTransformer<A>, S>>(
+                LoadTransformAndSave::new(IdentityAssetTransformer::new(), saver),
+            )
+        };
+    }
+    match format {
+        #[cfg(feature = "json")]
+        Format::Json => register!(crate::json::JsonAssetLoader<A>),
+        #[cfg(feature = "ron")]
+        Format::Ron => register!(crate::ron::RonAssetLoader<A>),
+        #[cfg(feature = "toml")]
+        Format::Toml => register!(crate::toml::TomlAssetLoader<A>),
+        #[cfg(feature = "yaml")]
+        Format::Yaml => register!(crate::yaml::YamlAssetLoader<A>),
+        #[cfg(feature = "msgpack")]
+        Format::MsgPack => register!(crate::msgpack::MsgPackAssetLoader<A>),
+        #[cfg(feature = "xml")]
+        Format::Xml => register!(crate::xml::XmlAssetLoader<A>),
+        #[cfg(feature = "postcard")]
+        Format::Postcard => register!(crate::postcard::PostcardAssetLoader<A>),
+        #[cfg(feature = "cbor")]
+        Format::Cbor => register!(crate::cbor::CborAssetLoader<A>),
+    };
+}
+
+#[cfg(all(test, feature = "json"))]
+mod tests {
+    use super::*;
+    use bevy_asset::Asset;
+    use bevy_reflect::TypePath;
+
+    #[derive(Asset, TypePath, serde::Deserialize)]
+    struct Level;
+
+    #[test]
+    #[cfg(all(feature = "json", feature = "ron"))]
+    fn extensions_of_several_formats_conflict() {
+        let plugin = CommonAssetsPlugin::<Level>::new()
+            .json(&["level", "json", "shared"])
+            .ron(&["ron", "shared"])
+            .ron(&["level"]);
+        assert_eq!(
+            plugin.conflicts().as_deref(),
+            Some("\n  `level` is used by json and ron\n  `shared` is used by json and ron")
+        );
+    }
+
+    #[test]
+    fn repeated_extensions_of_one_format_do_not_conflict() {
+        let plugin = CommonAssetsPlugin::<Level>::new()
+            .json(&["json", "json"])
+            .json(&["json"]);
+        assert_eq!(plugin.conflicts(), None);
+    }
+
+    #[test]
+    #[cfg(all(feature = "json", feature = "ron"))]
+    #[should_panic(
+        expected = "has conflicting file extensions:\n  `level` is used by json and ron"
+    )]
+    fn conflicts_fail_the_build() {
+        App::new().add_plugins(
+            CommonAssetsPlugin::<Level>::new()
+                .json(&["level"])
+                .ron(&["level"]),
+        );
+    }
+}
@@ -1,8 +1,13 @@
+use crate::fingerprint::{Fingerprint, FingerprintError, strip_envelope};
+use crate::loader::AssetCheck;
+use crate::stream::{Decoded, LoadedStream, read_records};
+use crate::{DataAsset, DataAssetError, ToDefinition, ValidateAsset, ValidationMode};
 use bevy_app::{App, Plugin};
 use bevy_asset::{
-    Asset, AssetApp, AssetLoader, AsyncWriteExt, LoadContext, io::Reader, saver::AssetSaver,
+    AssetApp, AssetLoader, AsyncWriteExt, LoadContext, io::Reader, saver::AssetSaver,
 };
-use postcard::{from_bytes, to_stdvec};
+use bevy_reflect::{TypePath, Typed};
+use postcard::{from_bytes, from_bytes_cobs, to_stdvec, to_stdvec_cobs};
 use serde::{Deserialize, Serialize};
 use std::marker::PhantomData;
 use thiserror::Error;
@@ -10,42 +15,109 @@ use thiserror::Error;
 /// Plugin to load your asset type `A` from `Postcard` files.
 pub struct PostcardAssetPlugin<A> {
     extensions: Vec<&'static str>,
-    _marker: PhantomData<A>,
+    fingerprint: Option<Fingerprint>,
+    check: AssetCheck<A>,
+    changes: Option<fn(&mut App)>,
 }
 
 impl<A> Plugin for PostcardAssetPlugin<A>
 where
-    for<'de> A: Deserialize<'de> + Asset,
+    A: DataAsset,
 {
     fn build(&self, app: &mut App) {
-        app.init_asset::<A>()
-            .register_asset_loader(PostcardAssetLoader::<A> {
-                extensions: self.extensions.clone(),
-                _marker: PhantomData,
-            });
+        crate::data_asset::init_data_asset::<A>(app);
+        self.register_loader(app);
+        if let Some(register) = self.changes {
+            register(app);
+        }
     }
 }
 
 impl<A> PostcardAssetPlugin<A>
 where
-    for<'de> A: Deserialize<'de> + Asset,
+    A: DataAsset,
 {
     /// Create a new plugin that will load assets from files with the given extensions.
     pub fn new(extensions: &[&'static str]) -> Self {
         Self {
             extensions: extensions.to_owned(),
-            _marker: PhantomData,
+            fingerprint: None,
+            check: AssetCheck::default(),
+            changes: None,
         }
     }
+
+    /// Only load files with the [`Fingerprint`] of `A`, as written by
+    /// [`PostcardAssetSaver::with_fingerprint`].
+    ///
+    /// Files without a fingerprint or with the fingerprint of an older schema fail to load.
+    pub fn with_fingerprint(mut self) -> Self
+    where
+        A::Definition: Typed,
+    {
+        self.fingerprint = Some(Fingerprint::of::<A>());
+        self
+    }
+
+    pub(crate) fn with_optional_fingerprint(mut self, fingerprint: Option<Fingerprint>) -> Self {
+        self.fingerprint = fingerprint;
+        self
+    }
+
+    /// Check loaded assets with their [`ValidateAsset`] implementation and fail or warn for
+    /// invalid assets depending on `mode`.
+    pub fn with_asset_validation(mut self, mode: ValidationMode) -> Self
+    where
+        A: ValidateAsset,
+    {
+        self.check = AssetCheck::new(mode);
+        self
+    }
+
+    /// Send a [`DataAssetChanged`](crate::change::DataAssetChanged) message with the changed
+    /// fields whenever an asset of type `A` is modified, e.g. by hot reloading.
+    pub fn with_change_events(mut self) -> Self
+    where
+        A: ToDefinition,
+    {
+        self.changes = Some(crate::change::register::<A>);
+        self
+    }
+
+    pub(crate) fn with_check(mut self, check: AssetCheck<A>) -> Self {
+        self.check = check;
+        self
+    }
+
+    /// Register the loader of this plugin without initializing the asset
+    pub(crate) fn register_loader(&self, app: &mut App) {
+        app.register_asset_loader(PostcardAssetLoader::<A> {
+            extensions: self.extensions.clone(),
+            fingerprint: self.fingerprint,
+            check: self.check,
+        });
+    }
 }
 
 /// Loads your asset type `A` from `Postcard` files
 pub struct PostcardAssetLoader<A> {
     extensions: Vec<&'static str>,
-    _marker: PhantomData<A>,
+    fingerprint: Option<Fingerprint>,
+    check: AssetCheck<A>,
 }
 
-/// Possible errors that can be produced by [`PostcardAssetLoader`] or [`PostcardAssetSaver`]
+impl<A> PostcardAssetLoader<A> {
+    /// Create a loader without extensions for other loaders delegating to it
+    pub(crate) fn new(check: AssetCheck<A>, fingerprint: Option<Fingerprint>) -> Self {
+        Self {
+            extensions: vec![],
+            fingerprint,
+            check,
+        }
+    }
+}
+
+/// Possible errors that can be produced by the `Postcard` asset loaders and savers
 #[non_exhaustive]
 #[derive(Debug, Error)]
 pub enum PostcardAssetError {
@@ -55,11 +127,17 @@ pub enum PostcardAssetError {
     /// A [Postcard Error](postcard::Error)
     #[error("Could not parse Postcard: {0}")]
     PostcardError(#[from] postcard::Error),
+    /// A missing or outdated [`Fingerprint`]
+    #[error(transparent)]
+    FingerprintError(#[from] FingerprintError),
+    /// An error of the format independent loading steps
+    #[error(transparent)]
+    DataAssetError(#[from] DataAssetError),
 }
 
 impl<A> AssetLoader for PostcardAssetLoader<A>
 where
-    for<'de> A: Deserialize<'de> + Asset,
+    A: DataAsset,
 {
     type Asset = A;
     type Settings = ();
@@ -69,11 +147,16 @@ where
         &self,
         reader: &mut dyn Reader,
         _settings: &(),
-        _load_context: &mut LoadContext<'_>,
+        load_context: &mut LoadContext<'_>,
     ) -> Result<Self::Asset, Self::Error> {
         let mut bytes = Vec::new();
         reader.read_to_end(&mut bytes).await?;
-        let asset = from_bytes::<A>(&bytes)?;
+        let bytes = strip_envelope(&bytes, A::type_path(), self.fingerprint.as_ref())?;
+        let definition = from_bytes::<A::Definition>(bytes)?;
+        let asset = A::from_definition(definition, load_context)
+            .await
+            .map_err(DataAssetError::Definition)?;
+        self.check.check(&asset, load_context)?;
         Ok(asset)
     }
 
@@ -84,18 +167,51 @@ where
 
 /// Saves your asset type `A` to `Postcard` files
 pub struct PostcardAssetSaver<A> {
-    _marker: PhantomData<A>,
+    fingerprint: Option<Fingerprint>,
+    check: AssetCheck<A>,
 }
 
 impl<A> Default for PostcardAssetSaver<A> {
     fn default() -> Self {
         Self {
-            _marker: PhantomData,
+            fingerprint: None,
+            check: AssetCheck::default(),
         }
     }
 }
 
-impl<A: Asset + for<'de> Deserialize<'de> + Serialize> AssetSaver for PostcardAssetSaver<A> {
+impl<A> PostcardAssetSaver<A> {
+    /// Create a saver writing the [`Fingerprint`] of `A` in front of the data
+    pub fn with_fingerprint() -> Self
+    where
+        A: DataAsset<Definition: Typed>,
+    {
+        Self::with_optional_fingerprint(Some(Fingerprint::of::<A>()))
+    }
+
+    pub(crate) fn with_optional_fingerprint(fingerprint: Option<Fingerprint>) -> Self {
+        Self {
+            fingerprint,
+            check: AssetCheck::default(),
+        }
+    }
+
+    /// Refuse to save assets failing their [`ValidateAsset`] check, which fails asset processing
+    pub fn with_asset_validation(mut self) -> Self
+    where
+        A: ValidateAsset,
+    {
+        self.check = AssetCheck::new(ValidationMode::Fail);
+        self
+    }
+
+    pub(crate) fn with_check(mut self, check: AssetCheck<A>) -> Self {
+        self.check = check;
+        self
+    }
+}
+
+impl<A: ToDefinition> AssetSaver for PostcardAssetSaver<A> {
     type Asset = A;
     type Settings = ();
     type OutputLoader = PostcardAssetLoader<A>;
@@ -107,8 +223,140 @@ impl<A: Asset + for<'de> Deserialize<'de> + Serialize> AssetSaver for PostcardAs
         asset: bevy_asset::saver::SavedAsset<'_, Self::Asset>,
         _settings: &Self::Settings,
     ) -> Result<<Self::OutputLoader as AssetLoader>::Settings, Self::Error> {
-        let bytes = to_stdvec(&asset.get())?;
+        self.check.require(asset.get())?;
+        if let Some(fingerprint) = &self.fingerprint {
+            writer.write_all(&fingerprint.envelope()).await?;
+        }
+        let bytes = to_stdvec(&asset.get().to_definition())?;
         writer.write_all(&bytes).await?;
         Ok(())
     }
 }
+
+/// Plugin to load streams of your asset type `A` from COBS framed `Postcard` files.
+///
+/// Every record is a `Postcard` message encoded with
+/// [COBS](https://en.wikipedia.org/wiki/Consistent_Overhead_Byte_Stuffing) and terminated by a
+/// zero byte. The records are collected in a [`LoadedStream`] asset.
+pub struct PostcardStreamAssetPlugin<A> {
+    extensions: Vec<&'static str>,
+    _marker: PhantomData<A>,
+}
+
+impl<A> Plugin for PostcardStreamAssetPlugin<A>
+where
+    for<'de> A: Deserialize<'de> + TypePath + Send + Sync + 'static,
+{
+    fn build(&self, app: &mut App) {
+        app.init_asset::<LoadedStream<A>>()
+            .register_asset_loader(PostcardStreamAssetLoader::<A> {
+                extensions: self.extensions.clone(),
+                _marker: PhantomData,
+            });
+    }
+}
+
+impl<A> PostcardStreamAssetPlugin<A>
+where
+    for<'de> A: Deserialize<'de> + TypePath + Send + Sync + 'static,
+{
+    /// Create a new plugin that will load assets from files with the given extensions.
+    pub fn new(extensions: &[&'static str]) -> Self {
+        Self {
+            extensions: extensions.to_owned(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+/// Loads streams of your asset type `A` from COBS framed `Postcard` files
+pub struct PostcardStreamAssetLoader<A> {
+    extensions: Vec<&'static str>,
+    _marker: PhantomData<A>,
+}
+
+impl<A> AssetLoader for PostcardStreamAssetLoader<A>
+where
+    for<'de> A: Deserialize<'de> + TypePath + Send + Sync + 'static,
+{
+    type Asset = LoadedStream<A>;
+    type Settings = ();
+    type Error = PostcardAssetError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let records = read_records(
+            reader,
+            |bytes, scanned, _| -> Result<_, PostcardAssetError> {
+                let Some(frame_end) = bytes[scanned..].iter().position(|byte| *byte == 0) else {
+                    return Ok(Decoded::Incomplete);
+                };
+                let frame_end = scanned + frame_end;
+                if frame_end == 0 {
+                    return Ok(Decoded::Skip(1));
+                }
+                let record = from_bytes_cobs::<A>(&mut bytes[..=frame_end])?;
+                Ok(Decoded::Record(record, frame_end + 1))
+            },
+        )
+        .await?;
+        Ok(LoadedStream { records })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &self.extensions
+    }
+}
+
+/// Saves streams of your asset type `A` to COBS framed `Postcard` files
+pub struct PostcardStreamAssetSaver<A> {
+    _marker: PhantomData<A>,
+}
+
+impl<A> Default for PostcardStreamAssetSaver<A> {
+    fn default() -> Self {
+        Self {
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<A> PostcardStreamAssetSaver<A>
+where
+    A: Serialize,
+{
+    /// Write `record` at the end of `writer`, see [`LoadedStream`]
+    pub async fn append_record(
+        writer: &mut bevy_asset::io::Writer,
+        record: &A,
+    ) -> Result<(), PostcardAssetError> {
+        writer.write_all(&to_stdvec_cobs(record)?).await?;
+        Ok(())
+    }
+}
+
+impl<A> AssetSaver for PostcardStreamAssetSaver<A>
+where
+    for<'de> A: Deserialize<'de> + Serialize + TypePath + Send + Sync + 'static,
+{
+    type Asset = LoadedStream<A>;
+    type Settings = ();
+    type OutputLoader = PostcardStreamAssetLoader<A>;
+    type Error = PostcardAssetError;
+
+    async fn save(
+        &self,
+        writer: &mut bevy_asset::io::Writer,
+        asset: bevy_asset::saver::SavedAsset<'_, Self::Asset>,
+        _settings: &Self::Settings,
+    ) -> Result<<Self::OutputLoader as AssetLoader>::Settings, Self::Error> {
+        for record in asset.get().records.iter() {
+            Self::append_record(writer, record).await?;
+        }
+        Ok(())
+    }
+}
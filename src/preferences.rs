@@ -0,0 +1,402 @@
+use crate::data::{DataFormat, read_definition, write_definition};
+use crate::save::write_file;
+use bevy_app::{App, AppExit, Last, Plugin};
+use bevy_asset::io::{AssetReaderError, AssetSource, Reader};
+use bevy_asset::{AssetPath, AssetServer};
+use bevy_ecs::change_detection::DetectChanges;
+use bevy_ecs::component::Tick;
+use bevy_ecs::message::MessageReader;
+use bevy_ecs::prelude::{IntoScheduleConfigs, Res, ResMut, Resource, resource_exists};
+use bevy_tasks::{IoTaskPool, Task, block_on};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::future::Future;
+use std::marker::PhantomData;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use tracing::{error, warn};
+
+/// Plugin to load the resource `R` from a preferences file and write it back when it changes
+///
+/// Preferences like volume, key bindings or graphics settings are read from the file at the
+/// given asset path when the app starts, in the format of its extension. Without a file, `R`
+/// starts as its default. The resource exists before the `Startup` schedule runs.
+///
+/// To guarantee that, the file is read in [`Plugin::finish`], blocking the main thread until
+/// the read completes. This is meant for small files in a local directory; for config files
+/// that can take long to read, e.g. from a remote source, use the
+/// [`ConfigResourcePlugin`](crate::config::ConfigResourcePlugin), which loads its file as an
+/// asset in the background.
+///
+/// Whenever the resource is changed, including in `Startup` systems, it is written back through the
+/// [`AssetWriter`](bevy_asset::io::AssetWriter) of the asset source, once it was not changed for
+/// the [debounce duration](Self::with_debounce). Files are replaced atomically, and RON, TOML
+/// and YAML files keep their comments like with [`SaveAssets`](crate::save::SaveAssets).
+/// Pending changes are written before the app exits.
+///
+/// A file that can not be deserialized, e.g. because it is corrupt or from an incompatible
+/// version, is moved to a backup next to it with the extension `.bak`, and the preferences
+/// are reset to their default.
+///
+/// Preferences usually live in a writable directory of the user instead of the assets of the
+/// game. Register an [asset source](bevy_asset::io::AssetSourceBuilder) for that directory
+/// before adding the `AssetPlugin` and use it in the path. Without that source, an error is
+/// logged and `R` stays at its default without being saved.
+///
+/// ```no_run
+/// # use bevy::prelude::*;
+/// # use bevy::asset::io::AssetSourceBuilder;
+/// # use bevy_common_assets::preferences::PreferencesPlugin;
+/// #[derive(serde::Deserialize, serde::Serialize, Resource, Default)]
+/// struct AudioPreferences {
+///     volume: f32,
+/// }
+///
+/// let directory = "preferences".to_string();
+/// App::new()
+///     .register_asset_source(
+///         "preferences",
+///         AssetSourceBuilder::platform_default(&directory, None),
+///     )
+///     .add_plugins((
+///         DefaultPlugins,
+///         PreferencesPlugin::<AudioPreferences>::new("preferences://audio.toml"),
+///     ));
+/// ```
+pub struct PreferencesPlugin<R> {
+    path: AssetPath<'static>,
+    format: DataFormat,
+    debounce: Duration,
+    _marker: PhantomData<fn() -> R>,
+}
+
+impl<R> PreferencesPlugin<R>
+where
+    R: Resource + Serialize + DeserializeOwned + Default,
+{
+    /// Create a new plugin that keeps the resource in the file at `path`
+    ///
+    /// # Panics
+    ///
+    /// Panics if the extension of `path` is not one of the enabled formats.
+    #[track_caller]
+    pub fn new(path: impl Into<AssetPath<'static>>) -> Self {
+        let path = path.into();
+        let Some(format) = path
+            .get_full_extension()
+            .and_then(|extension| DataFormat::from_extension(&extension))
+        else {
+            panic!("Can not tell the format of the preferences `{path}`");
+        };
+        Self {
+            path,
+            format,
+            debounce: Duration::from_secs(1),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Wait until the resource was not changed for `debounce` before writing it
+    ///
+    /// The default is one second, so moving a slider only writes the file once.
+    pub fn with_debounce(mut self, debounce: Duration) -> Self {
+        self.debounce = debounce;
+        self
+    }
+}
+
+impl<R> Plugin for PreferencesPlugin<R>
+where
+    R: Resource + Serialize + DeserializeOwned + Default,
+{
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Last,
+            save_preferences::<R>.run_if(resource_exists::<PreferencesFile<R>>),
+        );
+    }
+
+    fn finish(&self, app: &mut App) {
+        let format = self.format;
+        let server = app.world().resource::<AssetServer>().clone();
+        let source = match server.get_source(self.path.source()) {
+            Ok(source) => source,
+            Err(error) => {
+                error!(
+                    "Using the default preferences, which will not be saved, can not load `{}`: \
+                     {error}",
+                    self.path
+                );
+                app.init_resource::<R>();
+                return;
+            }
+        };
+        // Blocks, so the resource exists before `Startup`, see the documentation of the plugin
+        let (preferences, reset) = block_on(load::<R>(source, &self.path, format));
+        app.insert_resource(preferences);
+        let ticks = app
+            .world()
+            .get_resource_change_ticks::<R>()
+            .expect("the preferences were just inserted");
+        // Systems of the first update run at the current tick, so changes they make would not
+        // look newer than the insertion
+        app.world_mut().increment_change_tick();
+        app.insert_resource(PreferencesFile::<R> {
+            server,
+            path: self.path.clone(),
+            format,
+            debounce: self.debounce,
+            seen_change: ticks.changed,
+            changed: reset.then(Instant::now),
+            saving: None,
+            _marker: PhantomData,
+        });
+    }
+}
+
+/// Read the preferences at `path` and whether they were reset because the file was corrupt
+async fn load<R: DeserializeOwned + Default>(
+    source: &AssetSource,
+    path: &AssetPath<'_>,
+    format: DataFormat,
+) -> (R, bool) {
+    let mut bytes = vec![];
+    let read = match source.reader().read(path.path()).await {
+        Ok(mut reader) => reader.read_to_end(&mut bytes).await.map_err(Into::into),
+        Err(error) => Err(error),
+    };
+    match read {
+        Ok(_) => {}
+        Err(AssetReaderError::NotFound(_)) => return (R::default(), false),
+        Err(error) => {
+            warn!("Using the default preferences, could not read `{path}`: {error}");
+            return (R::default(), false);
+        }
+    }
+    let error = match read_definition(format, &bytes) {
+        Ok(preferences) => return (preferences, false),
+        Err(error) => error,
+    };
+    let mut backup = path.path().as_os_str().to_owned();
+    backup.push(".bak");
+    let backup = PathBuf::from(backup);
+    let moved = match source.writer() {
+        Ok(writer) => writer
+            .rename(path.path(), &backup)
+            .await
+            .map_err(|error| error.to_string()),
+        Err(error) => Err(error.to_string()),
+    };
+    match moved {
+        Ok(()) => {
+            warn!(
+                "Resetting the preferences `{path}`, which were moved to `{}`: {error}",
+                backup.display()
+            );
+            (R::default(), true)
+        }
+        Err(backup_error) => {
+            error!(
+                "Using the default preferences, `{path}` is invalid and could not be backed up: \
+                 {error}, {backup_error}"
+            );
+            (R::default(), false)
+        }
+    }
+}
+
+/// The file of the preferences `R` and the changes not written to it yet
+#[derive(Resource)]
+struct PreferencesFile<R> {
+    server: AssetServer,
+    path: AssetPath<'static>,
+    format: DataFormat,
+    debounce: Duration,
+    /// The change tick of the last change of the preferences that was seen
+    ///
+    /// Starts as the tick of inserting the loaded preferences.
+    seen_change: Tick,
+    /// When the preferences were last changed, if that change is not being written yet
+    changed: Option<Instant>,
+    saving: Option<Task<()>>,
+    _marker: PhantomData<fn() -> R>,
+}
+
+impl<R: Serialize> PreferencesFile<R> {
+    /// Serialize `preferences` and return the future writing them to the file
+    fn write(&self, preferences: &R) -> impl Future<Output = ()> + Send + 'static {
+        let serialized = write_definition(self.format, preferences);
+        let server = self.server.clone();
+        let path = self.path.clone();
+        let format = self.format;
+        async move {
+            let written = match serialized {
+                Ok(bytes) => match server.get_source(path.source()) {
+                    Ok(source) => write_file(source, &path, format, bytes).await,
+                    Err(error) => Err(error.into()),
+                },
+                Err(error) => {
+                    error!("Could not serialize the preferences `{path}`: {error}");
+                    return;
+                }
+            };
+            if let Err(error) = written {
+                error!("Could not save the preferences: {error}");
+            }
+        }
+    }
+}
+
+fn save_preferences<R>(
+    preferences: Res<R>,
+    mut file: ResMut<PreferencesFile<R>>,
+    mut exit: MessageReader<AppExit>,
+) where
+    R: Resource + Serialize,
+{
+    if preferences.last_changed() != file.seen_change {
+        file.seen_change = preferences.last_changed();
+        file.changed = Some(Instant::now());
+    }
+    if exit.read().count() > 0 {
+        if let Some(saving) = file.saving.take() {
+            block_on(saving);
+        }
+        if file.changed.take().is_some() {
+            block_on(file.write(&preferences));
+        }
+        return;
+    }
+    if file
+        .saving
+        .as_ref()
+        .is_some_and(|saving| !saving.is_finished())
+    {
+        return;
+    }
+    file.saving = None;
+    if file
+        .changed
+        .is_some_and(|changed| changed.elapsed() >= file.debounce)
+    {
+        file.changed = None;
+        file.saving = Some(IoTaskPool::get().spawn(file.write(&preferences)));
+    }
+}
+
+#[cfg(all(test, feature = "toml"))]
+mod tests {
+    use super::*;
+    use bevy::prelude::{AssetPlugin, MinimalPlugins};
+    use std::path::Path;
+
+    #[derive(serde::Deserialize, Serialize, Resource, Default, Debug, PartialEq)]
+    struct Audio {
+        volume: f32,
+    }
+
+    /// An app keeping `Audio` in `audio.toml` of a new directory with the given file content
+    fn app(name: &str, content: Option<&str>, debounce: Duration) -> (App, PathBuf) {
+        let directory = std::env::temp_dir().join(format!("bevy_common_assets_{name}"));
+        let _ = std::fs::remove_dir_all(&directory);
+        std::fs::create_dir_all(&directory).unwrap();
+        if let Some(content) = content {
+            std::fs::write(directory.join("audio.toml"), content).unwrap();
+        }
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            AssetPlugin {
+                file_path: directory.to_string_lossy().into(),
+                ..Default::default()
+            },
+            PreferencesPlugin::<Audio>::new("audio.toml").with_debounce(debounce),
+        ));
+        app.finish();
+        app.cleanup();
+        (app, directory)
+    }
+
+    /// Update `app` until `done` or fail after some seconds
+    fn update_until(app: &mut App, mut done: impl FnMut() -> bool) {
+        for _ in 0..500 {
+            app.update();
+            if done() {
+                return;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        panic!("timed out");
+    }
+
+    fn read(path: &Path) -> Option<String> {
+        std::fs::read_to_string(path).ok()
+    }
+
+    #[test]
+    #[should_panic(expected = "Can not tell the format of the preferences `audio.txt`")]
+    fn unknown_extensions_are_refused() {
+        PreferencesPlugin::<Audio>::new("audio.txt");
+    }
+
+    #[test]
+    fn missing_sources_use_the_default() {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            AssetPlugin::default(),
+            PreferencesPlugin::<Audio>::new("missing://audio.toml"),
+        ));
+        app.finish();
+        app.world_mut().resource_mut::<Audio>().volume = 0.5;
+        app.update();
+        assert_eq!(app.world().resource::<Audio>(), &Audio { volume: 0.5 });
+    }
+
+    #[test]
+    fn corrupt_files_are_backed_up_and_reset() {
+        let (mut app, directory) = app("corrupt_preferences", Some("volume = ["), Duration::ZERO);
+        assert_eq!(app.world().resource::<Audio>(), &Audio::default());
+        assert_eq!(
+            read(&directory.join("audio.toml.bak")).as_deref(),
+            Some("volume = [")
+        );
+        // The reset preferences are written without being changed
+        let path = directory.join("audio.toml");
+        update_until(&mut app, || read(&path).is_some());
+        assert_eq!(read(&path).as_deref(), Some("volume = 0.0\n"));
+    }
+
+    #[test]
+    fn changes_are_saved_once_they_settle() {
+        let debounce = Duration::from_millis(300);
+        let content = "# loud\nvolume = 1.0\n";
+        let (mut app, directory) = app("debounced_preferences", Some(content), debounce);
+        let path = directory.join("audio.toml");
+        assert_eq!(app.world().resource::<Audio>(), &Audio { volume: 1.0 });
+        app.update();
+
+        let changed = Instant::now();
+        app.world_mut().resource_mut::<Audio>().volume = 0.5;
+        app.update();
+        app.world_mut().resource_mut::<Audio>().volume = 0.25;
+        update_until(&mut app, || read(&path).as_deref() != Some(content));
+        assert!(changed.elapsed() >= debounce);
+        assert_eq!(read(&path).as_deref(), Some("# loud\nvolume = 0.25\n"));
+    }
+
+    #[test]
+    fn pending_changes_are_saved_on_exit() {
+        let (mut app, directory) = app("exiting_preferences", None, Duration::from_secs(100));
+        app.update();
+        app.world_mut().resource_mut::<Audio>().volume = 0.5;
+        app.update();
+        assert_eq!(read(&directory.join("audio.toml")), None);
+        app.world_mut().write_message(AppExit::Success);
+        app.update();
+        assert_eq!(
+            read(&directory.join("audio.toml")).as_deref(),
+            Some("volume = 0.5\n")
+        );
+    }
+}
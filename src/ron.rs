@@ -1,46 +1,79 @@
+use crate::data::DataFormat;
+use crate::loader::{
+    AssetCheck, DocumentSettings, LoaderOptions, loader_options_methods, text_options_methods,
+};
+use crate::value::{Map, Value, key_to_string};
+use crate::{DataAsset, DataAssetError};
 use bevy_app::{App, Plugin};
 use bevy_asset::io::Reader;
-use bevy_asset::{Asset, AssetApp, AssetLoader, LoadContext};
+use bevy_asset::{AssetApp, AssetLoader, LoadContext};
+use serde::Deserializer;
+use serde::de::{DeserializeSeed, MapAccess, SeqAccess, Visitor};
 use serde_ron::de::from_bytes;
-use std::marker::PhantomData;
+use serde_ron::error::SpannedError;
+use std::fmt;
 use thiserror::Error;
 
 /// Plugin to load your asset type `A` from ron files.
 pub struct RonAssetPlugin<A> {
     extensions: Vec<&'static str>,
-    _marker: PhantomData<A>,
+    options: LoaderOptions<A>,
 }
 
 impl<A> Plugin for RonAssetPlugin<A>
 where
-    for<'de> A: serde::Deserialize<'de> + Asset,
+    A: DataAsset,
 {
     fn build(&self, app: &mut App) {
-        app.init_asset::<A>()
-            .register_asset_loader(RonAssetLoader::<A> {
-                extensions: self.extensions.clone(),
-                _marker: PhantomData,
-            });
+        crate::data_asset::init_data_asset::<A>(app);
+        self.register_loader(app);
     }
 }
 
 impl<A> RonAssetPlugin<A>
 where
-    for<'de> A: serde::Deserialize<'de> + Asset,
+    A: DataAsset,
 {
     /// Create a new plugin that will load assets from files with the given extensions.
     pub fn new(extensions: &[&'static str]) -> Self {
         Self {
             extensions: extensions.to_owned(),
-            _marker: PhantomData,
+            options: LoaderOptions::default(),
         }
     }
+
+    pub(crate) fn with_check(mut self, check: AssetCheck<A>) -> Self {
+        self.options.check = check;
+        self
+    }
+
+    /// Register the loader of this plugin without initializing the asset
+    pub(crate) fn register_loader(&self, app: &mut App) {
+        let options = self.options.build(app);
+        app.register_asset_loader(RonAssetLoader::<A> {
+            extensions: self.extensions.clone(),
+            options,
+        });
+    }
+
+    loader_options_methods!();
+    text_options_methods!();
 }
 
 /// Loads your asset type `A` from ron files
 pub struct RonAssetLoader<A> {
     extensions: Vec<&'static str>,
-    _marker: PhantomData<A>,
+    options: LoaderOptions<A>,
+}
+
+impl<A> RonAssetLoader<A> {
+    /// Create a loader without extensions for other loaders delegating to it
+    pub(crate) fn from_options(options: LoaderOptions<A>) -> Self {
+        Self {
+            extensions: vec![],
+            options,
+        }
+    }
 }
 
 /// Possible errors that can be produced by [`RonAssetLoader`]
@@ -53,29 +86,561 @@ pub enum RonLoaderError {
     /// A [RON Error](serde_ron::error::SpannedError)
     #[error("Could not parse RON: {0}")]
     RonError(#[from] serde_ron::error::SpannedError),
+    /// An error of the format independent loading steps
+    #[error(transparent)]
+    DataAssetError(#[from] DataAssetError),
 }
 
 impl<A> AssetLoader for RonAssetLoader<A>
 where
-    for<'de> A: serde::Deserialize<'de> + Asset,
+    A: DataAsset,
 {
     type Asset = A;
-    type Settings = ();
+    type Settings = DocumentSettings;
     type Error = RonLoaderError;
 
     async fn load(
         &self,
         reader: &mut dyn Reader,
-        _settings: &(),
-        _load_context: &mut LoadContext<'_>,
+        settings: &DocumentSettings,
+        load_context: &mut LoadContext<'_>,
     ) -> Result<Self::Asset, Self::Error> {
         let mut bytes = Vec::new();
         reader.read_to_end(&mut bytes).await?;
-        let asset = from_bytes::<A>(&bytes)?;
-        Ok(asset)
+        if self.options.uses_value() {
+            let document = read_value(&bytes)?;
+            return Ok(self
+                .options
+                .load_document(document, DataFormat::Ron, settings, load_context)
+                .await?);
+        }
+        let definition = from_bytes::<A::Definition>(&bytes)?;
+        Ok(self.options.create_asset(definition, load_context).await?)
     }
 
     fn extensions(&self) -> &[&str] {
         &self.extensions
     }
 }
+
+/// Read a RON document into a [`Value`] tree, keeping the names of structs and enum variants.
+///
+/// RON only reports these names when deserializing into a known type, so they are taken from
+/// a scan of the text that lists the openings of all compound values in document order. The
+/// [`RonValueSeed`] takes one opening for every compound value it visits.
+pub(crate) fn read_value(bytes: &[u8]) -> Result<Value, SpannedError> {
+    let mut deserializer = serde_ron::Deserializer::from_bytes(bytes)?;
+    let mut openings = scan_openings(deserializer.remainder()).into_iter();
+    let value = RonValueSeed {
+        openings: &mut openings,
+    }
+    .deserialize(&mut deserializer)
+    .map_err(|error| deserializer.span_error(error))?;
+    deserializer
+        .end()
+        .map_err(|error| deserializer.span_error(error))?;
+    Ok(value)
+}
+
+/// The start of a value in a RON document that is not a plain scalar
+#[derive(Debug, PartialEq)]
+enum Opening {
+    /// A struct, tuple or enum variant with parentheses and its name, which can be empty
+    Parens(String),
+    /// A sequence
+    Bracket,
+    /// A map
+    Brace,
+    /// A unit struct or unit enum variant
+    Unit(String),
+    /// `Some(`
+    Some,
+}
+
+/// Identifiers that are values without a name
+const KEYWORDS: [&str; 9] = [
+    "true", "false", "None", "inf", "inff32", "inff64", "NaN", "NaNf32", "NaNf64",
+];
+
+/// The [`Opening`]s of all values in `text` in document order
+///
+/// Strings, characters, comments and attributes are skipped, and identifiers followed by `:`
+/// directly inside parentheses are field names.
+fn scan_openings(text: &str) -> Vec<Opening> {
+    let mut scanner = Scanner {
+        rest: text,
+        openings: vec![],
+        parens: vec![],
+    };
+    scanner.scan();
+    scanner.openings
+}
+
+/// Finds the [`Opening`]s in the text of a RON document, see [`scan_openings`]
+struct Scanner<'a> {
+    /// The text after the scanned part
+    rest: &'a str,
+    openings: Vec<Opening>,
+    /// For every open bracket, whether it is a parenthesis
+    parens: Vec<bool>,
+}
+
+impl Scanner<'_> {
+    fn scan(&mut self) {
+        loop {
+            self.skip_trivia();
+            let Some(character) = self.rest.chars().next() else {
+                return;
+            };
+            match character {
+                '(' => self.open(Opening::Parens(String::new())),
+                '[' => self.open(Opening::Bracket),
+                '{' => self.open(Opening::Brace),
+                ')' | ']' | '}' => {
+                    self.parens.pop();
+                    self.advance(1);
+                }
+                '"' | '\'' => self.skip_quoted(character),
+                'b' if self.rest[1..].starts_with(['"', '\'', 'r']) => {
+                    self.advance(1);
+                    match self.rest.chars().next() {
+                        Some(quote @ ('"' | '\'')) => self.skip_quoted(quote),
+                        _ if self.skip_raw_string() => {}
+                        _ => self.scan_identifier("b"),
+                    }
+                }
+                'r' if self.skip_raw_string() => {}
+                '#' if self.rest.starts_with("#![") => {
+                    let end = self.rest.find(']').map_or(self.rest.len(), |end| end + 1);
+                    self.advance(end);
+                }
+                character if character.is_ascii_digit() || ".+-".contains(character) => {
+                    self.skip_number();
+                }
+                character if character.is_alphanumeric() || character == '_' => {
+                    self.scan_identifier("");
+                }
+                character => self.advance(character.len_utf8()),
+            }
+        }
+    }
+
+    fn advance(&mut self, length: usize) {
+        self.rest = &self.rest[length..];
+    }
+
+    /// Push `opening` for the bracket at the start of the rest
+    fn open(&mut self, opening: Opening) {
+        self.parens.push(self.rest.starts_with('('));
+        self.openings.push(opening);
+        self.advance(1);
+    }
+
+    /// Skip whitespace and (nested block) comments
+    fn skip_trivia(&mut self) {
+        loop {
+            self.rest = self.rest.trim_start();
+            if self.rest.starts_with("//") {
+                let end = self.rest.find('\n').unwrap_or(self.rest.len());
+                self.advance(end);
+            } else if self.rest.starts_with("/*") {
+                let mut depth = 0;
+                while !self.rest.is_empty() {
+                    if self.rest.starts_with("/*") {
+                        depth += 1;
+                        self.advance(2);
+                    } else if self.rest.starts_with("*/") {
+                        depth -= 1;
+                        self.advance(2);
+                        if depth == 0 {
+                            break;
+                        }
+                    } else {
+                        let character = self.rest.chars().next().unwrap_or_default();
+                        self.advance(character.len_utf8());
+                    }
+                }
+            } else {
+                return;
+            }
+        }
+    }
+
+    /// Skip a string or character literal starting at its `quote`
+    fn skip_quoted(&mut self, quote: char) {
+        let mut characters = self.rest.char_indices().skip(1);
+        while let Some((index, character)) = characters.next() {
+            if character == '\\' {
+                characters.next();
+            } else if character == quote {
+                return self.advance(index + 1);
+            }
+        }
+        self.rest = "";
+    }
+
+    /// Skip a raw string like `r#"text"#` if one starts here
+    fn skip_raw_string(&mut self) -> bool {
+        let Some(after_r) = self.rest.strip_prefix('r') else {
+            return false;
+        };
+        let hashes = after_r.len() - after_r.trim_start_matches('#').len();
+        if !after_r[hashes..].starts_with('"') {
+            return false;
+        }
+        let end = format!("\"{}", "#".repeat(hashes));
+        let body = &after_r[hashes + 1..];
+        let length = body
+            .find(&end)
+            .map_or(body.len(), |index| index + end.len());
+        self.advance(1 + hashes + 1 + length);
+        true
+    }
+
+    /// Skip a number, including signs, exponents and suffixes like `u8`
+    fn skip_number(&mut self) {
+        let mut previous = ' ';
+        let length = self
+            .rest
+            .char_indices()
+            .find(|(index, character)| {
+                let part = *index == 0
+                    || character.is_alphanumeric()
+                    || "_.".contains(*character)
+                    || ("+-".contains(*character) && "eE".contains(previous));
+                previous = *character;
+                !part
+            })
+            .map_or(self.rest.len(), |(index, _)| index);
+        self.advance(length);
+    }
+
+    /// Scan an identifier, which continues `prefix` if the prefix was already skipped
+    fn scan_identifier(&mut self, prefix: &str) {
+        let raw = prefix.is_empty() && self.rest.starts_with("r#");
+        if raw {
+            self.advance(2);
+        }
+        let length = self
+            .rest
+            .find(|character: char| !(character.is_alphanumeric() || character == '_'))
+            .unwrap_or(self.rest.len());
+        let name = format!("{prefix}{}", &self.rest[..length]);
+        self.advance(length);
+        self.skip_trivia();
+        if self.rest.starts_with('(') {
+            let opening = if name == "Some" && !raw {
+                Opening::Some
+            } else {
+                Opening::Parens(name)
+            };
+            self.open(opening);
+        } else if self.rest.starts_with(':') && self.parens.last() == Some(&true) {
+            // a field name
+        } else if raw || !KEYWORDS.contains(&name.as_str()) {
+            self.openings.push(Opening::Unit(name));
+        }
+    }
+}
+
+/// Deserializes a RON value into a [`Value`], taking the names of structs and enum variants
+/// from the [`Opening`]s of the document
+struct RonValueSeed<'a> {
+    openings: &'a mut std::vec::IntoIter<Opening>,
+}
+
+impl RonValueSeed<'_> {
+    fn seed(&mut self) -> RonValueSeed<'_> {
+        RonValueSeed {
+            openings: self.openings,
+        }
+    }
+
+    /// The opening of the visited value, checked with `matches`
+    fn opening<E: serde::de::Error>(
+        &mut self,
+        matches: impl FnOnce(&Opening) -> bool,
+    ) -> Result<Opening, E> {
+        self.openings
+            .next()
+            .filter(matches)
+            .ok_or_else(|| E::custom("could not find the names of structs and enum variants"))
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for RonValueSeed<'_> {
+    type Value = Value;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+/// Give a sequence or map the name of its opening
+fn named(opening: Opening, value: Value) -> Value {
+    match opening {
+        Opening::Parens(name) | Opening::Unit(name) => Value::Named(name, Box::new(value)),
+        _ => value,
+    }
+}
+
+impl<'de> Visitor<'de> for RonValueSeed<'_> {
+    type Value = Value;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("any RON value")
+    }
+
+    fn visit_bool<E>(self, value: bool) -> Result<Value, E> {
+        Ok(value.into())
+    }
+
+    fn visit_i64<E>(self, value: i64) -> Result<Value, E> {
+        Ok(value.into())
+    }
+
+    fn visit_i128<E>(self, value: i128) -> Result<Value, E> {
+        Ok(Value::Integer(value))
+    }
+
+    fn visit_u64<E>(self, value: u64) -> Result<Value, E> {
+        Ok(value.into())
+    }
+
+    fn visit_u128<E: serde::de::Error>(self, value: u128) -> Result<Value, E> {
+        i128::try_from(value)
+            .map(Value::Integer)
+            .map_err(|_| E::custom(format!("integer {value} is too large")))
+    }
+
+    fn visit_f64<E>(self, value: f64) -> Result<Value, E> {
+        Ok(value.into())
+    }
+
+    fn visit_char<E>(self, value: char) -> Result<Value, E> {
+        Ok(value.to_string().into())
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<Value, E> {
+        Ok(value.into())
+    }
+
+    fn visit_bytes<E>(self, value: &[u8]) -> Result<Value, E> {
+        Ok(Value::Bytes(value.to_owned()))
+    }
+
+    fn visit_none<E>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(mut self, deserializer: D) -> Result<Value, D::Error> {
+        self.opening(|opening| *opening == Opening::Some)?;
+        self.deserialize(deserializer)
+    }
+
+    fn visit_unit<E: serde::de::Error>(mut self) -> Result<Value, E> {
+        match self.opening(|opening| matches!(opening, Opening::Parens(_) | Opening::Unit(_)))? {
+            Opening::Parens(name) | Opening::Unit(name) if !name.is_empty() => {
+                Ok(Value::Named(name, Box::new(Value::Null)))
+            }
+            _ => Ok(Value::Null),
+        }
+    }
+
+    fn visit_newtype_struct<D: Deserializer<'de>>(
+        mut self,
+        deserializer: D,
+    ) -> Result<Value, D::Error> {
+        let opening = self.opening(|opening| matches!(opening, Opening::Parens(_)))?;
+        let inner = self.deserialize(deserializer)?;
+        Ok(named(opening, Value::Seq(vec![inner])))
+    }
+
+    fn visit_seq<S: SeqAccess<'de>>(mut self, mut access: S) -> Result<Value, S::Error> {
+        let opening =
+            self.opening(|opening| matches!(opening, Opening::Parens(_) | Opening::Bracket))?;
+        let mut items = vec![];
+        while let Some(item) = access.next_element_seed(self.seed())? {
+            items.push(item);
+        }
+        Ok(named(opening, Value::Seq(items)))
+    }
+
+    fn visit_map<M: MapAccess<'de>>(mut self, mut access: M) -> Result<Value, M::Error> {
+        let opening =
+            self.opening(|opening| matches!(opening, Opening::Parens(_) | Opening::Brace))?;
+        let mut map = Map::new();
+        while let Some(key) = access.next_key_seed(self.seed())? {
+            let key = key_to_string(key).map_err(serde::de::Error::custom)?;
+            map.insert(key, access.next_value_seed(self.seed())?);
+        }
+        Ok(named(opening, Value::Map(map)))
+    }
+}
+
+/// Write a [`Value`] tree as RON, restoring the names of structs and enum variants.
+///
+/// Options are written without `Some`, so the document enables the `implicit_some` extension.
+pub(crate) fn write_value(value: &Value) -> Result<String, serde_ron::Error> {
+    let mut text = String::from("#![enable(implicit_some)]\n");
+    write_node(value, 0, &mut text)?;
+    text.push('\n');
+    Ok(text)
+}
+
+fn write_node(value: &Value, indent: usize, text: &mut String) -> Result<(), serde_ron::Error> {
+    match value {
+        Value::Null => text.push_str("None"),
+        Value::Seq(items) => {
+            write_items(items.iter().map(|item| (None, item)), "[]", indent, text)?
+        }
+        Value::Map(map) => write_items(
+            map.iter()
+                .map(|(key, item)| Ok((Some(serde_ron::to_string(key)?), item)))
+                .collect::<Result<Vec<_>, serde_ron::Error>>()?,
+            "{}",
+            indent,
+            text,
+        )?,
+        Value::Named(name, value) => {
+            text.push_str(name);
+            match value.as_ref() {
+                Value::Null if !name.is_empty() => {}
+                Value::Map(map) => write_items(
+                    map.iter().map(|(key, item)| (Some(key.clone()), item)),
+                    "()",
+                    indent,
+                    text,
+                )?,
+                Value::Seq(items) => {
+                    write_items(items.iter().map(|item| (None, item)), "()", indent, text)?
+                }
+                value => write_items([(None, value)], "()", indent, text)?,
+            }
+        }
+        value => text.push_str(&serde_ron::to_string(value)?),
+    }
+    Ok(())
+}
+
+/// Write the items of a sequence, map or struct on separate lines between `brackets`
+fn write_items<'a>(
+    items: impl IntoIterator<Item = (Option<String>, &'a Value)>,
+    brackets: &str,
+    indent: usize,
+    text: &mut String,
+) -> Result<(), serde_ron::Error> {
+    let mut brackets = brackets.chars();
+    text.extend(brackets.next());
+    let mut empty = true;
+    for (key, item) in items {
+        empty = false;
+        text.push('\n');
+        text.push_str(&"    ".repeat(indent + 1));
+        if let Some(key) = key {
+            text.push_str(&key);
+            text.push_str(": ");
+        }
+        write_node(item, indent + 1, text)?;
+        text.push(',');
+    }
+    if !empty {
+        text.push('\n');
+        text.push_str(&"    ".repeat(indent));
+    }
+    text.extend(brackets.next());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn named(name: &str, value: impl Into<Value>) -> Value {
+        Value::Named(name.to_owned(), Box::new(value.into()))
+    }
+
+    fn map<const N: usize>(entries: [(&str, Value); N]) -> Value {
+        Value::Map(
+            entries
+                .into_iter()
+                .map(|(key, value)| (key.to_owned(), value))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn read_value_keeps_names() {
+        let text = r#"Level(
+            kind: Sharp(edge: 1.5),
+            tags: [Dull, r#type, Blunt(2)],
+            spawn: Some(Spawn(x: 1)),
+            none: None,
+            pair: (1, 2),
+            unit: (),
+            map: {"key": Foo, Bar: 1},
+        )"#;
+        assert_eq!(
+            read_value(text.as_bytes()).unwrap(),
+            named(
+                "Level",
+                map([
+                    ("kind", named("Sharp", map([("edge", 1.5.into())]))),
+                    (
+                        "tags",
+                        Value::Seq(vec![
+                            named("Dull", Value::Null),
+                            named("type", Value::Null),
+                            named("Blunt", vec![2.into()]),
+                        ]),
+                    ),
+                    ("spawn", named("Spawn", map([("x", 1.into())]))),
+                    ("none", Value::Null),
+                    ("pair", named("", vec![1.into(), 2.into()])),
+                    ("unit", Value::Null),
+                    (
+                        "map",
+                        map([("key", named("Foo", Value::Null)), ("Bar", 1.into())])
+                    ),
+                ])
+            )
+        );
+    }
+
+    #[test]
+    fn read_value_skips_strings_comments_and_attributes() {
+        let text = r##"#![enable(implicit_some)]
+        // Comment(
+        Point(
+            /* nested /* ( */ [ */
+            label: "a(b) \"[c\"",
+            raw: r#"Raw("x")"#,
+            character: '(',
+            bytes: b"(",
+            float: -1.5e-3,
+            spawn: Spawn(x: 1),
+        )"##;
+        assert_eq!(
+            read_value(text.as_bytes()).unwrap(),
+            named(
+                "Point",
+                map([
+                    ("label", "a(b) \"[c\"".into()),
+                    ("raw", r#"Raw("x")"#.into()),
+                    ("character", "(".into()),
+                    ("bytes", Value::Bytes(b"(".to_vec())),
+                    ("float", (-1.5e-3).into()),
+                    ("spawn", named("Spawn", map([("x", 1.into())]))),
+                ])
+            )
+        );
+    }
+
+    #[test]
+    fn write_value_round_trips() {
+        let text = "Level(kind: Sharp(edge: 1.5), tags: [Dull], pair: (1, 2), map: {\"a\": 1})";
+        let value = read_value(text.as_bytes()).unwrap();
+        let written = write_value(&value).unwrap();
+        assert_eq!(read_value(written.as_bytes()).unwrap(), value);
+    }
+}
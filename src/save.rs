@@ -0,0 +1,409 @@
+use crate::data::{DataFormat, write_definition};
+#[cfg(any(feature = "ron", feature = "toml", feature = "yaml"))]
+use crate::edit::{edit_text, supports};
+use crate::{DataAsset, ToDefinition};
+use bevy_app::{App, Plugin, PostUpdate};
+use bevy_asset::io::AssetSource;
+#[cfg(any(feature = "ron", feature = "toml", feature = "yaml"))]
+use bevy_asset::io::Reader;
+use bevy_asset::io::{AssetWriterError, MissingAssetSourceError, MissingAssetWriterError};
+use bevy_asset::{
+    Asset, AssetEvent, AssetEventSystems, AssetId, AssetPath, AssetServer, Assets, UntypedAssetId,
+};
+use bevy_ecs::message::{Message, MessageReader, MessageWriter};
+use bevy_ecs::prelude::{IntoScheduleConfigs, Res, Resource};
+use bevy_ecs::system::SystemParam;
+use bevy_tasks::block_on;
+use std::any;
+use std::collections::HashMap;
+use std::future::Future;
+use std::marker::PhantomData;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+use thiserror::Error;
+#[cfg(any(feature = "ron", feature = "toml", feature = "yaml"))]
+use tracing::warn;
+
+/// How long after saving an asset the reload of its file is considered a [`SaveEcho`]
+const ECHO_WINDOW: Duration = Duration::from_secs(5);
+
+/// Plugin to write assets of type `A` back to the files they were loaded from with
+/// [`SaveAssets`].
+pub struct SaveAssetsPlugin<A> {
+    _marker: PhantomData<fn() -> A>,
+}
+
+impl<A> Default for SaveAssetsPlugin<A> {
+    fn default() -> Self {
+        Self {
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<A> Plugin for SaveAssetsPlugin<A>
+where
+    A: ToDefinition,
+{
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SavedAssets<A>>()
+            .add_message::<SaveEcho<A>>()
+            .add_systems(PostUpdate, mark_echoes::<A>.after(AssetEventSystems));
+    }
+}
+
+/// Writes modified assets back to the files they were loaded from
+///
+/// The definition of the asset is written in the format of the file extension through the
+/// [`AssetWriter`](bevy_asset::io::AssetWriter) of its asset source. The file is replaced
+/// atomically by writing a temporary file next to it and renaming it, unless the asset processor
+/// watches its source. When the asset server
+/// watches for changes, the reload caused by the new file is reported as [`SaveEcho`] next to
+/// its [`AssetEvent::Modified`].
+///
+/// Existing RON, TOML and YAML files are [edited](crate::edit::edit_document) in place, so only
+/// the changed fields are rewritten and comments and formatting are kept. Other formats are
+/// written as a whole. In both cases, includes, bases and other composed parts of the original
+/// file are replaced by their values. Requires the [`SaveAssetsPlugin`].
+///
+/// ```no_run
+/// # use bevy::prelude::*;
+/// # use bevy::tasks::IoTaskPool;
+/// # use bevy_common_assets::save::SaveAssets;
+/// # #[derive(serde::Deserialize, serde::Serialize, Asset, TypePath)]
+/// # struct Level;
+/// # #[derive(Resource)]
+/// # struct CurrentLevel(Handle<Level>);
+/// fn save_level(level: Res<CurrentLevel>, save_assets: SaveAssets<Level>) {
+///     let save = save_assets.save(&level.0);
+///     IoTaskPool::get()
+///         .spawn(async move {
+///             if let Err(error) = save.await {
+///                 error!("{error}");
+///             }
+///         })
+///         .detach();
+/// }
+/// ```
+#[derive(SystemParam)]
+pub struct SaveAssets<'w, A: ToDefinition> {
+    server: Res<'w, AssetServer>,
+    assets: Res<'w, Assets<A>>,
+    saved: Res<'w, SavedAssets<A>>,
+}
+
+impl<A: ToDefinition> SaveAssets<'_, A> {
+    /// Write the asset with `id` to the file it was loaded from
+    ///
+    /// The asset is serialized right away, so later changes are not part of the saved file.
+    /// The returned future writes the file and needs to be polled, e.g. in the `IoTaskPool`.
+    pub fn save(
+        &self,
+        id: impl Into<AssetId<A>>,
+    ) -> impl Future<Output = Result<(), SaveError>> + Send + 'static {
+        let id = id.into();
+        let serialized = self.serialize(id);
+        let server = self.server.clone();
+        let saved = self.saved.clone();
+        async move {
+            let (path, format, bytes) = serialized?;
+            let source = server.get_source(path.source())?;
+            // The reload can be reported before the write finishes, so the asset counts as saved
+            // from the start
+            let watching = server.watching_for_changes();
+            if watching {
+                saved.insert(id);
+            }
+            let written = write_file(source, &path, format, bytes).await;
+            if watching && written.is_err() {
+                saved.remove(id);
+            }
+            written
+        }
+    }
+
+    /// The path of the asset, its format and its definition in that format
+    fn serialize(
+        &self,
+        id: AssetId<A>,
+    ) -> Result<(AssetPath<'static>, DataFormat, Vec<u8>), SaveError> {
+        let untyped = id.untyped();
+        let asset = self.assets.get(id).ok_or(SaveError::NotLoaded(untyped))?;
+        let path = self
+            .server
+            .get_path(id)
+            .ok_or(SaveError::NoPath(untyped))?
+            .into_owned();
+        if path.label().is_some() {
+            return Err(SaveError::SubAsset(path.to_string()));
+        }
+        let format = self
+            .format(&path)
+            .ok_or_else(|| SaveError::UnknownFormat(path.to_string()))?;
+        let bytes = write_definition(format, &asset.to_definition()).map_err(|error| {
+            SaveError::Serialize {
+                path: path.to_string(),
+                message: error.to_string(),
+            }
+        })?;
+        Ok((path, format, bytes))
+    }
+
+    /// The format of the loader registered for `path`, or else of its extension
+    fn format(&self, path: &AssetPath) -> Option<DataFormat> {
+        // The asset was loaded from `path`, so its loader is registered and this does not block
+        block_on(self.server.get_path_asset_loader(path))
+            .ok()
+            .and_then(|loader| loader_format::<A>(loader.type_name()))
+            .or_else(|| {
+                path.get_full_extension()
+                    .and_then(|extension| DataFormat::from_extension(&extension))
+            })
+    }
+}
+
+/// The format loaded by the loader with `type_name` for assets of type `A`
+///
+/// The [`DataAssetLoader`](crate::data::DataAssetLoader) detects the format of each file and
+/// has none.
+fn loader_format<A: DataAsset>(type_name: &str) -> Option<DataFormat> {
+    let loaders = [
+        #[cfg(feature = "json")]
+        (
+            any::type_name::<crate::json::JsonAssetLoader<A>>(),
+            DataFormat::Json,
+        ),
+        #[cfg(feature = "ron")]
+        (
+            any::type_name::<crate::ron::RonAssetLoader<A>>(),
+            DataFormat::Ron,
+        ),
+        #[cfg(feature = "toml")]
+        (
+            any::type_name::<crate::toml::TomlAssetLoader<A>>(),
+            DataFormat::Toml,
+        ),
+        #[cfg(feature = "yaml")]
+        (
+            any::type_name::<crate::yaml::YamlAssetLoader<A>>(),
+            DataFormat::Yaml,
+        ),
+        #[cfg(feature = "msgpack")]
+        (
+            any::type_name::<crate::msgpack::MsgPackAssetLoader<A>>(),
+            DataFormat::MsgPack,
+        ),
+        #[cfg(feature = "cbor")]
+        (
+            any::type_name::<crate::cbor::CborAssetLoader<A>>(),
+            DataFormat::Cbor,
+        ),
+        #[cfg(feature = "xml")]
+        (
+            any::type_name::<crate::xml::XmlAssetLoader<A>>(),
+            DataFormat::Xml,
+        ),
+        #[cfg(feature = "postcard")]
+        (
+            any::type_name::<crate::postcard::PostcardAssetLoader<A>>(),
+            DataFormat::Postcard,
+        ),
+    ];
+    loaders
+        .into_iter()
+        .find(|(loader, _)| *loader == type_name)
+        .map(|(_, format)| format)
+}
+
+/// Replace the file at `path` by the document `bytes` in `format`
+///
+/// Existing files of formats that can be edited keep their formatting. The document is written
+/// to a temporary file next to `path`, which is then renamed, so the file is never left half
+/// written. Files of sources the asset processor watches are written directly instead, since
+/// the processor would process the temporary file.
+pub(crate) async fn write_file(
+    source: &AssetSource,
+    path: &AssetPath<'_>,
+    format: DataFormat,
+    bytes: Vec<u8>,
+) -> Result<(), SaveError> {
+    #[cfg(any(feature = "ron", feature = "toml", feature = "yaml"))]
+    let bytes = edit_file(source, path, format, bytes).await;
+    #[cfg(not(any(feature = "ron", feature = "toml", feature = "yaml")))]
+    let _ = format;
+    let writer = source.writer()?;
+    let write_error = |source| SaveError::Write {
+        path: path.to_string(),
+        source,
+    };
+    if source.should_process() {
+        return writer
+            .write_bytes(path.path(), &bytes)
+            .await
+            .map_err(write_error);
+    }
+    let mut temporary = path.path().as_os_str().to_owned();
+    temporary.push(".tmp");
+    let temporary = PathBuf::from(temporary);
+    writer
+        .write_bytes(&temporary, &bytes)
+        .await
+        .map_err(write_error)?;
+    writer
+        .rename(&temporary, path.path())
+        .await
+        .map_err(write_error)
+}
+
+/// The existing file at `path` edited to have the values of the `written` document
+///
+/// Falls back to `written` if the file can not be read or edited.
+#[cfg(any(feature = "ron", feature = "toml", feature = "yaml"))]
+async fn edit_file(
+    source: &AssetSource,
+    path: &AssetPath<'_>,
+    format: DataFormat,
+    written: Vec<u8>,
+) -> Vec<u8> {
+    if !supports(format) {
+        return written;
+    }
+    let Ok(mut reader) = source.reader().read(path.path()).await else {
+        return written;
+    };
+    let mut bytes = vec![];
+    if reader.read_to_end(&mut bytes).await.is_err() {
+        return written;
+    }
+    let (Ok(current), Ok(new)) = (String::from_utf8(bytes), std::str::from_utf8(&written)) else {
+        return written;
+    };
+    match edit_text(format, &current, new) {
+        Ok(edited) => edited.into_bytes(),
+        Err(error) => {
+            warn!("Rewriting `{path}` without its formatting: {error}");
+            written
+        }
+    }
+}
+
+/// The times assets were saved at
+type SaveTimes<A> = HashMap<AssetId<A>, Instant>;
+
+/// The assets of type `A` saved with [`SaveAssets`] whose files were not reloaded yet
+#[derive(Resource)]
+pub struct SavedAssets<A: Asset> {
+    saved: Arc<Mutex<SaveTimes<A>>>,
+}
+
+impl<A: Asset> Default for SavedAssets<A> {
+    fn default() -> Self {
+        Self {
+            saved: Arc::default(),
+        }
+    }
+}
+
+impl<A: Asset> Clone for SavedAssets<A> {
+    fn clone(&self) -> Self {
+        Self {
+            saved: self.saved.clone(),
+        }
+    }
+}
+
+impl<A: Asset> SavedAssets<A> {
+    /// Whether the asset with `id` was saved recently and its file was not reloaded yet
+    pub fn contains(&self, id: impl Into<AssetId<A>>) -> bool {
+        let id = id.into();
+        self.lock()
+            .get(&id)
+            .is_some_and(|time| time.elapsed() < ECHO_WINDOW)
+    }
+
+    fn insert(&self, id: AssetId<A>) {
+        self.lock().insert(id, Instant::now());
+    }
+
+    fn remove(&self, id: AssetId<A>) {
+        self.lock().remove(&id);
+    }
+
+    /// Remove the asset with `id` and return whether it was saved recently
+    fn take(&self, id: AssetId<A>) -> bool {
+        let mut saved = self.lock();
+        saved.retain(|_, time| time.elapsed() < ECHO_WINDOW);
+        saved.remove(&id).is_some()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, SaveTimes<A>> {
+        self.saved.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Sent with the [`AssetEvent::Modified`] of an asset that was reloaded because it was saved
+/// with [`SaveAssets`]
+///
+/// The reloaded asset has the values that were saved, so editors can skip these modifications
+/// instead of treating them as changes from outside, like edits of the file in another program.
+#[derive(Message)]
+pub struct SaveEcho<A: Asset> {
+    /// The id of the reloaded asset
+    pub id: AssetId<A>,
+}
+
+fn mark_echoes<A: Asset>(
+    mut events: MessageReader<AssetEvent<A>>,
+    mut echoes: MessageWriter<SaveEcho<A>>,
+    saved: Res<SavedAssets<A>>,
+) {
+    for event in events.read() {
+        match event {
+            AssetEvent::Modified { id } if saved.take(*id) => {
+                echoes.write(SaveEcho { id: *id });
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Possible errors when saving an asset with [`SaveAssets`]
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum SaveError {
+    /// The asset does not exist
+    #[error("The asset {0} is not loaded")]
+    NotLoaded(UntypedAssetId),
+    /// The asset was not loaded from a file
+    #[error("The asset {0} has no asset path")]
+    NoPath(UntypedAssetId),
+    /// The asset is part of another asset
+    #[error("The asset `{0}` is a sub-asset and can not be saved on its own")]
+    SubAsset(String),
+    /// The extension of the asset path belongs to no enabled format
+    #[error("Can not tell the format of `{0}` from its extension")]
+    UnknownFormat(String),
+    /// The definition of the asset could not be serialized
+    #[error("Could not serialize `{path}`: {message}")]
+    Serialize {
+        /// The path of the asset
+        path: String,
+        /// Description of the problem
+        message: String,
+    },
+    /// The asset source of the asset path does not exist
+    #[error(transparent)]
+    MissingSource(#[from] MissingAssetSourceError),
+    /// The asset source of the asset path can not be written to
+    #[error(transparent)]
+    MissingWriter(#[from] MissingAssetWriterError),
+    /// The file could not be written
+    #[error("Could not write `{path}`: {source}")]
+    Write {
+        /// The path of the asset
+        path: String,
+        /// The [error](AssetWriterError) writing the file
+        source: AssetWriterError,
+    },
+}
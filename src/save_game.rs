@@ -0,0 +1,759 @@
+use crate::data::{DataFormat, read_definition, write_definition};
+use bevy_app::{App, Plugin};
+use bevy_asset::io::{
+    AssetReaderError, AssetSource, AssetWriterError, MissingAssetSourceError,
+    MissingAssetWriterError, Reader,
+};
+use bevy_asset::{AssetPath, AssetServer};
+use bevy_ecs::prelude::Resource;
+use bevy_tasks::futures_lite::StreamExt;
+use flate2::Compression;
+use flate2::read::{DeflateDecoder, DeflateEncoder};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::future::Future;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+
+/// The first bytes of every save game file
+const MAGIC: &[u8; 4] = b"BSAV";
+/// The version of the layout of the header
+const HEADER_VERSION: u16 = 1;
+/// The length of the header before the checksummed body
+const HEADER_LENGTH: usize = 24;
+/// Flag for a compressed body
+const COMPRESSED: u8 = 1;
+
+/// Plugin to add the [`SaveGames`] resource, which writes save games to numbered slots
+///
+/// Every slot is a file `slot_<number>.sav` in the directory of this plugin. The file starts
+/// with a header holding the format, the version of the saved data, the time of saving and a
+/// CRC32 checksum, followed by a description and the serialized data. The data can be
+/// compressed with deflate. Saving a slot keeps its previous files as rotating backups
+/// `slot_<number>.sav.1` (the newest) to `slot_<number>.sav.<backups>`.
+///
+/// Save games usually live in a writable directory of the user, so register an
+/// [asset source](bevy_asset::io::AssetSourceBuilder) for it before adding the `AssetPlugin`,
+/// like for the [`PreferencesPlugin`](crate::preferences::PreferencesPlugin).
+///
+/// ```no_run
+/// # use bevy::prelude::*;
+/// # use bevy_common_assets::save_game::SaveGamePlugin;
+/// App::new().add_plugins((
+///     DefaultPlugins,
+///     SaveGamePlugin::new("saves://")
+///         .with_version(3)
+///         .with_compression()
+///         .with_backups(2),
+/// ));
+/// ```
+pub struct SaveGamePlugin {
+    settings: SaveGameSettings,
+}
+
+/// How the [`SaveGames`] write their files
+#[derive(Clone, Debug)]
+struct SaveGameSettings {
+    directory: AssetPath<'static>,
+    format: DataFormat,
+    version: u32,
+    compress: bool,
+    backups: u32,
+    max_data_size: usize,
+}
+
+impl SaveGamePlugin {
+    /// Create a new plugin writing save games to the directory at `directory`
+    ///
+    /// Save games are written in the first enabled format of postcard, cbor, `MessagePack`
+    /// and ron, with version 0, without compression and with one backup. Compressed save games
+    /// are read up to 256 MiB of data.
+    pub fn new(directory: impl Into<AssetPath<'static>>) -> Self {
+        Self {
+            settings: SaveGameSettings {
+                directory: directory.into(),
+                format: FORMATS[0],
+                version: 0,
+                compress: false,
+                backups: 1,
+                max_data_size: 256 * 1024 * 1024,
+            },
+        }
+    }
+
+    /// Write save games in `format`
+    ///
+    /// Binary formats like postcard are smallest; ron is readable for debugging. Loading
+    /// reads every save game in the format it was written in.
+    pub fn with_format(mut self, format: DataFormat) -> Self {
+        self.settings.format = format;
+        self
+    }
+
+    /// Write `version` into the header of save games
+    ///
+    /// Increase it when the saved type changes. Loading fails for save games of newer
+    /// versions and returns the version of older save games in their [`SaveInfo`].
+    pub fn with_version(mut self, version: u32) -> Self {
+        self.settings.version = version;
+        self
+    }
+
+    /// Compress save games with deflate
+    pub fn with_compression(mut self) -> Self {
+        self.settings.compress = true;
+        self
+    }
+
+    /// Refuse compressed save games with more than `max_data_size` bytes of data
+    ///
+    /// Decompressing damaged or crafted files could otherwise need unbounded memory.
+    pub fn with_max_data_size(mut self, max_data_size: usize) -> Self {
+        self.settings.max_data_size = max_data_size;
+        self
+    }
+
+    /// Keep the `backups` previous files of every slot
+    pub fn with_backups(mut self, backups: u32) -> Self {
+        self.settings.backups = backups;
+        self
+    }
+}
+
+impl Plugin for SaveGamePlugin {
+    fn build(&self, _app: &mut App) {}
+
+    fn finish(&self, app: &mut App) {
+        let server = app.world().resource::<AssetServer>().clone();
+        app.insert_resource(SaveGames {
+            server,
+            settings: self.settings.clone(),
+        });
+    }
+}
+
+/// The enabled formats, the most compact first
+const FORMATS: &[DataFormat] = &[
+    #[cfg(feature = "postcard")]
+    DataFormat::Postcard,
+    #[cfg(feature = "cbor")]
+    DataFormat::Cbor,
+    #[cfg(feature = "msgpack")]
+    DataFormat::MsgPack,
+    #[cfg(feature = "ron")]
+    DataFormat::Ron,
+    #[cfg(feature = "json")]
+    DataFormat::Json,
+    #[cfg(feature = "yaml")]
+    DataFormat::Yaml,
+    #[cfg(feature = "toml")]
+    DataFormat::Toml,
+    #[cfg(feature = "xml")]
+    DataFormat::Xml,
+];
+
+/// The number of a format in the header of save games
+fn format_tag(format: DataFormat) -> u8 {
+    match format {
+        #[cfg(feature = "json")]
+        DataFormat::Json => 1,
+        #[cfg(feature = "ron")]
+        DataFormat::Ron => 2,
+        #[cfg(feature = "toml")]
+        DataFormat::Toml => 3,
+        #[cfg(feature = "yaml")]
+        DataFormat::Yaml => 4,
+        #[cfg(feature = "msgpack")]
+        DataFormat::MsgPack => 5,
+        #[cfg(feature = "cbor")]
+        DataFormat::Cbor => 6,
+        #[cfg(feature = "xml")]
+        DataFormat::Xml => 7,
+        #[cfg(feature = "postcard")]
+        DataFormat::Postcard => 8,
+    }
+}
+
+/// The format with the number `tag`, if it is enabled
+fn format_from_tag(tag: u8) -> Option<DataFormat> {
+    match tag {
+        #[cfg(feature = "json")]
+        1 => Some(DataFormat::Json),
+        #[cfg(feature = "ron")]
+        2 => Some(DataFormat::Ron),
+        #[cfg(feature = "toml")]
+        3 => Some(DataFormat::Toml),
+        #[cfg(feature = "yaml")]
+        4 => Some(DataFormat::Yaml),
+        #[cfg(feature = "msgpack")]
+        5 => Some(DataFormat::MsgPack),
+        #[cfg(feature = "cbor")]
+        6 => Some(DataFormat::Cbor),
+        #[cfg(feature = "xml")]
+        7 => Some(DataFormat::Xml),
+        #[cfg(feature = "postcard")]
+        8 => Some(DataFormat::Postcard),
+        _ => None,
+    }
+}
+
+/// Writes and reads save games in numbered slots, added by the [`SaveGamePlugin`]
+///
+/// All methods return futures doing the file access, which need to be polled, e.g. in the
+/// `IoTaskPool`. Data is serialized right away, so later changes are not part of the save.
+///
+/// ```no_run
+/// # use bevy::prelude::*;
+/// # use bevy::tasks::IoTaskPool;
+/// # use bevy_common_assets::save_game::SaveGames;
+/// #[derive(serde::Serialize, Resource)]
+/// struct Progress {
+///     level: u32,
+/// }
+///
+/// fn quick_save(save_games: Res<SaveGames>, progress: Res<Progress>) {
+///     let save = save_games.save(0, format!("Level {}", progress.level), &*progress);
+///     IoTaskPool::get()
+///         .spawn(async move {
+///             if let Err(error) = save.await {
+///                 error!("{error}");
+///             }
+///         })
+///         .detach();
+/// }
+/// ```
+#[derive(Resource, Clone)]
+pub struct SaveGames {
+    server: AssetServer,
+    settings: SaveGameSettings,
+}
+
+/// The header and description of a save game
+#[derive(Clone, Debug, PartialEq)]
+pub struct SaveInfo {
+    /// The slot of the save game
+    pub slot: u32,
+    /// The version of the saved data, see [`SaveGamePlugin::with_version`]
+    pub version: u32,
+    /// The format of the saved data
+    pub format: DataFormat,
+    /// Whether the saved data is compressed
+    pub compressed: bool,
+    /// When the save game was written
+    pub saved_at: SystemTime,
+    /// The description given when saving
+    pub description: String,
+    /// The size of the file in bytes
+    pub size: usize,
+}
+
+/// A loaded save game
+#[derive(Clone, Debug)]
+pub struct SaveGame<T> {
+    /// The header and description of the save game
+    pub info: SaveInfo,
+    /// The saved data
+    pub data: T,
+}
+
+/// A slot with a save game file, see [`SaveGames::slots`]
+#[derive(Debug)]
+pub struct SaveSlot {
+    /// The number of the slot
+    pub slot: u32,
+    /// The header and description of the save game, or why they could not be read
+    pub info: Result<SaveInfo, SaveGameError>,
+}
+
+impl SaveGames {
+    /// Write `data` to `slot` with a `description`, e.g. the level and play time to show in
+    /// a menu
+    ///
+    /// The file is written next to the slot and renamed once complete. The previous file of
+    /// the slot becomes its newest backup.
+    pub fn save<T: Serialize + ?Sized>(
+        &self,
+        slot: u32,
+        description: impl Into<String>,
+        data: &T,
+    ) -> impl Future<Output = Result<SaveInfo, SaveGameError>> + Send + 'static {
+        let serialized = write_definition(self.settings.format, data)
+            .map_err(|error| SaveGameError::Serialize(error.to_string()));
+        let description = description.into();
+        let this = self.clone();
+        async move {
+            let saved_at = SystemTime::now();
+            let bytes = this.encode(&serialized?, &description, saved_at)?;
+            let source = this.source()?;
+            let writer = source.writer()?;
+            let path = this.slot_path(slot);
+            let write_error = |path: &Path| {
+                let path = path.display().to_string();
+                move |source| SaveGameError::Write { path, source }
+            };
+            let temporary = with_suffix(&path, ".tmp");
+            writer
+                .write_bytes(&temporary, &bytes)
+                .await
+                .map_err(write_error(&temporary))?;
+            if this.settings.backups > 0 {
+                for backup in (1..=this.settings.backups).rev() {
+                    let from = match backup {
+                        1 => path.clone(),
+                        backup => this.backup_path(slot, backup - 1),
+                    };
+                    let to = this.backup_path(slot, backup);
+                    match writer.rename(&from, &to).await {
+                        Ok(()) => {}
+                        Err(AssetWriterError::Io(error))
+                            if error.kind() == std::io::ErrorKind::NotFound => {}
+                        Err(error) => return Err(write_error(&to)(error)),
+                    }
+                }
+            }
+            writer
+                .rename(&temporary, &path)
+                .await
+                .map_err(write_error(&path))?;
+            Ok(SaveInfo {
+                slot,
+                version: this.settings.version,
+                format: this.settings.format,
+                compressed: this.settings.compress,
+                saved_at: UNIX_EPOCH + Duration::from_secs(unix_seconds(saved_at)),
+                description,
+                size: bytes.len(),
+            })
+        }
+    }
+
+    /// Read the save game in `slot`
+    ///
+    /// Fails if the slot is empty, if the file is no save game or was changed since it was
+    /// written, or if it is from a newer version.
+    pub fn load<T: DeserializeOwned>(
+        &self,
+        slot: u32,
+    ) -> impl Future<Output = Result<SaveGame<T>, SaveGameError>> + Send + 'static {
+        let this = self.clone();
+        async move { this.load_file(slot, this.slot_path(slot)).await }
+    }
+
+    /// Read the `backup`th backup of `slot`, where 1 is the newest
+    ///
+    /// Use this to recover a slot whose save game can not be loaded.
+    pub fn load_backup<T: DeserializeOwned>(
+        &self,
+        slot: u32,
+        backup: u32,
+    ) -> impl Future<Output = Result<SaveGame<T>, SaveGameError>> + Send + 'static {
+        let this = self.clone();
+        async move { this.load_file(slot, this.backup_path(slot, backup)).await }
+    }
+
+    /// All slots with a save game, ordered by their number
+    ///
+    /// Only the headers and descriptions are decoded, but the checksum of every file is
+    /// checked, so broken save games can be shown as such.
+    pub fn slots(
+        &self,
+    ) -> impl Future<Output = Result<Vec<SaveSlot>, SaveGameError>> + Send + 'static {
+        let this = self.clone();
+        async move {
+            let source = this.source()?;
+            let directory = this.settings.directory.path();
+            let mut paths = match source.reader().read_directory(directory).await {
+                Ok(paths) => paths,
+                Err(AssetReaderError::NotFound(_)) => return Ok(vec![]),
+                Err(source) => {
+                    return Err(SaveGameError::Read {
+                        path: directory.display().to_string(),
+                        source,
+                    });
+                }
+            };
+            let mut slots = vec![];
+            while let Some(path) = paths.next().await {
+                let Some(slot) = path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .and_then(|name| name.strip_prefix("slot_"))
+                    .and_then(|name| name.strip_suffix(".sav"))
+                    .and_then(|number| number.parse().ok())
+                else {
+                    continue;
+                };
+                let info = match this.read(source, &this.slot_path(slot)).await {
+                    Ok(bytes) => this.decode(slot, &bytes).map(|(info, _)| info),
+                    Err(error) => Err(error),
+                };
+                slots.push(SaveSlot { slot, info });
+            }
+            slots.sort_by_key(|slot| slot.slot);
+            Ok(slots)
+        }
+    }
+
+    /// Remove the save game in `slot` and its backups
+    pub fn delete(
+        &self,
+        slot: u32,
+    ) -> impl Future<Output = Result<(), SaveGameError>> + Send + 'static {
+        let this = self.clone();
+        async move {
+            let writer = this.source()?.writer()?;
+            let paths = std::iter::once(this.slot_path(slot))
+                .chain((1..=this.settings.backups).map(|backup| this.backup_path(slot, backup)));
+            for path in paths {
+                match writer.remove(&path).await {
+                    Ok(()) => {}
+                    Err(AssetWriterError::Io(error))
+                        if error.kind() == std::io::ErrorKind::NotFound => {}
+                    Err(source) => {
+                        return Err(SaveGameError::Write {
+                            path: path.display().to_string(),
+                            source,
+                        });
+                    }
+                }
+            }
+            Ok(())
+        }
+    }
+
+    fn source(&self) -> Result<&AssetSource, SaveGameError> {
+        Ok(self.server.get_source(self.settings.directory.source())?)
+    }
+
+    fn slot_path(&self, slot: u32) -> PathBuf {
+        self.settings
+            .directory
+            .path()
+            .join(format!("slot_{slot}.sav"))
+    }
+
+    fn backup_path(&self, slot: u32, backup: u32) -> PathBuf {
+        with_suffix(&self.slot_path(slot), &format!(".{backup}"))
+    }
+
+    async fn load_file<T: DeserializeOwned>(
+        &self,
+        slot: u32,
+        path: PathBuf,
+    ) -> Result<SaveGame<T>, SaveGameError> {
+        let bytes = self.read(self.source()?, &path).await?;
+        let (info, data) = self.decode(slot, &bytes)?;
+        if info.version > self.settings.version {
+            return Err(SaveGameError::NewerVersion {
+                slot,
+                version: info.version,
+                supported: self.settings.version,
+            });
+        }
+        let data =
+            read_definition(info.format, &data).map_err(|error| SaveGameError::Deserialize {
+                slot,
+                message: error.to_string(),
+            })?;
+        Ok(SaveGame { info, data })
+    }
+
+    async fn read(&self, source: &AssetSource, path: &Path) -> Result<Vec<u8>, SaveGameError> {
+        let read_error = |source| SaveGameError::Read {
+            path: path.display().to_string(),
+            source,
+        };
+        let mut reader = source.reader().read(path).await.map_err(read_error)?;
+        let mut bytes = vec![];
+        reader
+            .read_to_end(&mut bytes)
+            .await
+            .map_err(|error| read_error(error.into()))?;
+        Ok(bytes)
+    }
+
+    /// The file of a save game with the serialized `data`
+    fn encode(
+        &self,
+        data: &[u8],
+        description: &str,
+        saved_at: SystemTime,
+    ) -> Result<Vec<u8>, SaveGameError> {
+        let mut body = Vec::with_capacity(4 + description.len() + data.len());
+        body.extend_from_slice(&(description.len() as u32).to_le_bytes());
+        body.extend_from_slice(description.as_bytes());
+        if self.settings.compress {
+            DeflateEncoder::new(data, Compression::default())
+                .read_to_end(&mut body)
+                .map_err(|error| SaveGameError::Serialize(error.to_string()))?;
+        } else {
+            body.extend_from_slice(data);
+        }
+        let mut bytes = Vec::with_capacity(HEADER_LENGTH + body.len());
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&HEADER_VERSION.to_le_bytes());
+        bytes.push(format_tag(self.settings.format));
+        bytes.push(if self.settings.compress {
+            COMPRESSED
+        } else {
+            0
+        });
+        bytes.extend_from_slice(&self.settings.version.to_le_bytes());
+        bytes.extend_from_slice(&unix_seconds(saved_at).to_le_bytes());
+        bytes.extend_from_slice(&crc32fast::hash(&body).to_le_bytes());
+        bytes.extend_from_slice(&body);
+        Ok(bytes)
+    }
+
+    /// The header of the save game file `bytes` and its uncompressed data
+    fn decode(&self, slot: u32, bytes: &[u8]) -> Result<(SaveInfo, Vec<u8>), SaveGameError> {
+        let invalid = |reason| SaveGameError::Invalid { slot, reason };
+        if bytes.len() < HEADER_LENGTH || &bytes[..4] != MAGIC {
+            return Err(invalid("it is no save game"));
+        }
+        let (header, body) = bytes.split_at(HEADER_LENGTH);
+        let u32_at =
+            |index: usize| u32::from_le_bytes(header[index..index + 4].try_into().unwrap());
+        if u16::from_le_bytes([header[4], header[5]]) != HEADER_VERSION {
+            return Err(invalid("its header is from an unsupported version"));
+        }
+        let format = format_from_tag(header[6]).ok_or(invalid("its format is not enabled"))?;
+        let compressed = header[7] & COMPRESSED != 0;
+        let version = u32_at(8);
+        let saved_at = u64::from_le_bytes(header[12..20].try_into().unwrap());
+        if crc32fast::hash(body) != u32_at(20) {
+            return Err(invalid("its checksum does not match"));
+        }
+        let description_length = body
+            .get(..4)
+            .map(|length| u32::from_le_bytes(length.try_into().unwrap()) as usize)
+            .filter(|length| length.checked_add(4).is_some_and(|end| end <= body.len()))
+            .ok_or(invalid("its description is cut off"))?;
+        let description = std::str::from_utf8(&body[4..4 + description_length])
+            .map_err(|_| invalid("its description is no UTF-8"))?
+            .to_owned();
+        let data = &body[4 + description_length..];
+        let data = if compressed {
+            let max_data_size = self.settings.max_data_size;
+            let mut decompressed = vec![];
+            // Reading one byte more than allowed tells whether the data is too large
+            DeflateDecoder::new(data)
+                .take(max_data_size as u64 + 1)
+                .read_to_end(&mut decompressed)
+                .map_err(|_| invalid("its data can not be decompressed"))?;
+            if decompressed.len() > max_data_size {
+                return Err(invalid("its data is too large"));
+            }
+            decompressed
+        } else {
+            data.to_owned()
+        };
+        let info = SaveInfo {
+            slot,
+            version,
+            format,
+            compressed,
+            saved_at: UNIX_EPOCH + Duration::from_secs(saved_at),
+            description,
+            size: bytes.len(),
+        };
+        Ok((info, data))
+    }
+}
+
+/// `path` with `suffix` appended to its file name
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(suffix);
+    PathBuf::from(path)
+}
+
+fn unix_seconds(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+/// Possible errors when writing or reading save games with [`SaveGames`]
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum SaveGameError {
+    /// The data could not be serialized
+    #[error("Could not serialize the save game: {0}")]
+    Serialize(String),
+    /// The save game could not be deserialized into the requested type
+    #[error("Could not deserialize the save game in slot {slot}: {message}")]
+    Deserialize {
+        /// The slot of the save game
+        slot: u32,
+        /// Description of the problem
+        message: String,
+    },
+    /// The file is no valid save game
+    #[error("The save game in slot {slot} can not be loaded, {reason}")]
+    Invalid {
+        /// The slot of the save game
+        slot: u32,
+        /// Why the file is invalid
+        reason: &'static str,
+    },
+    /// The save game is from a newer version of the game
+    #[error(
+        "The save game in slot {slot} has version {version}, but only {supported} is supported"
+    )]
+    NewerVersion {
+        /// The slot of the save game
+        slot: u32,
+        /// The version of the save game
+        version: u32,
+        /// The version of the [`SaveGamePlugin`]
+        supported: u32,
+    },
+    /// A file could not be read, e.g. because the slot is empty
+    #[error("Could not read `{path}`: {source}")]
+    Read {
+        /// The path of the file
+        path: String,
+        /// The [error](AssetReaderError) reading the file
+        source: AssetReaderError,
+    },
+    /// A file could not be written
+    #[error("Could not write `{path}`: {source}")]
+    Write {
+        /// The path of the file
+        path: String,
+        /// The [error](AssetWriterError) writing the file
+        source: AssetWriterError,
+    },
+    /// The asset source of the save game directory does not exist
+    #[error(transparent)]
+    MissingSource(#[from] MissingAssetSourceError),
+    /// The asset source of the save game directory can not be written to
+    #[error(transparent)]
+    MissingWriter(#[from] MissingAssetWriterError),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy_asset::io::AssetSourceBuilders;
+    use bevy_asset::{AssetServerMode, UnapprovedPathMode};
+
+    fn save_games(plugin: SaveGamePlugin) -> SaveGames {
+        let mut sources = AssetSourceBuilders::default();
+        sources.init_default_source("assets", None);
+        let server = AssetServer::new(
+            sources.build_sources(false, false),
+            AssetServerMode::Unprocessed,
+            false,
+            UnapprovedPathMode::Forbid,
+        );
+        SaveGames {
+            server,
+            settings: plugin.settings,
+        }
+    }
+
+    fn saved_at() -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(1_700_000_000)
+    }
+
+    #[test]
+    fn header_round_trips() {
+        for compress in [false, true] {
+            let mut plugin = SaveGamePlugin::new("saves://").with_version(3);
+            plugin.settings.compress = compress;
+            let save_games = save_games(plugin);
+            let data = b"data data data data".repeat(4);
+            let bytes = save_games.encode(&data, "Level 2", saved_at()).unwrap();
+            assert_eq!(&bytes[..4], MAGIC);
+            let (info, decoded) = save_games.decode(1, &bytes).unwrap();
+            assert_eq!(decoded, data);
+            assert_eq!(
+                info,
+                SaveInfo {
+                    slot: 1,
+                    version: 3,
+                    format: FORMATS[0],
+                    compressed: compress,
+                    saved_at: saved_at(),
+                    description: "Level 2".to_owned(),
+                    size: bytes.len(),
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn every_enabled_format_has_a_tag() {
+        for &format in FORMATS {
+            assert_eq!(format_from_tag(format_tag(format)), Some(format));
+        }
+        assert_eq!(format_from_tag(0), None);
+    }
+
+    #[test]
+    fn damaged_files_are_invalid() {
+        let save_games = save_games(SaveGamePlugin::new("saves://"));
+        let bytes = save_games.encode(b"data", "Level 2", saved_at()).unwrap();
+        let reason = |bytes: &[u8]| match save_games.decode(0, bytes) {
+            Err(SaveGameError::Invalid { reason, .. }) => reason,
+            result => panic!("expected an invalid save game, got {result:?}"),
+        };
+        assert_eq!(reason(b"BSAV"), "it is no save game");
+        assert_eq!(
+            reason(&[b"XSAV", &bytes[4..]].concat()),
+            "it is no save game"
+        );
+        let mut changed = bytes.clone();
+        changed[4] = 2;
+        assert_eq!(
+            reason(&changed),
+            "its header is from an unsupported version"
+        );
+        let mut changed = bytes.clone();
+        changed[6] = 0;
+        assert_eq!(reason(&changed), "its format is not enabled");
+        let mut changed = bytes.clone();
+        *changed.last_mut().unwrap() ^= 1;
+        assert_eq!(reason(&changed), "its checksum does not match");
+        assert_eq!(
+            reason(&bytes[..bytes.len() - 1]),
+            "its checksum does not match"
+        );
+        let body = 100u32.to_le_bytes();
+        let mut changed = bytes[..HEADER_LENGTH].to_vec();
+        changed[20..24].copy_from_slice(&crc32fast::hash(&body).to_le_bytes());
+        changed.extend_from_slice(&body);
+        assert_eq!(reason(&changed), "its description is cut off");
+        let body = u32::MAX.to_le_bytes();
+        let mut changed = bytes[..HEADER_LENGTH].to_vec();
+        changed[20..24].copy_from_slice(&crc32fast::hash(&body).to_le_bytes());
+        changed.extend_from_slice(&body);
+        assert_eq!(reason(&changed), "its description is cut off");
+    }
+
+    #[test]
+    fn decompressed_data_is_limited() {
+        let data = [7; 100];
+        let plugin = |max_data_size| {
+            SaveGamePlugin::new("saves://")
+                .with_compression()
+                .with_max_data_size(max_data_size)
+        };
+        let bytes = save_games(plugin(100))
+            .encode(&data, "Level 2", saved_at())
+            .unwrap();
+        assert!(bytes.len() < HEADER_LENGTH + data.len());
+        assert_eq!(save_games(plugin(100)).decode(0, &bytes).unwrap().1, data);
+        assert!(matches!(
+            save_games(plugin(99)).decode(0, &bytes),
+            Err(SaveGameError::Invalid {
+                reason: "its data is too large",
+                ..
+            })
+        ));
+    }
+}
@@ -0,0 +1,349 @@
+use crate::data::{DataFormat, detect_format, read_document, write_document};
+use crate::value::Value;
+use bevy_app::{App, Plugin};
+use bevy_asset::io::{Reader, Writer};
+use bevy_asset::saver::{AssetSaver, SavedAsset};
+use bevy_asset::{AssetApp, AssetLoader, AsyncWriteExt, LoadContext};
+use bevy_ecs::reflect::AppTypeRegistry;
+use bevy_reflect::{TypeRegistry, TypeRegistryArc};
+use bevy_scene::DynamicScene;
+use bevy_scene::serde::{SceneDeserializer, SceneSerializer};
+use serde::de::DeserializeSeed;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Plugin to load [`DynamicScene`]s from files of the data formats
+///
+/// Bevy only loads scenes from RON files. This plugin deserializes the reflected components
+/// and resources of scenes from json, ron, toml, yaml, `MessagePack` and cbor files, using the
+/// types registered in the [`AppTypeRegistry`]. The format of a file is taken from the last
+/// part of its extension, like `json` in `level.scn.json`, or detected from its content.
+/// Spawning the loaded scenes needs Bevy's `ScenePlugin`.
+///
+/// Extensions must not overlap with the `scn` and `scn.ron` extensions of Bevy's scene loader.
+///
+/// ```no_run
+/// # use bevy::prelude::*;
+/// # use bevy_common_assets::scene::DataScenePlugin;
+/// App::new()
+///     .add_plugins((
+///         DefaultPlugins,
+///         DataScenePlugin::new(&["scn.json", "scn.yaml"]),
+///     ))
+///     .add_systems(Startup, spawn_level);
+///
+/// fn spawn_level(mut commands: Commands, asset_server: Res<AssetServer>) {
+///     commands.spawn(DynamicSceneRoot(asset_server.load("level.scn.json")));
+/// }
+/// ```
+pub struct DataScenePlugin {
+    extensions: Vec<&'static str>,
+}
+
+impl DataScenePlugin {
+    /// Create a new plugin that will load scenes from files with the given extensions.
+    pub fn new(extensions: &[&'static str]) -> Self {
+        Self {
+            extensions: extensions.to_owned(),
+        }
+    }
+}
+
+impl Plugin for DataScenePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<DynamicScene>();
+        let type_registry = app.world().resource::<AppTypeRegistry>().0.clone();
+        app.register_asset_loader(DataSceneLoader {
+            extensions: self.extensions.clone(),
+            type_registry,
+        });
+    }
+}
+
+/// Settings of the [`DataSceneLoader`]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct DataSceneSettings {
+    /// Load the file in this format instead of the format of its extension
+    pub format: Option<DataFormat>,
+}
+
+/// Loads [`DynamicScene`]s from files of the data formats
+pub struct DataSceneLoader {
+    extensions: Vec<&'static str>,
+    type_registry: TypeRegistryArc,
+}
+
+/// Possible errors that can be produced by [`DataSceneLoader`] and [`DataSceneSaver`]
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum DataSceneError {
+    /// An [IO Error](std::io::Error)
+    #[error("Could not read the scene file: {0}")]
+    Io(#[from] std::io::Error),
+    /// Neither the extension nor the content of the file tell its format
+    #[error("Could not detect the format of the scene file")]
+    UnknownFormat,
+    /// The document could not be parsed
+    #[error("Could not parse the scene as {format:?}: {message}")]
+    Parse {
+        /// The format of the document
+        format: DataFormat,
+        /// Description of the problem
+        message: String,
+    },
+    /// The document is no scene of the registered types
+    #[error("Could not deserialize the scene: {0}")]
+    Deserialize(String),
+    /// The scene could not be serialized, e.g. because a component is not registered
+    #[error("Could not serialize the scene: {0}")]
+    Serialize(String),
+    /// The scene can not be written in the format, like `null` values in toml
+    #[error("Could not write the scene as {format:?}: {message}")]
+    Write {
+        /// The format of the document
+        format: DataFormat,
+        /// Description of the problem
+        message: String,
+    },
+}
+
+impl AssetLoader for DataSceneLoader {
+    type Asset = DynamicScene;
+    type Settings = DataSceneSettings;
+    type Error = DataSceneError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        settings: &DataSceneSettings,
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let format = settings
+            .format
+            .or_else(|| {
+                load_context
+                    .asset_path()
+                    .get_full_extension()
+                    .and_then(|extension| DataFormat::from_extension(&extension))
+            })
+            .or_else(|| detect_format(&bytes))
+            .ok_or(DataSceneError::UnknownFormat)?;
+        read_scene(format, &bytes, &self.type_registry.read())
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &self.extensions
+    }
+}
+
+/// Saves [`DynamicScene`]s in one of the data formats
+///
+/// The saved scenes are loaded by the [`DataSceneLoader`]. Use it in an asset processor to
+/// convert scenes between formats, e.g. to `MessagePack` for shipping.
+pub struct DataSceneSaver {
+    format: DataFormat,
+    type_registry: TypeRegistryArc,
+}
+
+impl DataSceneSaver {
+    /// Create a saver writing scenes in `format` with the types of `type_registry`
+    pub fn new(format: DataFormat, type_registry: &AppTypeRegistry) -> Self {
+        Self {
+            format,
+            type_registry: type_registry.0.clone(),
+        }
+    }
+}
+
+impl AssetSaver for DataSceneSaver {
+    type Asset = DynamicScene;
+    type Settings = ();
+    type OutputLoader = DataSceneLoader;
+    type Error = DataSceneError;
+
+    async fn save(
+        &self,
+        writer: &mut Writer,
+        asset: SavedAsset<'_, Self::Asset>,
+        _settings: &Self::Settings,
+    ) -> Result<DataSceneSettings, Self::Error> {
+        let bytes = write_scene(self.format, asset.get(), &self.type_registry.read())?;
+        writer.write_all(&bytes).await?;
+        Ok(DataSceneSettings {
+            format: Some(self.format),
+        })
+    }
+}
+
+/// Deserialize a [`DynamicScene`] from a document in `format`
+///
+/// The document is read into a [`Value`] tree first, so scenes look the same in every format.
+/// Entities are map keys, which text formats store as strings.
+pub fn read_scene(
+    format: DataFormat,
+    bytes: &[u8],
+    type_registry: &TypeRegistry,
+) -> Result<DynamicScene, DataSceneError> {
+    let document = read_document(format, bytes).map_err(|error| DataSceneError::Parse {
+        format,
+        message: error.to_string(),
+    })?;
+    SceneDeserializer { type_registry }
+        .deserialize(document)
+        .map_err(|error| DataSceneError::Deserialize(error.to_string()))
+}
+
+/// Serialize a [`DynamicScene`] as document in `format`
+///
+/// Use this to save scenes at runtime, e.g. a scene created with
+/// [`DynamicSceneBuilder`](bevy_scene::DynamicSceneBuilder) from the entities of a level.
+/// Toml has no `null`, so scenes with unit components like `TransformTreeChanged` or `None`
+/// values can not be written as toml.
+pub fn write_scene(
+    format: DataFormat,
+    scene: &DynamicScene,
+    type_registry: &TypeRegistry,
+) -> Result<Vec<u8>, DataSceneError> {
+    let document = Value::from_serialize(&SceneSerializer::new(scene, type_registry))
+        .map_err(|error| DataSceneError::Serialize(error.to_string()))?;
+    write_document(format, &document).map_err(|error| DataSceneError::Write {
+        format,
+        message: error.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::prelude::{Component, ReflectComponent, ReflectResource, Resource, World};
+    use bevy_reflect::Reflect;
+    use bevy_scene::DynamicSceneBuilder;
+
+    #[derive(Component, Reflect, Default)]
+    #[reflect(Component)]
+    struct Health {
+        points: u32,
+        name: String,
+    }
+
+    #[derive(Component, Reflect, Default)]
+    #[reflect(Component)]
+    struct Speed(f32);
+
+    #[derive(Resource, Reflect, Default)]
+    #[reflect(Resource)]
+    struct Score(u64);
+
+    fn registry() -> AppTypeRegistry {
+        let registry = AppTypeRegistry::default();
+        {
+            let mut registry = registry.write();
+            registry.register::<Health>();
+            registry.register::<Speed>();
+            registry.register::<Score>();
+        }
+        registry
+    }
+
+    fn scene(registry: &AppTypeRegistry) -> DynamicScene {
+        let mut world = World::new();
+        world.insert_resource(registry.clone());
+        world.insert_resource(Score(42));
+        let knight = world
+            .spawn(Health {
+                points: 3,
+                name: "knight".to_owned(),
+            })
+            .id();
+        let rat = world.spawn((
+            Health {
+                points: 1,
+                name: "rat".to_owned(),
+            },
+            Speed(2.5),
+        ));
+        let rat = rat.id();
+        DynamicSceneBuilder::from_world(&world)
+            .extract_entities([knight, rat].into_iter())
+            .extract_resources()
+            .build()
+    }
+
+    #[test]
+    fn scenes_round_trip_in_all_formats() {
+        let registry = registry();
+        let scene = scene(&registry);
+        let registry = registry.read();
+        let formats = [
+            #[cfg(feature = "json")]
+            DataFormat::Json,
+            #[cfg(feature = "ron")]
+            DataFormat::Ron,
+            #[cfg(feature = "toml")]
+            DataFormat::Toml,
+            #[cfg(feature = "yaml")]
+            DataFormat::Yaml,
+            #[cfg(feature = "msgpack")]
+            DataFormat::MsgPack,
+            #[cfg(feature = "cbor")]
+            DataFormat::Cbor,
+        ];
+        // Scenes are compared in one format, the type registry does not compare reflected values
+        let expected = write_scene(formats[0], &scene, &registry).unwrap();
+        for format in formats {
+            let bytes = write_scene(format, &scene, &registry).unwrap();
+            let read = read_scene(format, &bytes, &registry).unwrap();
+            assert_eq!(read.entities.len(), 2, "{format:?}");
+            assert_eq!(read.resources.len(), 1, "{format:?}");
+            assert_eq!(
+                write_scene(formats[0], &read, &registry).unwrap(),
+                expected,
+                "{format:?}"
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn entities_are_string_keys_in_text_formats() {
+        let document = r#"{
+            "resources": {
+                "bevy_common_assets::scene::tests::Score": 7
+            },
+            "entities": {
+                "4294967290": {
+                    "components": {
+                        "bevy_common_assets::scene::tests::Health": {"points": 2, "name": "bat"},
+                        "bevy_common_assets::scene::tests::Speed": 4.0
+                    }
+                }
+            }
+        }"#;
+        let registry = registry();
+        let scene = read_scene(DataFormat::Json, document.as_bytes(), &registry.read()).unwrap();
+        assert_eq!(scene.resources.len(), 1);
+        assert_eq!(scene.entities.len(), 1);
+        assert_eq!(scene.entities[0].entity.to_bits(), 4294967290);
+        assert_eq!(scene.entities[0].components.len(), 2);
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn unregistered_types_are_errors() {
+        let registry = registry();
+        let read = |document: &str| {
+            read_scene(DataFormat::Json, document.as_bytes(), &registry.read())
+                .err()
+                .expect("the scene is invalid")
+        };
+        let error = read(r#"{"resources": {"nope::Missing": []}, "entities": {}}"#);
+        assert!(
+            matches!(&error, DataSceneError::Deserialize(message) if message.contains("nope::Missing")),
+            "{error}"
+        );
+        let error = read("{");
+        assert!(matches!(error, DataSceneError::Parse { .. }), "{error}");
+    }
+}
@@ -0,0 +1,213 @@
+use crate::DataAsset;
+use bevy_app::App;
+use bevy_ecs::resource::Resource;
+use schemars::generate::SchemaSettings;
+use schemars::{JsonSchema, Schema};
+use serde_json::{Map, Value};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// [JSON Schemas](https://json-schema.org/) of the asset types registered with `with_schema` on
+/// the json, yaml and toml plugins
+///
+/// [`AssetSchemas::write`] writes a schema file per asset type and VS Code settings mapping the
+/// registered extensions to the schemas, giving autocompletion and validation while editing
+/// asset files.
+///
+/// ```no_run
+/// # use bevy::prelude::*;
+/// # use bevy_common_assets::json::JsonAssetPlugin;
+/// # use bevy_common_assets::schema::AssetSchemas;
+/// # #[derive(serde::Deserialize, schemars::JsonSchema, Asset, TypePath)]
+/// # struct Level;
+/// App::new()
+///     .add_plugins(JsonAssetPlugin::<Level>::new(&["level.json"]).with_schema())
+///     .add_systems(Startup, |schemas: Res<AssetSchemas>| {
+///         schemas.write("schemas").expect("Failed to write schemas");
+///     });
+/// ```
+#[derive(Resource, Default)]
+pub struct AssetSchemas {
+    schemas: Vec<AssetSchema>,
+}
+
+/// The schema of an asset type and the files it applies to
+struct AssetSchema {
+    type_path: &'static str,
+    format: SchemaFormat,
+    extensions: Vec<&'static str>,
+    generate: fn() -> Schema,
+}
+
+/// The formats that editors can validate with JSON Schemas
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SchemaFormat {
+    #[cfg(feature = "json")]
+    Json,
+    #[cfg(feature = "yaml")]
+    Yaml,
+    #[cfg(feature = "toml")]
+    Toml,
+}
+
+/// Registers the schema of an asset type, stored by plugins with a schema
+pub(crate) type RegisterSchema = fn(&mut App, SchemaFormat, &[&'static str]);
+
+/// Register the schema of the definition of `A` for files with the given extensions
+pub(crate) fn register_schema<A>(app: &mut App, format: SchemaFormat, extensions: &[&'static str])
+where
+    A: DataAsset,
+    A::Definition: JsonSchema,
+{
+    app.world_mut()
+        .get_resource_or_init::<AssetSchemas>()
+        .schemas
+        .push(AssetSchema {
+            type_path: A::type_path(),
+            format,
+            extensions: extensions.to_owned(),
+            generate: || {
+                SchemaSettings::draft07()
+                    .into_generator()
+                    .into_root_schema_for::<A::Definition>()
+            },
+        });
+}
+
+impl AssetSchemas {
+    /// The schema of the asset type with the given type path
+    pub fn schema(&self, type_path: &str) -> Option<Schema> {
+        self.schemas
+            .iter()
+            .find(|schema| schema.type_path == type_path)
+            .map(|schema| (schema.generate)())
+    }
+
+    /// Write the schemas and VS Code settings mapping files to them into `directory`.
+    ///
+    /// Every asset type gets a `<type path>.schema.json` file. The `settings.json` contains
+    /// the `json.schemas`, `yaml.schemas` (for the YAML extension by Red Hat) and
+    /// `evenBetterToml.schema.associations` (for the Even Better TOML extension) settings
+    /// for all registered extensions. Copy them into the `.vscode/settings.json` of your
+    /// workspace. The schemas are referenced relative to the workspace, so `directory` should
+    /// be relative to the workspace root as well.
+    pub fn write(&self, directory: impl AsRef<Path>) -> io::Result<()> {
+        let directory = directory.as_ref();
+        fs::create_dir_all(directory)?;
+        let mut written = vec![];
+        for schema in &self.schemas {
+            if !written.contains(&schema.type_path) {
+                let contents = serde_json::to_string_pretty(&(schema.generate)())?;
+                fs::write(directory.join(schema_file_name(schema.type_path)), contents)?;
+                written.push(schema.type_path);
+            }
+        }
+        let mut settings = Map::new();
+        #[cfg(feature = "json")]
+        {
+            let json_schemas: Vec<Value> = self
+                .of_format(SchemaFormat::Json, directory)
+                .map(|(schema, url)| {
+                    serde_json::json!({
+                        "fileMatch": file_patterns(schema),
+                        "url": url,
+                    })
+                })
+                .collect();
+            if !json_schemas.is_empty() {
+                settings.insert("json.schemas".to_owned(), Value::Array(json_schemas));
+            }
+        }
+        #[cfg(feature = "yaml")]
+        {
+            let mut yaml_schemas = Map::new();
+            for (schema, url) in self.of_format(SchemaFormat::Yaml, directory) {
+                let patterns = yaml_schemas
+                    .entry(url)
+                    .or_insert_with(|| serde_json::json!([]));
+                if let Value::Array(patterns) = patterns {
+                    patterns.extend(file_patterns(schema).into_iter().map(Value::from));
+                }
+            }
+            if !yaml_schemas.is_empty() {
+                settings.insert("yaml.schemas".to_owned(), Value::Object(yaml_schemas));
+            }
+        }
+        #[cfg(feature = "toml")]
+        {
+            let mut toml_schemas = Map::new();
+            for (schema, url) in self.of_format(SchemaFormat::Toml, directory) {
+                for extension in &schema.extensions {
+                    toml_schemas.insert(
+                        format!(".*\\.{}$", extension.replace('.', "\\.")),
+                        Value::from(url.clone()),
+                    );
+                }
+            }
+            if !toml_schemas.is_empty() {
+                settings.insert(
+                    "evenBetterToml.schema.associations".to_owned(),
+                    Value::Object(toml_schemas),
+                );
+            }
+        }
+        fs::write(
+            directory.join("settings.json"),
+            serde_json::to_string_pretty(&settings)?,
+        )
+    }
+
+    /// The schemas registered for `format` with the urls of their files in `directory`
+    fn of_format<'a>(
+        &'a self,
+        format: SchemaFormat,
+        directory: &'a Path,
+    ) -> impl Iterator<Item = (&'a AssetSchema, String)> {
+        self.schemas
+            .iter()
+            .filter(move |schema| schema.format == format)
+            .map(|schema| {
+                let url = schema_url(&directory.join(schema_file_name(schema.type_path)));
+                (schema, url)
+            })
+    }
+}
+
+/// Glob patterns matching the files with the extensions of a schema
+#[cfg(any(feature = "json", feature = "yaml"))]
+fn file_patterns(schema: &AssetSchema) -> Vec<String> {
+    schema
+        .extensions
+        .iter()
+        .map(|extension| format!("*.{extension}"))
+        .collect()
+}
+
+/// The file name of the schema for a type, like `my_game.level.Level.schema.json`
+fn schema_file_name(type_path: &str) -> String {
+    let name: String = type_path
+        .replace("::", ".")
+        .chars()
+        .map(|character| {
+            if character.is_ascii_alphanumeric() || "._-".contains(character) {
+                character
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("{name}.schema.json")
+}
+
+/// Reference a schema file relative to the workspace, or by file URL for absolute paths
+fn schema_url(path: &Path) -> String {
+    let path = path.to_string_lossy().replace('\\', "/");
+    if path.starts_with('/') {
+        format!("file://{path}")
+    } else if path.starts_with("./") {
+        path
+    } else {
+        format!("./{path}")
+    }
+}
@@ -0,0 +1,97 @@
+use bevy_asset::io::Reader;
+use bevy_asset::{Asset, AsyncReadExt};
+use bevy_reflect::TypePath;
+use std::io::ErrorKind;
+
+/// Asset representing a loaded stream of records deserialized to type `A`
+///
+/// The stream savers write all records of the asset. Their `append_record` writes a single
+/// record, so records can be written one by one while they come in, e.g. to log telemetry while
+/// the game runs. The writers of asset sources replace existing files, so appending to a file
+/// needs a writer that was opened for appending.
+#[derive(TypePath, Asset)]
+pub struct LoadedStream<A>
+where
+    A: TypePath + Send + Sync + 'static,
+{
+    /// The records of the stream in the order they were read
+    pub records: Vec<A>,
+}
+
+/// Outcome of decoding the record at the start of a buffer
+pub(crate) enum Decoded<A> {
+    /// A complete record and the number of bytes it occupied
+    Record(A, usize),
+    /// Bytes between records that do not contain any data, like empty lines
+    #[cfg(any(feature = "json", feature = "postcard"))]
+    Skip(usize),
+    /// The buffer ends before the record does
+    Incomplete,
+}
+
+const CHUNK_SIZE: usize = 8 * 1024;
+
+/// Reads records from `reader` chunk by chunk, decoding every record as soon as it was fully read.
+///
+/// `decode` gets the buffered bytes starting at the next record, how many of them it already
+/// scanned without finding the end of the record and whether the end of the file was reached.
+/// Formats with a record terminator only need to search the bytes after the scanned ones. After
+/// an incomplete record, at least as many bytes as are buffered are read before decoding again,
+/// so records spanning many chunks are decoded a logarithmic number of times.
+pub(crate) async fn read_records<A, E>(
+    reader: &mut dyn Reader,
+    mut decode: impl FnMut(&mut [u8], usize, bool) -> Result<Decoded<A>, E>,
+) -> Result<Vec<A>, E>
+where
+    E: From<std::io::Error>,
+{
+    let mut records = vec![];
+    let mut buffer = Vec::new();
+    let mut chunk = vec![0; CHUNK_SIZE];
+    let mut start = 0;
+    let mut scanned = 0;
+    let mut end_of_file = false;
+    loop {
+        if start < buffer.len() {
+            match decode(&mut buffer[start..], scanned, end_of_file)? {
+                Decoded::Record(record, length) => {
+                    records.push(record);
+                    start += length;
+                    scanned = 0;
+                    continue;
+                }
+                #[cfg(any(feature = "json", feature = "postcard"))]
+                Decoded::Skip(length) => {
+                    start += length;
+                    scanned = 0;
+                    continue;
+                }
+                Decoded::Incomplete if end_of_file => {
+                    return Err(std::io::Error::new(
+                        ErrorKind::UnexpectedEof,
+                        "The file ended in the middle of a record",
+                    )
+                    .into());
+                }
+                Decoded::Incomplete => {}
+            }
+        } else if end_of_file {
+            return Ok(records);
+        }
+        buffer.drain(..start);
+        start = 0;
+        scanned = buffer.len();
+        let wanted = 2 * buffer.len();
+        loop {
+            let read = reader.read(&mut chunk).await?;
+            if read == 0 {
+                end_of_file = true;
+                break;
+            }
+            buffer.extend_from_slice(&chunk[..read]);
+            if buffer.len() >= wanted {
+                break;
+            }
+        }
+    }
+}
@@ -0,0 +1,421 @@
+use crate::data::{DataFormat, read_document};
+use crate::value::Value;
+use bevy_asset::{AssetPath, LoadContext, ReadAssetBytesError};
+use std::collections::BTreeMap;
+use thiserror::Error;
+
+/// Variables replacing placeholders in documents of the json, ron, toml and yaml plugins
+///
+/// With `with_substitution` on their plugins, placeholders like `${BALANCE_MULT}` or
+/// `${vars.tile_size}` in string values are replaced by the values of variables before the
+/// document is deserialized. A placeholder that is the whole string is replaced by the value
+/// itself, so `"${vars.tile_size}"` becomes a number if the variable is one. Placeholders in
+/// longer strings are replaced by the text of strings, numbers and bools. `$${` is written as
+/// `${` without substituting anything.
+///
+/// Variables are looked up in this order:
+/// - the [`variables`](crate::DocumentSettings::variables) in the loader settings of the document
+/// - the variable files, later files first; `${vars.tile_size}` is the `tile_size` field of the
+///   file added with the namespace `vars`, and dots select nested fields
+/// - the environment variables of the process, if [enabled](Self::with_environment); values
+///   like `true` or `1.5` replacing a whole string are read as bool or number, while placeholders
+///   in longer strings keep the text of the variable
+///
+/// Variable files are read in the format of their extension as loader dependencies, so editing
+/// them reloads the assets using them. A document with placeholders that can not be resolved
+/// fails to load with the names of all missing variables and of all variables that can only
+/// be used as whole string.
+///
+/// ```no_run
+/// # use bevy::prelude::*;
+/// # use bevy_common_assets::json::JsonAssetPlugin;
+/// # use bevy_common_assets::substitution::Substitution;
+/// # #[derive(serde::Deserialize, Asset, TypePath)]
+/// # struct Level;
+/// App::new().add_plugins((
+///     DefaultPlugins,
+///     JsonAssetPlugin::<Level>::new(&["level.json"]).with_substitution(
+///         Substitution::default()
+///             .with_file("vars", "config/vars.toml")
+///             .with_environment(),
+///     ),
+/// ));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Substitution {
+    files: Vec<(String, AssetPath<'static>)>,
+    environment: bool,
+}
+
+impl Substitution {
+    /// Provide the fields of the document at `path` as variables in `namespace`
+    ///
+    /// With an empty namespace, the fields are variables without prefix.
+    pub fn with_file(
+        mut self,
+        namespace: impl Into<String>,
+        path: impl Into<AssetPath<'static>>,
+    ) -> Self {
+        self.files.push((namespace.into(), path.into()));
+        self
+    }
+
+    /// Resolve placeholders that are no other variable from the environment of the process
+    pub fn with_environment(mut self) -> Self {
+        self.environment = true;
+        self
+    }
+}
+
+/// Possible errors when substituting the placeholders of a document
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum SubstitutionError {
+    /// A variable file could not be read
+    #[error("Could not read the variables `{file}`: {source}")]
+    Read {
+        /// The path of the variable file
+        file: String,
+        /// The [error](ReadAssetBytesError) reading the file
+        source: Box<ReadAssetBytesError>,
+    },
+    /// A variable file could not be parsed
+    #[error("Could not parse the variables `{file}`: {message}")]
+    Parse {
+        /// The path of the variable file
+        file: String,
+        /// Description of the problem
+        message: String,
+    },
+    /// Placeholders name variables that do not exist or can not be used where they are
+    #[error("Unresolved variables in `{path}`: {}", describe(.names, .not_scalar))]
+    Unresolved {
+        /// The path of the document
+        path: String,
+        /// The names of all missing variables
+        names: Vec<String>,
+        /// The names of all variables that are no string, number or bool, but are part of a
+        /// longer string
+        not_scalar: Vec<String>,
+    },
+}
+
+/// List the missing variables and the variables that can only be used as whole string
+fn describe(names: &[String], not_scalar: &[String]) -> String {
+    let mut problems = names.to_vec();
+    problems.extend(
+        not_scalar
+            .iter()
+            .map(|name| format!("{name} (can only be used as the whole string)")),
+    );
+    problems.join(", ")
+}
+
+/// Replace all placeholders in `document` by the values of their variables
+///
+/// Variable files without a known extension are read in `format`.
+pub(crate) async fn substitute(
+    document: &mut Value,
+    format: DataFormat,
+    substitution: &Substitution,
+    settings: &BTreeMap<String, Value>,
+    load_context: &mut LoadContext<'_>,
+) -> Result<(), SubstitutionError> {
+    let mut files = vec![];
+    for (namespace, path) in &substitution.files {
+        let bytes = load_context
+            .read_asset_bytes(path)
+            .await
+            .map_err(|source| SubstitutionError::Read {
+                file: path.to_string(),
+                source: Box::new(source),
+            })?;
+        let file_format = path
+            .get_full_extension()
+            .and_then(|extension| DataFormat::from_extension(&extension))
+            .unwrap_or(format);
+        let variables =
+            read_document(file_format, &bytes).map_err(|error| SubstitutionError::Parse {
+                file: path.to_string(),
+                message: error.to_string(),
+            })?;
+        files.push((namespace.as_str(), variables));
+    }
+    let mut variables = Variables {
+        path: load_context.asset_path().to_string(),
+        settings,
+        files,
+        environment: substitution.environment,
+        missing: vec![],
+        not_scalar: vec![],
+    };
+    variables.replace(document);
+    if !variables.missing.is_empty() || !variables.not_scalar.is_empty() {
+        return Err(SubstitutionError::Unresolved {
+            path: variables.path,
+            names: variables.missing,
+            not_scalar: variables.not_scalar,
+        });
+    }
+    Ok(())
+}
+
+/// The sources of variables for one document and the names not found in them
+struct Variables<'a> {
+    path: String,
+    settings: &'a BTreeMap<String, Value>,
+    files: Vec<(&'a str, Value)>,
+    environment: bool,
+    missing: Vec<String>,
+    not_scalar: Vec<String>,
+}
+
+impl Variables<'_> {
+    /// Replace the placeholders in all strings of `value`, remembering the problems
+    fn replace(&mut self, value: &mut Value) {
+        let value = value.unnamed_mut();
+        match value {
+            Value::String(text) => {
+                if let Some(replaced) = self.expand(text) {
+                    *value = replaced;
+                }
+            }
+            Value::Map(map) => {
+                for child in map.values_mut() {
+                    self.replace(child);
+                }
+            }
+            Value::Seq(seq) => {
+                for child in seq {
+                    self.replace(child);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// The value replacing `text`, if it contains placeholders
+    fn expand(&mut self, text: &str) -> Option<Value> {
+        if !text.contains("${") {
+            return None;
+        }
+        if let Some(name) = text
+            .strip_prefix("${")
+            .and_then(|rest| rest.strip_suffix('}'))
+            .filter(|name| !name.contains('}'))
+        {
+            return self.resolve(name, true);
+        }
+        let mut expanded = String::new();
+        let mut rest = text;
+        while let Some(start) = rest.find("${") {
+            if rest[..start].ends_with('$') {
+                expanded.push_str(&rest[..start - 1]);
+                expanded.push_str("${");
+                rest = &rest[start + 2..];
+                continue;
+            }
+            let Some(length) = rest[start..].find('}') else {
+                break;
+            };
+            expanded.push_str(&rest[..start]);
+            let name = &rest[start + 2..start + length];
+            rest = &rest[start + length + 1..];
+            match self.resolve(name, false) {
+                Some(Value::String(value)) => expanded.push_str(&value),
+                Some(Value::Integer(value)) => expanded.push_str(&value.to_string()),
+                Some(Value::Float(value)) => expanded.push_str(&value.to_string()),
+                Some(Value::Bool(value)) => expanded.push_str(&value.to_string()),
+                Some(_) => {
+                    let name = name.trim();
+                    if !self.not_scalar.iter().any(|not_scalar| not_scalar == name) {
+                        self.not_scalar.push(name.to_owned());
+                    }
+                }
+                None => {}
+            }
+        }
+        expanded.push_str(rest);
+        Some(Value::String(expanded))
+    }
+
+    /// The value of the variable `name`, remembering it as missing if it does not exist
+    ///
+    /// Environment variables are read as bool or number if they replace a `whole` string.
+    fn resolve(&mut self, name: &str, whole: bool) -> Option<Value> {
+        let name = name.trim();
+        let value = self.lookup(name, whole);
+        if value.is_none() && !self.missing.iter().any(|missing| missing == name) {
+            self.missing.push(name.to_owned());
+        }
+        value
+    }
+
+    fn lookup(&self, name: &str, whole: bool) -> Option<Value> {
+        let (first, rest) = name.split_once('.').unwrap_or((name, ""));
+        if let Some(value) = self
+            .settings
+            .get(first)
+            .and_then(|value| nested(value, rest))
+        {
+            return Some(value);
+        }
+        for (namespace, variables) in self.files.iter().rev() {
+            let value = if namespace.is_empty() {
+                nested(variables, name)
+            } else if *namespace == first {
+                nested(variables, rest)
+            } else {
+                None
+            };
+            if value.is_some() {
+                return value;
+            }
+        }
+        if self.environment {
+            return std::env::var(name).ok().map(|value| {
+                if whole {
+                    parse_scalar(&value)
+                } else {
+                    Value::String(value)
+                }
+            });
+        }
+        None
+    }
+}
+
+/// The value at the dot separated `path` in `value`
+fn nested(value: &Value, path: &str) -> Option<Value> {
+    path.split('.')
+        .filter(|segment| !segment.is_empty())
+        .try_fold(value, |value, segment| value.child(segment))
+        .map(|value| value.unnamed().clone())
+}
+
+/// Read the text of an environment variable as bool or number if possible
+fn parse_scalar(text: &str) -> Value {
+    if let Ok(value) = text.parse::<bool>() {
+        Value::Bool(value)
+    } else if let Ok(value) = text.parse::<i128>() {
+        Value::Integer(value)
+    } else if let Some(value) = text.parse::<f64>().ok().filter(|value| value.is_finite()) {
+        Value::Float(value)
+    } else {
+        Value::String(text.to_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn map<const N: usize>(entries: [(&str, Value); N]) -> Value {
+        Value::Map(
+            entries
+                .into_iter()
+                .map(|(key, value)| (key.to_owned(), value))
+                .collect(),
+        )
+    }
+
+    fn variables<'a>(
+        settings: &'a BTreeMap<String, Value>,
+        files: Vec<(&'a str, Value)>,
+    ) -> Variables<'a> {
+        Variables {
+            path: "level.json".to_owned(),
+            settings,
+            files,
+            environment: false,
+            missing: vec![],
+            not_scalar: vec![],
+        }
+    }
+
+    #[test]
+    fn whole_strings_keep_the_type_of_variables() {
+        let settings = BTreeMap::new();
+        let files = vec![(
+            "vars",
+            map([("tile", 16.into()), ("spawn", map([("x", 1.5.into())]))]),
+        )];
+        let mut variables = variables(&settings, files);
+        assert_eq!(variables.expand("${vars.tile}"), Some(16.into()));
+        assert_eq!(variables.expand("${ vars.spawn.x }"), Some(1.5.into()));
+        assert_eq!(
+            variables.expand("${vars.spawn}"),
+            Some(map([("x", 1.5.into())]))
+        );
+        assert_eq!(variables.expand("no placeholder"), None);
+        assert!(variables.missing.is_empty());
+    }
+
+    #[test]
+    fn placeholders_in_longer_strings_are_replaced_by_text() {
+        let settings = BTreeMap::new();
+        let files = vec![("", map([("name", "orc".into()), ("hp", 5.into())]))];
+        let mut variables = variables(&settings, files);
+        assert_eq!(
+            variables.expand("${name} has ${hp} hp"),
+            Some("orc has 5 hp".into())
+        );
+        assert_eq!(variables.expand("${name}${hp}"), Some("orc5".into()));
+    }
+
+    #[test]
+    fn escaped_and_unterminated_placeholders_are_kept() {
+        let settings = BTreeMap::new();
+        let mut variables = variables(&settings, vec![("", map([("a", 1.into())]))]);
+        assert_eq!(variables.expand("$${a} is ${a}"), Some("${a} is 1".into()));
+        assert_eq!(variables.expand("$${a}"), Some("${a}".into()));
+        assert_eq!(variables.expand("${a} and ${a"), Some("1 and ${a".into()));
+        assert!(variables.missing.is_empty());
+    }
+
+    #[test]
+    fn settings_take_precedence_over_later_files() {
+        let settings = BTreeMap::from([("mult".to_owned(), 3.into())]);
+        let files = vec![
+            ("", map([("mult", 1.into()), ("base", 1.into())])),
+            ("", map([("mult", 2.into()), ("base", 2.into())])),
+        ];
+        let mut variables = variables(&settings, files);
+        assert_eq!(variables.expand("${mult}"), Some(3.into()));
+        assert_eq!(variables.expand("${base}"), Some(2.into()));
+    }
+
+    #[test]
+    fn problems_are_collected_once() {
+        let settings = BTreeMap::new();
+        let files = vec![("vars", map([("list", vec![1.into()].into())]))];
+        let mut variables = variables(&settings, files);
+        let mut document = map([
+            ("a", "${missing}".into()),
+            ("b", vec!["x ${missing} ${vars.list}".into()].into()),
+            ("c", "${vars.list} ${other}".into()),
+        ]);
+        variables.replace(&mut document);
+        assert_eq!(variables.missing, ["missing", "other"]);
+        assert_eq!(variables.not_scalar, ["vars.list"]);
+        assert_eq!(
+            SubstitutionError::Unresolved {
+                path: variables.path,
+                names: variables.missing,
+                not_scalar: variables.not_scalar,
+            }
+            .to_string(),
+            "Unresolved variables in `level.json`: missing, other, \
+             vars.list (can only be used as the whole string)"
+        );
+    }
+
+    #[test]
+    fn environment_values_are_parsed_as_scalars() {
+        assert_eq!(parse_scalar("true"), Value::Bool(true));
+        assert_eq!(parse_scalar("-3"), Value::Integer(-3));
+        assert_eq!(parse_scalar("1.5"), Value::Float(1.5));
+        assert_eq!(parse_scalar("inf"), Value::from("inf"));
+        assert_eq!(parse_scalar("orc"), Value::from("orc"));
+    }
+}
@@ -1,46 +1,116 @@
+use crate::data::DataFormat;
+use crate::loader::{
+    AssetCheck, DocumentSettings, LoaderOptions, loader_options_methods, text_options_methods,
+};
+#[cfg(feature = "schema")]
+use crate::schema::{RegisterSchema, SchemaFormat, register_schema};
+use crate::value::Value;
+use crate::{DataAsset, DataAssetError};
 use bevy_app::{App, Plugin};
 use bevy_asset::io::Reader;
-use bevy_asset::{Asset, AssetApp, AssetLoader, LoadContext};
-use std::marker::PhantomData;
+use bevy_asset::{AssetApp, AssetLoader, LoadContext};
 use std::str::from_utf8;
 use thiserror::Error;
 
 /// Plugin to load your asset type `A` from toml files.
 pub struct TomlAssetPlugin<A> {
     extensions: Vec<&'static str>,
-    _marker: PhantomData<A>,
+    options: LoaderOptions<A>,
+    #[cfg(feature = "schema")]
+    schema: Option<RegisterSchema>,
 }
 
 impl<A> Plugin for TomlAssetPlugin<A>
 where
-    for<'de> A: serde::Deserialize<'de> + Asset,
+    A: DataAsset,
 {
     fn build(&self, app: &mut App) {
-        app.init_asset::<A>()
-            .register_asset_loader(TomlAssetLoader::<A> {
-                extensions: self.extensions.clone(),
-                _marker: PhantomData,
-            });
+        crate::data_asset::init_data_asset::<A>(app);
+        self.register_loader(app);
     }
 }
 
 impl<A> TomlAssetPlugin<A>
 where
-    for<'de> A: serde::Deserialize<'de> + Asset,
+    A: DataAsset,
 {
     /// Create a new plugin that will load assets from files with the given extensions.
     pub fn new(extensions: &[&'static str]) -> Self {
         Self {
             extensions: extensions.to_owned(),
-            _marker: PhantomData,
+            options: LoaderOptions::default(),
+            #[cfg(feature = "schema")]
+            schema: None,
         }
     }
+
+    pub(crate) fn with_check(mut self, check: AssetCheck<A>) -> Self {
+        self.options.check = check;
+        self
+    }
+
+    /// Register the loader of this plugin without initializing the asset
+    pub(crate) fn register_loader(&self, app: &mut App) {
+        let options = self.options.build(app);
+        app.register_asset_loader(TomlAssetLoader::<A> {
+            extensions: self.extensions.clone(),
+            options,
+        });
+        #[cfg(feature = "schema")]
+        if let Some(register_schema) = self.schema {
+            register_schema(app, SchemaFormat::Toml, &self.extensions);
+        }
+    }
+
+    loader_options_methods!();
+    text_options_methods!();
+}
+
+#[cfg(feature = "validation")]
+impl<A> TomlAssetPlugin<A>
+where
+    A: DataAsset,
+{
+    /// Validate loaded documents against a JSON Schema before deserializing them.
+    ///
+    /// See [`DocumentSettings::schema`](crate::DocumentSettings::schema) for where the schema
+    /// comes from. All places in a document not matching the schema are reported in one error.
+    pub fn with_schema_validation(mut self) -> Self {
+        self.options.validate = true;
+        self
+    }
+}
+
+#[cfg(feature = "schema")]
+impl<A> TomlAssetPlugin<A>
+where
+    A: DataAsset,
+    A::Definition: schemars::JsonSchema,
+{
+    /// Register the JSON Schema of the asset definition for the extensions of this plugin
+    ///
+    /// The schemas of all registered types can be written to disk with
+    /// [`AssetSchemas::write`](crate::schema::AssetSchemas::write).
+    pub fn with_schema(mut self) -> Self {
+        self.schema = Some(register_schema::<A>);
+        self
+    }
 }
 
 /// Loads your asset type `A` from toml files
 pub struct TomlAssetLoader<A> {
     extensions: Vec<&'static str>,
-    _marker: PhantomData<A>,
+    options: LoaderOptions<A>,
+}
+
+impl<A> TomlAssetLoader<A> {
+    /// Create a loader without extensions for other loaders delegating to it
+    pub(crate) fn from_options(options: LoaderOptions<A>) -> Self {
+        Self {
+            extensions: vec![],
+            options,
+        }
+    }
 }
 
 /// Possible errors that can be produced by [`TomlAssetLoader`]
@@ -56,26 +126,36 @@ pub enum TomlLoaderError {
     /// A [TOML Error](serde_toml::de::Error)
     #[error("Could not parse TOML: {0}")]
     TomlError(#[from] serde_toml::de::Error),
+    /// An error of the format independent loading steps
+    #[error(transparent)]
+    DataAssetError(#[from] DataAssetError),
 }
 
 impl<A> AssetLoader for TomlAssetLoader<A>
 where
-    for<'de> A: serde::Deserialize<'de> + Asset,
+    A: DataAsset,
 {
     type Asset = A;
-    type Settings = ();
+    type Settings = DocumentSettings;
     type Error = TomlLoaderError;
 
     async fn load(
         &self,
         reader: &mut dyn Reader,
-        _settings: &(),
-        _load_context: &mut LoadContext<'_>,
+        settings: &DocumentSettings,
+        load_context: &mut LoadContext<'_>,
     ) -> Result<Self::Asset, Self::Error> {
         let mut bytes = Vec::new();
         reader.read_to_end(&mut bytes).await?;
-        let asset = serde_toml::from_str::<A>(from_utf8(&bytes)?)?;
-        Ok(asset)
+        if self.options.uses_value() {
+            let document = serde_toml::from_str::<Value>(from_utf8(&bytes)?)?;
+            return Ok(self
+                .options
+                .load_document(document, DataFormat::Toml, settings, load_context)
+                .await?);
+        }
+        let definition = serde_toml::from_str::<A::Definition>(from_utf8(&bytes)?)?;
+        Ok(self.options.create_asset(definition, load_context).await?)
     }
 
     fn extensions(&self) -> &[&str] {
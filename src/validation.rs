@@ -0,0 +1,125 @@
+use crate::DocumentSettings;
+use crate::value::Value;
+use bevy_asset::{LoadContext, ReadAssetBytesError};
+use std::fmt;
+use thiserror::Error;
+
+/// The field of a document referencing its schema
+const SCHEMA_FIELD: &str = "$schema";
+
+/// A place in a document that does not match its schema
+#[derive(Clone, Debug)]
+pub struct Violation {
+    /// [JSON Pointer](https://www.rfc-editor.org/rfc/rfc6901) to the value; empty for the
+    /// whole document
+    pub path: String,
+    /// Description of the mismatch
+    pub message: String,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let path = if self.path.is_empty() {
+            "/"
+        } else {
+            &self.path
+        };
+        write!(formatter, "at `{path}`: {}", self.message)
+    }
+}
+
+/// Possible errors when validating a document against its schema
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum ValidationError {
+    /// The `$schema` field or the schema setting is no valid asset path
+    #[error("Invalid schema path `{0}`")]
+    InvalidPath(String),
+    /// The schema file could not be read
+    #[error("Could not read the schema `{path}`: {source}")]
+    Read {
+        /// The path of the schema
+        path: String,
+        /// The [error](ReadAssetBytesError) reading the schema
+        source: ReadAssetBytesError,
+    },
+    /// The schema file is no valid JSON Schema
+    #[error("The schema `{path}` is invalid: {message}")]
+    InvalidSchema {
+        /// The path of the schema
+        path: String,
+        /// Description of the problem
+        message: String,
+    },
+    /// The document does not match the schema
+    #[error(
+        "The document does not match the schema `{schema}`:{}",
+        violations.iter().map(|violation| format!("\n  {violation}")).collect::<String>()
+    )]
+    Invalid {
+        /// The path of the schema
+        schema: String,
+        /// All places in the document not matching the schema
+        violations: Vec<Violation>,
+    },
+}
+
+/// Validate `document` against the schema from `settings` or its `$schema` field
+///
+/// The `$schema` field is removed from the document, so it does not have to be part of the
+/// asset definition. Schemas referenced by URL are left to editors and ignored here.
+pub(crate) async fn validate(
+    document: &mut Value,
+    settings: &DocumentSettings,
+    load_context: &mut LoadContext<'_>,
+) -> Result<(), ValidationError> {
+    let referenced = document
+        .as_map_mut()
+        .and_then(|map| map.shift_remove(SCHEMA_FIELD));
+    let schema = match (&settings.schema, &referenced) {
+        (Some(schema), _) => schema.as_str(),
+        (None, Some(Value::String(schema))) if !schema.contains("://") => schema.as_str(),
+        _ => return Ok(()),
+    };
+    let path = load_context
+        .asset_path()
+        .resolve_embed(schema)
+        .map_err(|_| ValidationError::InvalidPath(schema.to_owned()))?;
+    let bytes = load_context
+        .read_asset_bytes(&path)
+        .await
+        .map_err(|source| ValidationError::Read {
+            path: path.to_string(),
+            source,
+        })?;
+    let invalid_schema = |message: String| ValidationError::InvalidSchema {
+        path: path.to_string(),
+        message,
+    };
+    let schema = serde_json::from_slice::<serde_json::Value>(&bytes)
+        .map_err(|error| invalid_schema(error.to_string()))?;
+    let validator =
+        jsonschema::validator_for(&schema).map_err(|error| invalid_schema(error.to_string()))?;
+    let instance = serde_json::to_value(&*document).map_err(|error| ValidationError::Invalid {
+        schema: path.to_string(),
+        violations: vec![Violation {
+            path: String::new(),
+            message: error.to_string(),
+        }],
+    })?;
+    let violations: Vec<Violation> = validator
+        .iter_errors(&instance)
+        .map(|error| Violation {
+            path: error.instance_path().to_string(),
+            message: error.to_string(),
+        })
+        .collect();
+    if violations.is_empty() {
+        Ok(())
+    } else {
+        Err(ValidationError::Invalid {
+            schema: path.to_string(),
+            violations,
+        })
+    }
+}
@@ -0,0 +1,1425 @@
+use indexmap::IndexMap;
+use serde::de::value::{Error, MapDeserializer, SeqDeserializer, StringDeserializer};
+use serde::de::{
+    self, DeserializeSeed, EnumAccess, IntoDeserializer, MapAccess, SeqAccess, Unexpected,
+    VariantAccess, Visitor,
+};
+use serde::ser::{
+    self, SerializeMap, SerializeSeq, SerializeStruct, SerializeStructVariant, SerializeTuple,
+    SerializeTupleStruct, SerializeTupleVariant, Serializer,
+};
+use serde::{Deserialize, Deserializer, Serialize, forward_to_deserialize_any};
+use std::fmt;
+
+/// Map of a [`Value`] tree, keeping the order of its keys
+pub type Map = IndexMap<String, Value>;
+
+/// A format independent tree of the values in a document
+///
+/// The format plugins read documents into this tree for all loading steps that work on the
+/// content of a document before it is deserialized into an asset.
+/// Any type implementing [`Deserialize`] can be deserialized from a `Value`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum Value {
+    /// An absent value, like `null` in JSON or `()` and `None` in RON
+    #[default]
+    Null,
+    /// A boolean
+    Bool(bool),
+    /// An integer
+    Integer(i128),
+    /// A floating point number
+    Float(f64),
+    /// A string
+    String(String),
+    /// A byte string
+    Bytes(Vec<u8>),
+    /// A sequence of values
+    Seq(Vec<Value>),
+    /// A map of values
+    ///
+    /// Keys of other types than strings are converted to strings and parsed again when needed.
+    Map(Map),
+    /// A value with the name of its type or enum variant, like `Variant(1)` in RON
+    ///
+    /// The name is ignored when deserializing anything but an enum. Unnamed tuples and structs
+    /// in RON are stored with an empty name to tell them apart from newtypes and maps.
+    Named(String, Box<Value>),
+}
+
+impl Value {
+    /// The value without any [names](Value::Named) wrapped around it
+    pub fn unnamed(&self) -> &Value {
+        match self {
+            Value::Named(_, value) => value.unnamed(),
+            value => value,
+        }
+    }
+
+    /// The value without any [names](Value::Named) wrapped around it
+    pub fn unnamed_mut(&mut self) -> &mut Value {
+        match self {
+            Value::Named(_, value) => value.unnamed_mut(),
+            value => value,
+        }
+    }
+
+    /// The value of `key` if this is a map
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.as_map()?.get(key)
+    }
+
+    /// The value of `key` if this is a map
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut Value> {
+        self.as_map_mut()?.get_mut(key)
+    }
+
+    /// Look up a value by a [JSON Pointer](https://www.rfc-editor.org/rfc/rfc6901)
+    ///
+    /// The empty pointer references the whole tree.
+    pub fn pointer(&self, pointer: &str) -> Option<&Value> {
+        if pointer.is_empty() {
+            return Some(self);
+        }
+        pointer
+            .strip_prefix('/')?
+            .split('/')
+            .try_fold(self, |value, segment| value.child(&unescape(segment)))
+    }
+
+    /// Look up a value by a [JSON Pointer](https://www.rfc-editor.org/rfc/rfc6901)
+    ///
+    /// The empty pointer references the whole tree.
+    pub fn pointer_mut(&mut self, pointer: &str) -> Option<&mut Value> {
+        if pointer.is_empty() {
+            return Some(self);
+        }
+        pointer
+            .strip_prefix('/')?
+            .split('/')
+            .try_fold(self, |value, segment| value.child_mut(&unescape(segment)))
+    }
+
+    /// The value of a map key or sequence index
+    pub fn child(&self, segment: &str) -> Option<&Value> {
+        match self.unnamed() {
+            Value::Map(map) => map.get(segment),
+            Value::Seq(seq) => seq.get(segment.parse::<usize>().ok()?),
+            _ => None,
+        }
+    }
+
+    /// The value of a map key or sequence index
+    pub fn child_mut(&mut self, segment: &str) -> Option<&mut Value> {
+        match self.unnamed_mut() {
+            Value::Map(map) => map.get_mut(segment),
+            Value::Seq(seq) => seq.get_mut(segment.parse::<usize>().ok()?),
+            _ => None,
+        }
+    }
+
+    /// Whether this is [`Value::Null`]
+    pub fn is_null(&self) -> bool {
+        matches!(self.unnamed(), Value::Null)
+    }
+
+    /// The boolean if this is one
+    pub fn as_bool(&self) -> Option<bool> {
+        match self.unnamed() {
+            Value::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// The integer if this is one that fits into an `i64`
+    pub fn as_i64(&self) -> Option<i64> {
+        match self.unnamed() {
+            Value::Integer(value) => i64::try_from(*value).ok(),
+            _ => None,
+        }
+    }
+
+    /// The integer if this is one that fits into a `u64`
+    pub fn as_u64(&self) -> Option<u64> {
+        match self.unnamed() {
+            Value::Integer(value) => u64::try_from(*value).ok(),
+            _ => None,
+        }
+    }
+
+    /// The number as `f64` if this is an integer or floating point number
+    pub fn as_f64(&self) -> Option<f64> {
+        match self.unnamed() {
+            Value::Integer(value) => Some(*value as f64),
+            Value::Float(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// The string if this is one
+    pub fn as_str(&self) -> Option<&str> {
+        match self.unnamed() {
+            Value::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// The values if this is a sequence
+    pub fn as_seq(&self) -> Option<&Vec<Value>> {
+        match self.unnamed() {
+            Value::Seq(seq) => Some(seq),
+            _ => None,
+        }
+    }
+
+    /// The values if this is a sequence
+    pub fn as_seq_mut(&mut self) -> Option<&mut Vec<Value>> {
+        match self.unnamed_mut() {
+            Value::Seq(seq) => Some(seq),
+            _ => None,
+        }
+    }
+
+    /// The entries if this is a map
+    pub fn as_map(&self) -> Option<&Map> {
+        match self.unnamed() {
+            Value::Map(map) => Some(map),
+            _ => None,
+        }
+    }
+
+    /// The entries if this is a map
+    pub fn as_map_mut(&mut self) -> Option<&mut Map> {
+        match self.unnamed_mut() {
+            Value::Map(map) => Some(map),
+            _ => None,
+        }
+    }
+
+    /// Serialize `value` into a `Value` tree
+    ///
+    /// Structs become [maps](Value::Map) and enum variants are [named](Value::Named) after the
+    /// variant, like in RON documents.
+    pub fn from_serialize<T: Serialize + ?Sized>(value: &T) -> Result<Value, Error> {
+        value.serialize(ValueSerializer)
+    }
+
+    fn unexpected(&self) -> Unexpected<'_> {
+        match self {
+            Value::Null => Unexpected::Unit,
+            Value::Bool(value) => Unexpected::Bool(*value),
+            Value::Integer(value) => match i64::try_from(*value) {
+                Ok(value) => Unexpected::Signed(value),
+                Err(_) => Unexpected::Other("integer"),
+            },
+            Value::Float(value) => Unexpected::Float(*value),
+            Value::String(value) => Unexpected::Str(value),
+            Value::Bytes(value) => Unexpected::Bytes(value),
+            Value::Seq(_) => Unexpected::Seq,
+            Value::Map(_) => Unexpected::Map,
+            Value::Named(_, value) => value.unexpected(),
+        }
+    }
+}
+
+/// Escape a key for use as segment of a JSON Pointer
+#[cfg(any(
+    feature = "json",
+    feature = "msgpack",
+    feature = "ron",
+    feature = "toml",
+    feature = "xml",
+    feature = "yaml",
+    feature = "postcard",
+    feature = "cbor"
+))]
+pub(crate) fn escape(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+/// Reverse [`escape`]
+pub(crate) fn unescape(segment: &str) -> String {
+    segment.replace("~1", "/").replace("~0", "~")
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Value::Bool(value)
+    }
+}
+
+macro_rules! from_integer {
+    ($($integer:ty),*) => {
+        $(impl From<$integer> for Value {
+            fn from(value: $integer) -> Self {
+                Value::Integer(value as i128)
+            }
+        })*
+    };
+}
+from_integer!(i8, i16, i32, i64, u8, u16, u32, u64, isize, usize);
+
+impl From<f32> for Value {
+    fn from(value: f32) -> Self {
+        Value::Float(value as f64)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(value: f64) -> Self {
+        Value::Float(value)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Value::String(value.to_owned())
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Value::String(value)
+    }
+}
+
+impl From<Vec<Value>> for Value {
+    fn from(value: Vec<Value>) -> Self {
+        Value::Seq(value)
+    }
+}
+
+impl From<Map> for Value {
+    fn from(value: Map) -> Self {
+        Value::Map(value)
+    }
+}
+
+impl Serialize for Value {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Value::Null => serializer.serialize_unit(),
+            Value::Bool(value) => serializer.serialize_bool(*value),
+            Value::Integer(value) => {
+                if let Ok(value) = i64::try_from(*value) {
+                    serializer.serialize_i64(value)
+                } else if let Ok(value) = u64::try_from(*value) {
+                    serializer.serialize_u64(value)
+                } else {
+                    serializer.serialize_i128(*value)
+                }
+            }
+            Value::Float(value) => serializer.serialize_f64(*value),
+            Value::String(value) => serializer.serialize_str(value),
+            Value::Bytes(value) => serializer.serialize_bytes(value),
+            Value::Seq(seq) => serializer.collect_seq(seq),
+            Value::Map(map) => serializer.collect_map(map),
+            Value::Named(name, value) if value.is_null() => serializer.serialize_str(name),
+            Value::Named(name, value) if name.is_empty() => value.serialize(serializer),
+            // Externally tagged like serde does for enums
+            Value::Named(name, value) => {
+                let mut map = serializer.serialize_map(Some(1))?;
+                map.serialize_entry(name, value)?;
+                map.end()
+            }
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Value {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(ValueVisitor)
+    }
+}
+
+struct ValueVisitor;
+
+impl<'de> Visitor<'de> for ValueVisitor {
+    type Value = Value;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("any value")
+    }
+
+    fn visit_bool<E>(self, value: bool) -> Result<Value, E> {
+        Ok(Value::Bool(value))
+    }
+
+    fn visit_i64<E>(self, value: i64) -> Result<Value, E> {
+        Ok(Value::Integer(value.into()))
+    }
+
+    fn visit_i128<E>(self, value: i128) -> Result<Value, E> {
+        Ok(Value::Integer(value))
+    }
+
+    fn visit_u64<E>(self, value: u64) -> Result<Value, E> {
+        Ok(Value::Integer(value.into()))
+    }
+
+    fn visit_u128<E: de::Error>(self, value: u128) -> Result<Value, E> {
+        i128::try_from(value)
+            .map(Value::Integer)
+            .map_err(|_| E::custom(format!("integer {value} is too large")))
+    }
+
+    fn visit_f64<E>(self, value: f64) -> Result<Value, E> {
+        Ok(Value::Float(value))
+    }
+
+    fn visit_char<E>(self, value: char) -> Result<Value, E> {
+        Ok(Value::String(value.to_string()))
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<Value, E> {
+        Ok(Value::String(value.to_owned()))
+    }
+
+    fn visit_string<E>(self, value: String) -> Result<Value, E> {
+        Ok(Value::String(value))
+    }
+
+    fn visit_bytes<E>(self, value: &[u8]) -> Result<Value, E> {
+        Ok(Value::Bytes(value.to_owned()))
+    }
+
+    fn visit_byte_buf<E>(self, value: Vec<u8>) -> Result<Value, E> {
+        Ok(Value::Bytes(value))
+    }
+
+    fn visit_none<E>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+        Value::deserialize(deserializer)
+    }
+
+    fn visit_unit<E>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_newtype_struct<D: Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<Value, D::Error> {
+        Value::deserialize(deserializer)
+    }
+
+    fn visit_seq<S: SeqAccess<'de>>(self, mut access: S) -> Result<Value, S::Error> {
+        let mut seq = Vec::with_capacity(access.size_hint().unwrap_or(0));
+        while let Some(value) = access.next_element()? {
+            seq.push(value);
+        }
+        Ok(Value::Seq(seq))
+    }
+
+    fn visit_map<M: MapAccess<'de>>(self, mut access: M) -> Result<Value, M::Error> {
+        let mut map = Map::with_capacity(access.size_hint().unwrap_or(0));
+        while let Some((key, value)) = access.next_entry::<Value, Value>()? {
+            map.insert(key_to_string(key).map_err(de::Error::custom)?, value);
+        }
+        Ok(Value::Map(map))
+    }
+
+    fn visit_enum<A: EnumAccess<'de>>(self, access: A) -> Result<Value, A::Error> {
+        let (name, variant) = access.variant::<String>()?;
+        Ok(Value::Named(
+            name,
+            Box::new(variant.newtype_variant::<Value>()?),
+        ))
+    }
+}
+
+/// Convert a scalar map key to the string it is stored as
+pub(crate) fn key_to_string(key: Value) -> Result<String, String> {
+    match key {
+        Value::String(key) => Ok(key),
+        Value::Integer(key) => Ok(key.to_string()),
+        Value::Float(key) => Ok(key.to_string()),
+        Value::Bool(key) => Ok(key.to_string()),
+        Value::Named(name, value) if value.is_null() => Ok(name),
+        key => Err(format!(
+            "map keys need to be strings, numbers or booleans, but found {}",
+            key.unexpected()
+        )),
+    }
+}
+
+fn visit_integer<'de, V: Visitor<'de>>(value: i128, visitor: V) -> Result<V::Value, Error> {
+    if let Ok(value) = i64::try_from(value) {
+        visitor.visit_i64(value)
+    } else if let Ok(value) = u64::try_from(value) {
+        visitor.visit_u64(value)
+    } else {
+        visitor.visit_i128(value)
+    }
+}
+
+fn visit_seq<'de, V: Visitor<'de>>(seq: Vec<Value>, visitor: V) -> Result<V::Value, Error> {
+    let mut deserializer = SeqDeserializer::new(seq.into_iter());
+    let value = visitor.visit_seq(&mut deserializer)?;
+    deserializer.end()?;
+    Ok(value)
+}
+
+fn visit_map<'de, V: Visitor<'de>>(map: Map, visitor: V) -> Result<V::Value, Error> {
+    let mut deserializer =
+        MapDeserializer::new(map.into_iter().map(|(key, value)| (MapKey(key), value)));
+    let value = visitor.visit_map(&mut deserializer)?;
+    deserializer.end()?;
+    Ok(value)
+}
+
+impl<'de> IntoDeserializer<'de, Error> for Value {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+impl<'de> Deserializer<'de> for Value {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            Value::Null => visitor.visit_unit(),
+            Value::Bool(value) => visitor.visit_bool(value),
+            Value::Integer(value) => visit_integer(value, visitor),
+            Value::Float(value) => visitor.visit_f64(value),
+            Value::String(value) => visitor.visit_string(value),
+            Value::Bytes(value) => visitor.visit_byte_buf(value),
+            Value::Seq(seq) => visit_seq(seq, visitor),
+            Value::Map(map) => visit_map(map, visitor),
+            // A bare name, like a unit variant in RON
+            Value::Named(name, value) if value.is_null() => visitor.visit_string(name),
+            Value::Named(_, value) => value.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            Value::Null => visitor.visit_none(),
+            value => visitor.visit_some(value),
+        }
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.unnamed() {
+            Value::Null => visitor.visit_unit(),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        match self {
+            // `Name(value)` in RON
+            Value::Named(_, value) => match *value {
+                Value::Seq(mut seq) if seq.len() == 1 => {
+                    visitor.visit_newtype_struct(seq.remove(0))
+                }
+                value => visitor.visit_newtype_struct(value),
+            },
+            value => visitor.visit_newtype_struct(value),
+        }
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            Value::Named(_, value) => value.deserialize_seq(visitor),
+            Value::Bytes(bytes) => visit_seq(bytes.into_iter().map(Value::from).collect(), visitor),
+            value => value.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            Value::Named(_, value) => value.deserialize_map(visitor),
+            value => value.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        let access = match self {
+            Value::String(variant) => VariantDeserializer {
+                variant,
+                value: None,
+                named: false,
+            },
+            Value::Named(variant, value) => VariantDeserializer {
+                variant,
+                value: Some(*value),
+                named: true,
+            },
+            Value::Map(map) if map.len() == 1 => {
+                let (variant, value) = map.into_iter().next().expect("map has one entry");
+                VariantDeserializer {
+                    variant,
+                    value: Some(value),
+                    named: false,
+                }
+            }
+            value => {
+                return Err(de::Error::invalid_type(
+                    value.unexpected(),
+                    &"a string, a map with a single key or a named value",
+                ));
+            }
+        };
+        visitor.visit_enum(access)
+    }
+
+    fn deserialize_identifier<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_any(visitor)
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_unit()
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf
+    }
+}
+
+struct VariantDeserializer {
+    variant: String,
+    value: Option<Value>,
+    /// Whether the content comes from a named value, which wraps newtype content in a sequence
+    named: bool,
+}
+
+impl<'de> EnumAccess<'de> for VariantDeserializer {
+    type Error = Error;
+    type Variant = Self;
+
+    fn variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<(T::Value, Self), Error> {
+        let variant = seed.deserialize(StringDeserializer::<Error>::new(self.variant.clone()))?;
+        Ok((variant, self))
+    }
+}
+
+impl<'de> VariantAccess<'de> for VariantDeserializer {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Error> {
+        match self.value {
+            None | Some(Value::Null) => Ok(()),
+            Some(value) => Err(de::Error::invalid_type(value.unexpected(), &"unit variant")),
+        }
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, Error> {
+        match self.value {
+            Some(Value::Seq(mut seq)) if self.named && seq.len() == 1 => {
+                seed.deserialize(seq.remove(0))
+            }
+            Some(value) => seed.deserialize(value),
+            None => Err(de::Error::invalid_type(
+                Unexpected::UnitVariant,
+                &"newtype variant",
+            )),
+        }
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, Error> {
+        match self.value {
+            Some(value) => value.deserialize_seq(visitor),
+            None => Err(de::Error::invalid_type(
+                Unexpected::UnitVariant,
+                &"tuple variant",
+            )),
+        }
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        match self.value {
+            Some(value) => value.deserialize_map(visitor),
+            None => Err(de::Error::invalid_type(
+                Unexpected::UnitVariant,
+                &"struct variant",
+            )),
+        }
+    }
+}
+
+/// Deserializer for map keys, which parses the key when a number or boolean is requested
+struct MapKey(String);
+
+impl<'de> IntoDeserializer<'de, Error> for MapKey {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+macro_rules! deserialize_parsed_key {
+    ($($method:ident => $visit:ident),*) => {
+        $(fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+            match self.0.parse() {
+                Ok(value) => visitor.$visit(value),
+                Err(_) => visitor.visit_string(self.0),
+            }
+        })*
+    };
+}
+
+impl<'de> Deserializer<'de> for MapKey {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_string(self.0)
+    }
+
+    deserialize_parsed_key! {
+        deserialize_bool => visit_bool,
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_i128 => visit_i128,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_u128 => visit_u128,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        Value::String(self.0).deserialize_enum(name, variants, visitor)
+    }
+
+    forward_to_deserialize_any! {
+        char str string bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+#[cfg(any(
+    feature = "json",
+    feature = "ron",
+    feature = "toml",
+    feature = "yaml",
+    feature = "msgpack",
+    feature = "xml",
+    feature = "postcard",
+    feature = "cbor"
+))]
+/// A value that differs between two value trees
+pub(crate) struct Difference<'a> {
+    /// The map keys and sequence indices leading to the value
+    pub(crate) path: Vec<String>,
+    /// The value in the old tree, `None` if it was added
+    pub(crate) old: Option<&'a Value>,
+    /// The value in the new tree, `None` if it was removed
+    pub(crate) new: Option<&'a Value>,
+}
+
+#[cfg(any(
+    feature = "json",
+    feature = "ron",
+    feature = "toml",
+    feature = "yaml",
+    feature = "msgpack",
+    feature = "xml",
+    feature = "postcard",
+    feature = "cbor"
+))]
+/// The values that differ between `old` and `new`
+///
+/// Maps are compared per key and sequences per index, with added or removed items at their
+/// end. Everything else is different as a whole. Names are only compared if both values have
+/// one, since not every format keeps the names of structs.
+pub(crate) fn diff<'a>(old: &'a Value, new: &'a Value) -> Vec<Difference<'a>> {
+    let mut differences = vec![];
+    collect_differences(old, new, &mut vec![], &mut differences);
+    differences
+}
+
+#[cfg(any(
+    feature = "json",
+    feature = "ron",
+    feature = "toml",
+    feature = "yaml",
+    feature = "msgpack",
+    feature = "xml",
+    feature = "postcard",
+    feature = "cbor"
+))]
+fn collect_differences<'a>(
+    old: &'a Value,
+    new: &'a Value,
+    path: &mut Vec<String>,
+    differences: &mut Vec<Difference<'a>>,
+) {
+    if old == new {
+        return;
+    }
+    let renamed = matches!(
+        (old, new),
+        (Value::Named(old, _), Value::Named(new, _))
+            if !old.is_empty() && !new.is_empty() && old != new
+    );
+    let mut difference = |path: &[String], old, new| {
+        differences.push(Difference {
+            path: path.to_vec(),
+            old,
+            new,
+        });
+    };
+    match (old.unnamed(), new.unnamed()) {
+        _ if renamed => difference(path, Some(old), Some(new)),
+        (Value::Map(old), Value::Map(new)) => {
+            for (key, old) in old {
+                path.push(key.clone());
+                match new.get(key) {
+                    Some(new) => collect_differences(old, new, path, differences),
+                    None => differences.push(Difference {
+                        path: path.clone(),
+                        old: Some(old),
+                        new: None,
+                    }),
+                }
+                path.pop();
+            }
+            for (key, new) in new.iter().filter(|(key, _)| !old.contains_key(*key)) {
+                path.push(key.clone());
+                differences.push(Difference {
+                    path: path.clone(),
+                    old: None,
+                    new: Some(new),
+                });
+                path.pop();
+            }
+        }
+        (Value::Seq(old_items), Value::Seq(new_items)) => {
+            for index in 0..old_items.len().max(new_items.len()) {
+                path.push(index.to_string());
+                match (old_items.get(index), new_items.get(index)) {
+                    (Some(old), Some(new)) => collect_differences(old, new, path, differences),
+                    (old, new) => differences.push(Difference {
+                        path: path.clone(),
+                        old,
+                        new,
+                    }),
+                }
+                path.pop();
+            }
+        }
+        _ => difference(path, Some(old), Some(new)),
+    }
+}
+
+/// Serializes values into a [`Value`] tree
+struct ValueSerializer;
+
+/// Wrap `value` in the name of an enum variant
+fn with_name(name: Option<&'static str>, value: Value) -> Value {
+    match name {
+        Some(name) => Value::Named(name.to_owned(), Box::new(value)),
+        None => value,
+    }
+}
+
+impl Serializer for ValueSerializer {
+    type Ok = Value;
+    type Error = Error;
+    type SerializeSeq = SeqSerializer;
+    type SerializeTuple = SeqSerializer;
+    type SerializeTupleStruct = SeqSerializer;
+    type SerializeTupleVariant = SeqSerializer;
+    type SerializeMap = MapSerializer;
+    type SerializeStruct = MapSerializer;
+    type SerializeStructVariant = MapSerializer;
+
+    fn serialize_bool(self, value: bool) -> Result<Value, Error> {
+        Ok(Value::Bool(value))
+    }
+
+    fn serialize_i8(self, value: i8) -> Result<Value, Error> {
+        Ok(value.into())
+    }
+
+    fn serialize_i16(self, value: i16) -> Result<Value, Error> {
+        Ok(value.into())
+    }
+
+    fn serialize_i32(self, value: i32) -> Result<Value, Error> {
+        Ok(value.into())
+    }
+
+    fn serialize_i64(self, value: i64) -> Result<Value, Error> {
+        Ok(value.into())
+    }
+
+    fn serialize_i128(self, value: i128) -> Result<Value, Error> {
+        Ok(Value::Integer(value))
+    }
+
+    fn serialize_u8(self, value: u8) -> Result<Value, Error> {
+        Ok(value.into())
+    }
+
+    fn serialize_u16(self, value: u16) -> Result<Value, Error> {
+        Ok(value.into())
+    }
+
+    fn serialize_u32(self, value: u32) -> Result<Value, Error> {
+        Ok(value.into())
+    }
+
+    fn serialize_u64(self, value: u64) -> Result<Value, Error> {
+        Ok(value.into())
+    }
+
+    fn serialize_u128(self, value: u128) -> Result<Value, Error> {
+        i128::try_from(value)
+            .map(Value::Integer)
+            .map_err(|_| ser::Error::custom(format!("integer {value} is too large")))
+    }
+
+    fn serialize_f32(self, value: f32) -> Result<Value, Error> {
+        Ok(value.into())
+    }
+
+    fn serialize_f64(self, value: f64) -> Result<Value, Error> {
+        Ok(value.into())
+    }
+
+    fn serialize_char(self, value: char) -> Result<Value, Error> {
+        Ok(value.to_string().into())
+    }
+
+    fn serialize_str(self, value: &str) -> Result<Value, Error> {
+        Ok(value.into())
+    }
+
+    fn serialize_bytes(self, value: &[u8]) -> Result<Value, Error> {
+        Ok(Value::Bytes(value.to_owned()))
+    }
+
+    fn serialize_none(self) -> Result<Value, Error> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Value, Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Value, Error> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Value, Error> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<Value, Error> {
+        Ok(with_name(Some(variant), Value::Null))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Value, Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Value, Error> {
+        Ok(with_name(Some(variant), value.serialize(self)?))
+    }
+
+    fn serialize_seq(self, length: Option<usize>) -> Result<SeqSerializer, Error> {
+        Ok(SeqSerializer::new(None, length.unwrap_or_default()))
+    }
+
+    fn serialize_tuple(self, length: usize) -> Result<SeqSerializer, Error> {
+        Ok(SeqSerializer::new(None, length))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        length: usize,
+    ) -> Result<SeqSerializer, Error> {
+        Ok(SeqSerializer::new(None, length))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        length: usize,
+    ) -> Result<SeqSerializer, Error> {
+        Ok(SeqSerializer::new(Some(variant), length))
+    }
+
+    fn serialize_map(self, length: Option<usize>) -> Result<MapSerializer, Error> {
+        Ok(MapSerializer::new(None, length.unwrap_or_default()))
+    }
+
+    fn serialize_struct(self, _name: &'static str, length: usize) -> Result<MapSerializer, Error> {
+        Ok(MapSerializer::new(None, length))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        length: usize,
+    ) -> Result<MapSerializer, Error> {
+        Ok(MapSerializer::new(Some(variant), length))
+    }
+}
+
+/// Serializes sequences, tuples and tuple variants into a [`Value::Seq`]
+struct SeqSerializer {
+    name: Option<&'static str>,
+    items: Vec<Value>,
+}
+
+impl SeqSerializer {
+    fn new(name: Option<&'static str>, length: usize) -> Self {
+        SeqSerializer {
+            name,
+            items: Vec::with_capacity(length),
+        }
+    }
+
+    fn push<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.items.push(value.serialize(ValueSerializer)?);
+        Ok(())
+    }
+
+    fn finish(self) -> Value {
+        with_name(self.name, Value::Seq(self.items))
+    }
+}
+
+impl SerializeSeq for SeqSerializer {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        Ok(self.finish())
+    }
+}
+
+impl SerializeTuple for SeqSerializer {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        Ok(self.finish())
+    }
+}
+
+impl SerializeTupleStruct for SeqSerializer {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        Ok(self.finish())
+    }
+}
+
+impl SerializeTupleVariant for SeqSerializer {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        Ok(self.finish())
+    }
+}
+
+/// Serializes maps, structs and struct variants into a [`Value::Map`]
+struct MapSerializer {
+    name: Option<&'static str>,
+    map: Map,
+    key: Option<String>,
+}
+
+impl MapSerializer {
+    fn new(name: Option<&'static str>, length: usize) -> Self {
+        MapSerializer {
+            name,
+            map: Map::with_capacity(length),
+            key: None,
+        }
+    }
+
+    fn insert<T: Serialize + ?Sized>(&mut self, key: &str, value: &T) -> Result<(), Error> {
+        self.map
+            .insert(key.to_owned(), value.serialize(ValueSerializer)?);
+        Ok(())
+    }
+
+    fn finish(self) -> Value {
+        with_name(self.name, Value::Map(self.map))
+    }
+}
+
+impl SerializeMap for MapSerializer {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Error> {
+        let key = key_to_string(key.serialize(ValueSerializer)?).map_err(ser::Error::custom)?;
+        self.key = Some(key);
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        let key = self
+            .key
+            .take()
+            .ok_or_else(|| ser::Error::custom("map value without key"))?;
+        self.insert(&key, value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        Ok(self.finish())
+    }
+}
+
+impl SerializeStruct for MapSerializer {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.insert(key, value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        Ok(self.finish())
+    }
+}
+
+impl SerializeStructVariant for MapSerializer {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.insert(key, value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        Ok(self.finish())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use std::collections::BTreeMap;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Level {
+        name: String,
+        size: (u32, u32),
+        spawn: Option<Spawn>,
+        kinds: Vec<Kind>,
+        weights: BTreeMap<u8, f32>,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Spawn {
+        x: i32,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    enum Kind {
+        Dull,
+        Blunt(u8),
+        Sharp { edge: f32 },
+        Pair(u8, u8),
+    }
+
+    fn named(name: &str, value: impl Into<Value>) -> Value {
+        Value::Named(name.to_owned(), Box::new(value.into()))
+    }
+
+    fn map<const N: usize>(entries: [(&str, Value); N]) -> Value {
+        Value::Map(
+            entries
+                .into_iter()
+                .map(|(key, value)| (key.to_owned(), value))
+                .collect(),
+        )
+    }
+
+    fn level() -> Level {
+        Level {
+            name: "forest".to_owned(),
+            size: (4, 2),
+            spawn: Some(Spawn { x: -1 }),
+            kinds: vec![
+                Kind::Dull,
+                Kind::Blunt(2),
+                Kind::Sharp { edge: 1.5 },
+                Kind::Pair(1, 2),
+            ],
+            weights: BTreeMap::from([(1, 0.5), (20, 2.0)]),
+        }
+    }
+
+    #[test]
+    fn serialize_names_enum_variants() {
+        assert_eq!(
+            Value::from_serialize(&level()).unwrap(),
+            map([
+                ("name", "forest".into()),
+                ("size", vec![4.into(), 2.into()].into()),
+                ("spawn", map([("x", (-1).into())])),
+                (
+                    "kinds",
+                    vec![
+                        named("Dull", Value::Null),
+                        named("Blunt", 2),
+                        named("Sharp", map([("edge", 1.5.into())])),
+                        named("Pair", vec![1.into(), 2.into()]),
+                    ]
+                    .into()
+                ),
+                ("weights", map([("1", 0.5.into()), ("20", 2.0.into())])),
+            ])
+        );
+    }
+
+    #[test]
+    fn serialize_rejects_compound_map_keys() {
+        let keys = BTreeMap::from([((1, 2), 3)]);
+        assert!(Value::from_serialize(&keys).is_err());
+    }
+
+    #[test]
+    fn deserialize_round_trips() {
+        let value = Value::from_serialize(&level()).unwrap();
+        assert_eq!(Level::deserialize(value).unwrap(), level());
+    }
+
+    #[test]
+    fn deserialize_externally_tagged_enums() {
+        let kinds = Value::Seq(vec![
+            "Dull".into(),
+            map([("Blunt", 2.into())]),
+            map([("Sharp", map([("edge", 1.5.into())]))]),
+            map([("Pair", vec![1.into(), 2.into()].into())]),
+        ]);
+        assert_eq!(
+            Vec::<Kind>::deserialize(kinds).unwrap(),
+            vec![
+                Kind::Dull,
+                Kind::Blunt(2),
+                Kind::Sharp { edge: 1.5 },
+                Kind::Pair(1, 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn deserialize_named_values() {
+        // `Spawn(x: 1)` and `Blunt(2)` in RON
+        let spawn = named("Spawn", map([("x", 1.into())]));
+        assert_eq!(Spawn::deserialize(spawn).unwrap(), Spawn { x: 1 });
+        let blunt = named("Blunt", vec![2.into()]);
+        assert_eq!(Kind::deserialize(blunt).unwrap(), Kind::Blunt(2));
+        assert!(Kind::deserialize(named("Dull", 1)).is_err());
+    }
+
+    #[test]
+    fn deserialize_options_and_bytes() {
+        assert_eq!(Option::<u8>::deserialize(Value::Null).unwrap(), None);
+        assert_eq!(Option::<u8>::deserialize(Value::from(3)).unwrap(), Some(3));
+        let bytes = Value::Bytes(vec![1, 2]);
+        assert_eq!(Vec::<u8>::deserialize(bytes).unwrap(), vec![1, 2]);
+        assert!(u8::deserialize(Value::from(300)).is_err());
+    }
+
+    #[test]
+    fn map_keys_are_parsed_when_needed() {
+        let weights = map([("1", 0.5.into()), ("true", 1.0.into())]);
+        assert!(BTreeMap::<u8, f32>::deserialize(weights.clone()).is_err());
+        assert_eq!(
+            BTreeMap::<String, f32>::deserialize(weights).unwrap(),
+            BTreeMap::from([("1".to_owned(), 0.5), ("true".to_owned(), 1.0)])
+        );
+        let flags = map([("true", 1.into()), ("false", 0.into())]);
+        assert_eq!(
+            BTreeMap::<bool, u8>::deserialize(flags).unwrap(),
+            BTreeMap::from([(true, 1), (false, 0)])
+        );
+    }
+
+    #[test]
+    fn pointers_escape_segments() {
+        let document = map([("a/b", map([("c~d", vec![1.into(), 2.into()].into())]))]);
+        assert_eq!(document.pointer("/a~1b/c~0d/1"), Some(&Value::from(2)));
+        assert_eq!(document.pointer("/a~1b/missing"), None);
+    }
+
+    #[cfg(any(
+        feature = "json",
+        feature = "ron",
+        feature = "toml",
+        feature = "yaml",
+        feature = "msgpack",
+        feature = "xml",
+        feature = "postcard",
+        feature = "cbor"
+    ))]
+    #[test]
+    fn diff_reports_changed_paths() {
+        let old = map([
+            ("hp", 1.into()),
+            ("tags", vec!["a".into(), "b".into()].into()),
+            ("kind", named("Blunt", 2)),
+            ("gone", true.into()),
+        ]);
+        let new = map([
+            ("hp", 2.into()),
+            ("tags", vec!["a".into()].into()),
+            ("kind", named("Sharp", 2)),
+            ("added", false.into()),
+        ]);
+        let differences: Vec<_> = diff(&old, &new)
+            .into_iter()
+            .map(|difference| (difference.path.join("/"), difference.old, difference.new))
+            .collect();
+        assert_eq!(
+            differences,
+            vec![
+                ("hp".to_owned(), Some(&1.into()), Some(&2.into())),
+                ("tags/1".to_owned(), Some(&"b".into()), None),
+                ("kind".to_owned(), old.get("kind"), new.get("kind")),
+                ("gone".to_owned(), Some(&true.into()), None),
+                ("added".to_owned(), None, Some(&false.into())),
+            ]
+        );
+    }
+
+    #[cfg(any(
+        feature = "json",
+        feature = "ron",
+        feature = "toml",
+        feature = "yaml",
+        feature = "msgpack",
+        feature = "xml",
+        feature = "postcard",
+        feature = "cbor"
+    ))]
+    #[test]
+    fn diff_ignores_missing_names() {
+        // Only RON keeps the names of structs
+        let old = named("Spawn", map([("x", 1.into())]));
+        assert!(diff(&old, &map([("x", 1.into())])).is_empty());
+        assert_eq!(diff(&old, &named("Spawn", map([("x", 2.into())]))).len(), 1);
+    }
+}
@@ -0,0 +1,222 @@
+use crate::data::{DataFormat, read_document};
+use crate::dependency::MissingFiles;
+use crate::include::{IncludeError, include};
+use crate::inheritance::{Inheritance, merge};
+use crate::value::Value;
+use bevy_app::App;
+use bevy_asset::io::AssetReaderError;
+use bevy_asset::{AssetPath, LoadContext, LoadDirectError, ReadAssetBytesError};
+use bevy_ecs::resource::Resource;
+use std::mem::take;
+use std::sync::{Arc, PoisonError, RwLock};
+use thiserror::Error;
+
+/// Variants of documents of the json, ron, toml and yaml plugins for profiles like debug builds
+/// or handheld platforms
+///
+/// With `with_variants` on their plugins, loading `trees.level.json` also looks for a sibling
+/// file per active profile with the profile in front of the last extension, like
+/// `trees.level.debug.json` for the profile `debug`. Variants are partial documents in the
+/// format of the document and are deep-merged onto it in the order of the profiles, so later
+/// profiles override earlier ones.
+///
+/// The active profiles are the profiles added here, followed by the profiles in the
+/// [`ActiveProfiles`] resource. Variants are loader dependencies of the asset whether they exist
+/// or not, so adding or changing them reloads the asset when watching for changes. With asset
+/// processing, only existing variants are dependencies and variants added later are picked up
+/// the next time the asset is processed.
+///
+/// ```no_run
+/// # use bevy::prelude::*;
+/// # use bevy_common_assets::toml::TomlAssetPlugin;
+/// # use bevy_common_assets::variant::{ActiveProfiles, Variants};
+/// # #[derive(serde::Deserialize, Asset, TypePath)]
+/// # struct Config;
+/// let variants = if cfg!(debug_assertions) {
+///     Variants::default().with_profile("debug")
+/// } else {
+///     Variants::default()
+/// };
+/// App::new()
+///     .insert_resource(ActiveProfiles::new(["steamdeck"]))
+///     .add_plugins((
+///         DefaultPlugins,
+///         TomlAssetPlugin::<Config>::new(&["config.toml"]).with_variants(variants),
+///     ));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Variants {
+    profiles: Vec<String>,
+    active: ActiveProfiles,
+    missing: MissingFiles,
+}
+
+impl Variants {
+    /// Merge the variants for `profile` after the variants of all previously added profiles
+    pub fn with_profile(mut self, profile: impl Into<String>) -> Self {
+        self.profiles.push(profile.into());
+        self
+    }
+
+    /// Use the [`ActiveProfiles`] resource of `app`, initializing it if no other plugin did,
+    /// and watch missing variants if its asset server watches for changes
+    pub(crate) fn connected(&self, app: &mut App) -> Self {
+        Variants {
+            profiles: self.profiles.clone(),
+            active: app
+                .world_mut()
+                .get_resource_or_init::<ActiveProfiles>()
+                .clone(),
+            missing: MissingFiles::new(app),
+        }
+    }
+
+    /// The profiles of this plugin followed by the active profiles, without duplicates
+    fn profiles(&self) -> Vec<String> {
+        let mut profiles: Vec<String> = vec![];
+        for profile in self.profiles.iter().cloned().chain(self.active.get()) {
+            if !profiles.contains(&profile) {
+                profiles.push(profile);
+            }
+        }
+        profiles
+    }
+}
+
+/// The profiles whose variants are merged onto documents of all plugins with
+/// [`Variants`], after the profiles configured on the plugins
+///
+/// The profiles are shared with the loaders, so they can be changed at runtime. Assets that are
+/// already loaded keep their variants until they are reloaded, e.g. with
+/// [`AssetServer::reload`](bevy_asset::AssetServer::reload).
+#[derive(Resource, Clone, Debug, Default)]
+pub struct ActiveProfiles {
+    profiles: Arc<RwLock<Vec<String>>>,
+}
+
+impl ActiveProfiles {
+    /// Activate `profiles` in this order
+    pub fn new(profiles: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        let active = ActiveProfiles::default();
+        active.set(profiles);
+        active
+    }
+
+    /// Replace the active profiles by `profiles`
+    pub fn set(&self, profiles: impl IntoIterator<Item = impl Into<String>>) {
+        *self
+            .profiles
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) =
+            profiles.into_iter().map(Into::into).collect();
+    }
+
+    /// The active profiles in the order their variants are merged
+    pub fn get(&self) -> Vec<String> {
+        self.profiles
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+}
+
+/// Possible errors when merging the variants of a document onto it
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum VariantError {
+    /// A variant exists, but could not be read
+    #[error("Could not read the variant `{variant}`: {source}")]
+    Read {
+        /// The path of the variant
+        variant: String,
+        /// The [error](ReadAssetBytesError) reading the variant
+        source: Box<ReadAssetBytesError>,
+    },
+    /// A variant could not be parsed in the format of the document
+    #[error("Could not parse the variant `{variant}`: {message}")]
+    Parse {
+        /// The path of the variant
+        variant: String,
+        /// Description of the problem
+        message: String,
+    },
+    /// The includes of a variant could not be resolved
+    #[error(transparent)]
+    Include(#[from] IncludeError),
+    /// A missing variant could not be watched for changes
+    #[error("Could not watch the missing variant `{variant}`: {source}")]
+    Watch {
+        /// The path of the variant
+        variant: String,
+        /// The [error](LoadDirectError) loading the placeholder of the variant
+        source: Box<LoadDirectError>,
+    },
+}
+
+/// Merge the variants of `document` for all active profiles onto it
+///
+/// Variants are read in `format` as dependencies of the loaded asset. With `includes`, the
+/// included documents of variants are spliced into them before merging.
+pub(crate) async fn apply(
+    document: &mut Value,
+    format: DataFormat,
+    variants: &Variants,
+    includes: bool,
+    load_context: &mut LoadContext<'_>,
+) -> Result<(), VariantError> {
+    let path = load_context.asset_path().clone_owned();
+    for profile in variants.profiles() {
+        let Some(variant_path) = variant_path(&path, &profile) else {
+            continue;
+        };
+        let bytes = match load_context.read_asset_bytes(&variant_path).await {
+            Ok(bytes) => bytes,
+            Err(ReadAssetBytesError::AssetReaderError(AssetReaderError::NotFound(_))) => {
+                variants
+                    .missing
+                    .watch(load_context, &variant_path)
+                    .await
+                    .map_err(|source| VariantError::Watch {
+                        variant: variant_path.to_string(),
+                        source,
+                    })?;
+                continue;
+            }
+            Err(source) => {
+                return Err(VariantError::Read {
+                    variant: variant_path.to_string(),
+                    source: Box::new(source),
+                });
+            }
+        };
+        let mut variant = read_document(format, &bytes).map_err(|error| VariantError::Parse {
+            variant: variant_path.to_string(),
+            message: error.to_string(),
+        })?;
+        if includes {
+            include(&mut variant, &variant_path, format, load_context).await?;
+        }
+        *document = merge(
+            take(document),
+            variant,
+            &Inheritance::default(),
+            &mut vec![],
+        );
+    }
+    Ok(())
+}
+
+/// The path of the variant of the document at `path` for `profile`
+///
+/// The profile is put in front of the last extension of the file name.
+fn variant_path(path: &AssetPath<'static>, profile: &str) -> Option<AssetPath<'static>> {
+    let file_name = path.path().file_name()?.to_str()?;
+    let variant_name = match file_name.rsplit_once('.') {
+        Some((stem, extension)) => format!("{stem}.{profile}.{extension}"),
+        None => format!("{file_name}.{profile}"),
+    };
+    Some(
+        AssetPath::from(path.path().with_file_name(variant_name))
+            .with_source(path.source().clone_owned()),
+    )
+}
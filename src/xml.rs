@@ -1,8 +1,9 @@
+use crate::loader::AssetCheck;
+use crate::{DataAsset, DataAssetError, ToDefinition, ValidateAsset, ValidationMode};
 use bevy_app::{App, Plugin};
 use bevy_asset::io::Reader;
-use bevy_asset::{Asset, AssetApp, AssetLoader, LoadContext};
+use bevy_asset::{AssetApp, AssetLoader, LoadContext};
 use quick_xml::de::from_str;
-use std::marker::PhantomData;
 use std::str::from_utf8;
 use thiserror::Error;
 
@@ -10,39 +11,84 @@ use thiserror::Error;
 /// Read the [`quick_xml` docs](https://docs.rs/quick-xml/latest/quick_xml/de/) for tips on deserialization.
 pub struct XmlAssetPlugin<A> {
     extensions: Vec<&'static str>,
-    _marker: PhantomData<A>,
+    check: AssetCheck<A>,
+    changes: Option<fn(&mut App)>,
 }
 
 impl<A> Plugin for XmlAssetPlugin<A>
 where
-    for<'de> A: serde::Deserialize<'de> + Asset,
+    A: DataAsset,
 {
     fn build(&self, app: &mut App) {
-        app.init_asset::<A>()
-            .register_asset_loader(XmlAssetLoader::<A> {
-                extensions: self.extensions.clone(),
-                _marker: PhantomData,
-            });
+        crate::data_asset::init_data_asset::<A>(app);
+        self.register_loader(app);
+        if let Some(register) = self.changes {
+            register(app);
+        }
     }
 }
 
 impl<A> XmlAssetPlugin<A>
 where
-    for<'de> A: serde::Deserialize<'de> + Asset,
+    A: DataAsset,
 {
     /// Create a new plugin that will load assets from files with the given extensions.
     pub fn new(extensions: &[&'static str]) -> Self {
         Self {
             extensions: extensions.to_owned(),
-            _marker: PhantomData,
+            check: AssetCheck::default(),
+            changes: None,
         }
     }
+
+    /// Check loaded assets with their [`ValidateAsset`] implementation and fail or warn for
+    /// invalid assets depending on `mode`.
+    pub fn with_asset_validation(mut self, mode: ValidationMode) -> Self
+    where
+        A: ValidateAsset,
+    {
+        self.check = AssetCheck::new(mode);
+        self
+    }
+
+    /// Send a [`DataAssetChanged`](crate::change::DataAssetChanged) message with the changed
+    /// fields whenever an asset of type `A` is modified, e.g. by hot reloading.
+    pub fn with_change_events(mut self) -> Self
+    where
+        A: ToDefinition,
+    {
+        self.changes = Some(crate::change::register::<A>);
+        self
+    }
+
+    pub(crate) fn with_check(mut self, check: AssetCheck<A>) -> Self {
+        self.check = check;
+        self
+    }
+
+    /// Register the loader of this plugin without initializing the asset
+    pub(crate) fn register_loader(&self, app: &mut App) {
+        app.register_asset_loader(XmlAssetLoader::<A> {
+            extensions: self.extensions.clone(),
+            check: self.check,
+        });
+    }
 }
 
 /// Loads your asset type `A` from xml files
 pub struct XmlAssetLoader<A> {
     extensions: Vec<&'static str>,
-    _marker: PhantomData<A>,
+    check: AssetCheck<A>,
+}
+
+impl<A> XmlAssetLoader<A> {
+    /// Create a loader without extensions for other loaders delegating to it
+    pub(crate) fn new(check: AssetCheck<A>) -> Self {
+        Self {
+            extensions: vec![],
+            check,
+        }
+    }
 }
 
 /// Possible errors that can be produced by [`XmlAssetLoader`]
@@ -58,11 +104,14 @@ pub enum XmlLoaderError {
     /// A [XML Error](quick_xml::DeError)
     #[error("Could not parse XML: {0}")]
     XmlError(#[from] quick_xml::DeError),
+    /// An error of the format independent loading steps
+    #[error(transparent)]
+    DataAssetError(#[from] DataAssetError),
 }
 
 impl<A> AssetLoader for XmlAssetLoader<A>
 where
-    for<'de> A: serde::Deserialize<'de> + Asset,
+    A: DataAsset,
 {
     type Asset = A;
     type Settings = ();
@@ -72,11 +121,15 @@ where
         &self,
         reader: &mut dyn Reader,
         _settings: &(),
-        _load_context: &mut LoadContext<'_>,
+        load_context: &mut LoadContext<'_>,
     ) -> Result<Self::Asset, Self::Error> {
         let mut bytes = Vec::new();
         reader.read_to_end(&mut bytes).await?;
-        let asset = from_str::<A>(from_utf8(&bytes)?)?;
+        let definition = from_str::<A::Definition>(from_utf8(&bytes)?)?;
+        let asset = A::from_definition(definition, load_context)
+            .await
+            .map_err(DataAssetError::Definition)?;
+        self.check.check(&asset, load_context)?;
         Ok(asset)
     }
 
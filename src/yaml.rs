@@ -1,49 +1,122 @@
+use crate::data::DataFormat;
+use crate::loader::{
+    AssetCheck, DocumentSettings, LoaderOptions, loader_options_methods, text_options_methods,
+};
+#[cfg(feature = "schema")]
+use crate::schema::{RegisterSchema, SchemaFormat, register_schema};
+use crate::value::Value;
+use crate::{DataAsset, DataAssetError};
 use bevy_app::{App, Plugin};
 use bevy_asset::io::Reader;
-use bevy_asset::{Asset, AssetApp, AssetLoader, LoadContext};
-use serde_yaml::from_slice;
-use std::marker::PhantomData;
+use bevy_asset::{Asset, AssetApp, AssetLoader, Handle, LoadContext};
+use bevy_reflect::TypePath;
+use serde::Deserialize;
+use serde_yaml::{Deserializer, from_slice};
+use std::collections::HashSet;
 use thiserror::Error;
 
 /// Plugin to load your asset type `A` from yaml files.
 pub struct YamlAssetPlugin<A> {
     extensions: Vec<&'static str>,
-    _marker: PhantomData<A>,
+    options: LoaderOptions<A>,
+    #[cfg(feature = "schema")]
+    schema: Option<RegisterSchema>,
 }
 
 impl<A> Plugin for YamlAssetPlugin<A>
 where
-    for<'de> A: serde::Deserialize<'de> + Asset,
+    A: DataAsset,
 {
     fn build(&self, app: &mut App) {
-        app.init_asset::<A>()
-            .register_asset_loader(YamlAssetLoader::<A> {
-                extensions: self.extensions.clone(),
-                _marker: PhantomData,
-            });
+        crate::data_asset::init_data_asset::<A>(app);
+        self.register_loader(app);
     }
 }
 
 impl<A> YamlAssetPlugin<A>
 where
-    for<'de> A: serde::Deserialize<'de> + Asset,
+    A: DataAsset,
 {
     /// Create a new plugin that will load assets from files with the given extensions.
     pub fn new(extensions: &[&'static str]) -> Self {
         Self {
             extensions: extensions.to_owned(),
-            _marker: PhantomData,
+            options: LoaderOptions::default(),
+            #[cfg(feature = "schema")]
+            schema: None,
+        }
+    }
+
+    pub(crate) fn with_check(mut self, check: AssetCheck<A>) -> Self {
+        self.options.check = check;
+        self
+    }
+
+    /// Register the loader of this plugin without initializing the asset
+    pub(crate) fn register_loader(&self, app: &mut App) {
+        let options = self.options.build(app);
+        app.register_asset_loader(YamlAssetLoader::<A> {
+            extensions: self.extensions.clone(),
+            options,
+        });
+        #[cfg(feature = "schema")]
+        if let Some(register_schema) = self.schema {
+            register_schema(app, SchemaFormat::Yaml, &self.extensions);
         }
     }
+
+    loader_options_methods!();
+    text_options_methods!();
+}
+
+#[cfg(feature = "validation")]
+impl<A> YamlAssetPlugin<A>
+where
+    A: DataAsset,
+{
+    /// Validate loaded documents against a JSON Schema before deserializing them.
+    ///
+    /// See [`DocumentSettings::schema`](crate::DocumentSettings::schema) for where the schema
+    /// comes from. All places in a document not matching the schema are reported in one error.
+    pub fn with_schema_validation(mut self) -> Self {
+        self.options.validate = true;
+        self
+    }
+}
+
+#[cfg(feature = "schema")]
+impl<A> YamlAssetPlugin<A>
+where
+    A: DataAsset,
+    A::Definition: schemars::JsonSchema,
+{
+    /// Register the JSON Schema of the asset definition for the extensions of this plugin
+    ///
+    /// The schemas of all registered types can be written to disk with
+    /// [`AssetSchemas::write`](crate::schema::AssetSchemas::write).
+    pub fn with_schema(mut self) -> Self {
+        self.schema = Some(register_schema::<A>);
+        self
+    }
 }
 
 /// Loads your asset type `A` from yaml files
 pub struct YamlAssetLoader<A> {
     extensions: Vec<&'static str>,
-    _marker: PhantomData<A>,
+    options: LoaderOptions<A>,
 }
 
-/// Possible errors that can be produced by [`YamlAssetLoader`]
+impl<A> YamlAssetLoader<A> {
+    /// Create a loader without extensions for other loaders delegating to it
+    pub(crate) fn from_options(options: LoaderOptions<A>) -> Self {
+        Self {
+            extensions: vec![],
+            options,
+        }
+    }
+}
+
+/// Possible errors that can be produced by [`YamlAssetLoader`] or [`YamlDocumentsAssetLoader`]
 #[non_exhaustive]
 #[derive(Debug, Error)]
 pub enum YamlLoaderError {
@@ -53,26 +126,209 @@ pub enum YamlLoaderError {
     /// A [YAML Error](serde_yaml::Error)
     #[error("Could not parse YAML: {0}")]
     YamlError(#[from] serde_yaml::Error),
+    /// A document is missing the field configured to label it
+    #[error("Document {index} has no string or number field `{field}` to use as label")]
+    MissingLabel {
+        /// Index of the document in the file
+        index: usize,
+        /// The configured label field
+        field: &'static str,
+    },
+    /// Two documents resolved to the same label
+    #[error("Multiple documents are labeled `{0}`")]
+    DuplicateLabel(String),
+    /// An error of the format independent loading steps
+    #[error(transparent)]
+    DataAssetError(#[from] DataAssetError),
 }
 
 impl<A> AssetLoader for YamlAssetLoader<A>
 where
-    for<'de> A: serde::Deserialize<'de> + Asset,
+    A: DataAsset,
 {
     type Asset = A;
-    type Settings = ();
+    type Settings = DocumentSettings;
+    type Error = YamlLoaderError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        settings: &DocumentSettings,
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        if self.options.uses_value() {
+            let document = from_slice::<Value>(&bytes)?;
+            return Ok(self
+                .options
+                .load_document(document, DataFormat::Yaml, settings, load_context)
+                .await?);
+        }
+        let definition = from_slice::<A::Definition>(&bytes)?;
+        Ok(self.options.create_asset(definition, load_context).await?)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &self.extensions
+    }
+}
+
+/// Plugin to load your asset type `A` from yaml files containing multiple `---` separated documents.
+///
+/// All documents of a file are collected in a [`LoadedYamlDocuments`] asset. Each document is also
+/// added as a labeled sub-asset of type `A`. Labels are the index of the document in the file
+/// (`enemies.yaml#0`) or, if configured with [`YamlDocumentsAssetPlugin::with_label_field`], the
+/// value of a field of the document (`enemies.yaml#goblin`).
+///
+/// Every document runs through the same loading steps as the documents of the
+/// [`YamlAssetPlugin`]. Each document is created in a load context of its own, so its
+/// dependencies belong to the document instead of the whole file.
+pub struct YamlDocumentsAssetPlugin<A> {
+    extensions: Vec<&'static str>,
+    label_field: Option<&'static str>,
+    options: LoaderOptions<A>,
+}
+
+impl<A> Plugin for YamlDocumentsAssetPlugin<A>
+where
+    A: DataAsset,
+{
+    fn build(&self, app: &mut App) {
+        crate::data_asset::init_data_asset::<A>(app);
+        let options = self.options.build(app);
+        app.init_asset::<LoadedYamlDocuments<A>>()
+            .register_asset_loader(YamlDocumentsAssetLoader::<A> {
+                extensions: self.extensions.clone(),
+                label_field: self.label_field,
+                options,
+            });
+    }
+}
+
+impl<A> YamlDocumentsAssetPlugin<A>
+where
+    A: DataAsset,
+{
+    /// Create a new plugin that will load assets from files with the given extensions.
+    pub fn new(extensions: &[&'static str]) -> Self {
+        Self {
+            extensions: extensions.to_owned(),
+            label_field: None,
+            options: LoaderOptions::default(),
+        }
+    }
+
+    /// Label the documents by the value of the given field instead of their index.
+    ///
+    /// Every document needs a string or number value for the field and the values have to be unique
+    /// within the file.
+    ///
+    /// ```no_run
+    /// # use bevy::prelude::*;
+    /// # use bevy_common_assets::yaml::YamlDocumentsAssetPlugin;
+    /// App::new()
+    ///     .add_plugins(YamlDocumentsAssetPlugin::<Enemy>::new(&["enemies.yaml"]).with_label_field("name"))
+    ///     .add_systems(Startup, |asset_server: Res<AssetServer>| {
+    ///         let goblin: Handle<Enemy> = asset_server.load("enemies.yaml#goblin");
+    ///     });
+    /// # #[derive(serde::Deserialize, Asset, TypePath, Debug)]
+    /// # struct Enemy {
+    /// #     name: String,
+    /// #     health: u32,
+    /// # }
+    /// ```
+    pub fn with_label_field(mut self, field: &'static str) -> Self {
+        self.label_field = Some(field);
+        self
+    }
+
+    loader_options_methods!();
+    text_options_methods!();
+}
+
+#[cfg(feature = "validation")]
+impl<A> YamlDocumentsAssetPlugin<A>
+where
+    A: DataAsset,
+{
+    /// Validate every document against a JSON Schema before deserializing it.
+    ///
+    /// See [`DocumentSettings::schema`](crate::DocumentSettings::schema) for where the schema
+    /// comes from.
+    pub fn with_schema_validation(mut self) -> Self {
+        self.options.validate = true;
+        self
+    }
+}
+
+/// Loads all documents of yaml files as your asset type `A`
+pub struct YamlDocumentsAssetLoader<A> {
+    extensions: Vec<&'static str>,
+    label_field: Option<&'static str>,
+    options: LoaderOptions<A>,
+}
+
+/// Asset representing a loaded multi-document yaml file
+///
+/// The documents are labeled sub-assets of this asset.
+#[derive(TypePath, Asset)]
+pub struct LoadedYamlDocuments<A>
+where
+    A: Asset,
+{
+    /// Handles to the documents of the file in the order they appear in
+    pub documents: Vec<Handle<A>>,
+}
+
+impl<A> AssetLoader for YamlDocumentsAssetLoader<A>
+where
+    A: DataAsset,
+{
+    type Asset = LoadedYamlDocuments<A>;
+    type Settings = DocumentSettings;
     type Error = YamlLoaderError;
 
     async fn load(
         &self,
         reader: &mut dyn Reader,
-        _settings: &(),
-        _load_context: &mut LoadContext<'_>,
+        settings: &DocumentSettings,
+        load_context: &mut LoadContext<'_>,
     ) -> Result<Self::Asset, Self::Error> {
         let mut bytes = Vec::new();
         reader.read_to_end(&mut bytes).await?;
-        let asset = from_slice::<A>(&bytes)?;
-        Ok(asset)
+        // The yaml deserializer can not be held across awaits
+        let values = Deserializer::from_slice(&bytes)
+            .map(Value::deserialize)
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut documents = vec![];
+        let mut labels = HashSet::new();
+        for (index, document) in values.into_iter().enumerate() {
+            let document = self
+                .options
+                .prepare_document(document, DataFormat::Yaml, settings, load_context)
+                .await?;
+            let label = match self.label_field {
+                None => index.to_string(),
+                Some(field) => match document.get(field).map(Value::unnamed) {
+                    Some(Value::String(label)) => label.clone(),
+                    Some(Value::Integer(label)) => label.to_string(),
+                    Some(Value::Float(label)) => label.to_string(),
+                    _ => return Err(YamlLoaderError::MissingLabel { index, field }),
+                },
+            };
+            if !labels.insert(label.clone()) {
+                return Err(YamlLoaderError::DuplicateLabel(label));
+            }
+            let mut document_context = load_context.begin_labeled_asset();
+            let asset = self
+                .options
+                .load_migrated(document, &mut document_context)
+                .await?;
+            documents
+                .push(load_context.add_loaded_labeled_asset(label, document_context.finish(asset)));
+        }
+        Ok(LoadedYamlDocuments { documents })
     }
 
     fn extensions(&self) -> &[&str] {